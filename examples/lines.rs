@@ -5,6 +5,7 @@ use std::path::Path;
 
 use harrow::{FileRef, ViewRef};
 
+#[derive(Debug)]
 struct Lines {
     data: FileRef,
     offsets: Vec<usize>,
@@ -28,10 +29,10 @@ impl Lines {
         offsets.push(last_offset);
         for (offset, byte) in data.iter()?.enumerate() {
             // Is the byte the newline character?
-            if byte == '\n' as u8 {
+            if byte == b'\n' {
                 // Check if the line is valid UTF-8.
                 std::str::from_utf8(&data.view_range(last_offset..offset)?)
-                    .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+                    .map_err(io::Error::other)?;
 
                 last_offset = offset + 1;
                 offsets.push(last_offset);
@@ -3,11 +3,13 @@ use std::path::Path;
 
 use harrow::FileMut;
 
+#[derive(Debug)]
 struct StrPtr {
     start: usize,
     len: usize,
 }
 
+#[derive(Debug)]
 struct StringStorage {
     data: FileMut,
     len: usize,
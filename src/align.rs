@@ -1,17 +1,29 @@
+use std::io;
+
 use once_cell::sync::Lazy;
 
 use crate::os;
 
-pub static ALIGNMENT: Lazy<usize> = Lazy::new(|| os::get_alignment());
+pub static ALIGNMENT: Lazy<usize> = Lazy::new(os::get_alignment);
 
-pub fn align_add(len: usize) -> usize {
-    let alignment = *ALIGNMENT;
+// Returns `None` if the aligned length does not fit into `usize`.
+pub fn align_add(len: usize, alignment: usize) -> Option<usize> {
     let offset = len % alignment;
-    len + if offset > 0 { alignment - offset } else { 0 }
+    len.checked_add(if offset > 0 { alignment - offset } else { 0 })
+}
+
+pub fn try_align_add(len: usize, alignment: usize) -> io::Result<usize> {
+    align_add(len, alignment)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "length too large to align"))
 }
 
-pub fn align_sub(len: usize) -> usize {
-    let alignment = *ALIGNMENT;
+// Returns the largest aligned length if the aligned length does not fit into
+// `usize`.
+pub fn saturating_align_add(len: usize, alignment: usize) -> usize {
+    align_add(len, alignment).unwrap_or_else(|| align_sub(usize::MAX, alignment))
+}
+
+pub fn align_sub(len: usize, alignment: usize) -> usize {
     let factor = len / alignment;
     factor * alignment
 }
@@ -23,14 +35,35 @@ mod tests {
     #[test]
     fn alignment() {
         let alignment = os::get_alignment();
-        assert_eq!(align_add(1), alignment);
-        assert_eq!(align_add(0), 0);
-        assert_eq!(align_add(alignment), alignment);
-        assert_eq!(align_add(alignment + 1), 2 * alignment);
-
-        assert_eq!(align_sub(1), 0);
-        assert_eq!(align_sub(0), 0);
-        assert_eq!(align_sub(alignment), alignment);
-        assert_eq!(align_sub(alignment + 1), alignment);
+        assert_eq!(align_add(1, alignment), Some(alignment));
+        assert_eq!(align_add(0, alignment), Some(0));
+        assert_eq!(align_add(alignment, alignment), Some(alignment));
+        assert_eq!(align_add(alignment + 1, alignment), Some(2 * alignment));
+
+        assert_eq!(align_sub(1, alignment), 0);
+        assert_eq!(align_sub(0, alignment), 0);
+        assert_eq!(align_sub(alignment, alignment), alignment);
+        assert_eq!(align_sub(alignment + 1, alignment), alignment);
+    }
+
+    #[test]
+    fn alignment_overflow() {
+        let alignment = os::get_alignment();
+        let largest = align_sub(usize::MAX, alignment);
+
+        assert_eq!(align_add(usize::MAX, alignment), None);
+        assert_eq!(align_add(largest + 1, alignment), None);
+        // This one is already aligned.
+        assert_eq!(
+            align_add(usize::MAX - alignment + 1, alignment),
+            Some(largest)
+        );
+        assert_eq!(align_add(largest - 1, alignment), Some(largest));
+
+        let err = try_align_add(usize::MAX, alignment).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        assert_eq!(saturating_align_add(usize::MAX, alignment), largest);
+        assert_eq!(saturating_align_add(1, alignment), alignment);
     }
 }
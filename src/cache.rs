@@ -1,46 +1,135 @@
-use std::collections::VecDeque;
+use std::borrow::Borrow;
+use std::collections::{btree_map, BTreeMap};
+use std::fmt;
 use std::io;
 use std::num::NonZeroUsize;
 use std::ops::{Deref, DerefMut};
+use std::ptr::NonNull;
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
-use std::sync::{Mutex, RwLock};
+use std::sync::{Arc, Mutex, RwLock};
 
-use crate::os;
+use arc_swap::ArcSwap;
+
+use crate::options::EvictionPolicy;
+use crate::source::Block;
 
 pub struct Cache {
     // Blocks available for acquiring, no reference to this memory exists in the
     // outside world. This has a limited capacity.
-    available: RwLock<VecDeque<CachedBlock>>,
-    // Blocks that are currently lent to the outside world. This vector must
+    available: RwLock<Blocks>,
+    // Blocks that are currently lent to the outside world. This collection must
     // hold all cached blocks until all references are dropped. In that case,
     // the cached block goes into available ones.
-    lent: RwLock<Vec<CachedBlock>>,
-    // A block that is current lent as ViewMut. There can be only one at a time.
-    exclusive: Mutex<Option<CachedBlock>>,
+    //
+    // Invariant: a block has non-zero reference count if and only if it is in
+    // this collection. The reference count is increased from zero and the
+    // block is removed only while holding the lock.
+    lent: Mutex<Blocks>,
+    // A snapshot of the lent blocks, updated on every change of `lent`. It is
+    // used for finding a lent block without locking. The snapshot may be
+    // stale, but a block that is no longer lent has zero reference count,
+    // which is detected (see `CachedBlock::try_view_ref`).
+    lent_snapshot: ArcSwap<Snapshot>,
+    // Blocks that are currently lent as ViewMut, together with the ranges of
+    // the views. The ranges never overlap each other nor any lent block.
+    //
+    // All views except those created from `lent_snapshot` are created while
+    // holding this lock, so the check for overlapping ranges and the creation
+    // of the view are atomic. The lock is always acquired before the locks of
+    // other collections.
+    exclusive: Mutex<Vec<ExclusiveBlock>>,
+    // Number of active ViewMuts. As long as it is non-zero, shared views are
+    // not created from `lent_snapshot`, because a lent block may overlap a
+    // mutable view.
+    exclusive_views: AtomicUsize,
     // Current size of the available blocks.
     len: AtomicUsize,
-    // Capacity for the available blocks.
-    capacity: NonZeroUsize,
+    // Capacity for the available blocks. It is never zero.
+    capacity: AtomicUsize,
+    // Moving average of the lengths of the recent requests.
+    request_len: AtomicUsize,
+    // Incremented whenever cached blocks are invalidated, so that stale
+    // pointers into them can be detected.
+    generation: AtomicUsize,
+    // Statistics of the cache behavior. They are only informative, so they
+    // use relaxed ordering.
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+    evictions: AtomicUsize,
+    // Which available block is discarded when the capacity is exceeded.
+    policy: EvictionPolicy,
+    // Number of blocks fetched so far, used for ordering the blocks by their
+    // insertion for the FIFO policy.
+    fetched: AtomicUsize,
 }
 
 impl Cache {
     pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_policy(capacity, EvictionPolicy::default())
+    }
+
+    pub fn with_policy(capacity: usize, policy: EvictionPolicy) -> Self {
         Self {
-            available: RwLock::new(VecDeque::with_capacity(capacity)),
-            lent: RwLock::new(Vec::new()),
-            exclusive: Mutex::new(None),
+            available: RwLock::new(Blocks::default()),
+            lent: Mutex::new(Blocks::default()),
+            lent_snapshot: ArcSwap::from_pointee(Snapshot::default()),
+            exclusive: Mutex::new(Vec::new()),
+            exclusive_views: AtomicUsize::new(0),
             len: AtomicUsize::new(0),
-            capacity: NonZeroUsize::new(capacity).expect("capacity must be non-zero"),
+            capacity: AtomicUsize::new(
+                NonZeroUsize::new(capacity)
+                    .expect("capacity must be non-zero")
+                    .get(),
+            ),
+            request_len: AtomicUsize::new(0),
+            generation: AtomicUsize::new(0),
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+            evictions: AtomicUsize::new(0),
+            policy,
+            fetched: AtomicUsize::new(0),
         }
     }
 
+    pub fn record_request(&self, len: usize) {
+        // Exponential moving average with the weight of 1/8 for the new value.
+        // The first request initializes the average.
+        let _ = self
+            .request_len
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |avg| {
+                if avg == 0 {
+                    Some(len)
+                } else if len >= avg {
+                    Some(avg + (len - avg) / 8)
+                } else {
+                    Some(avg - (avg - len) / 8)
+                }
+            });
+    }
+
+    pub fn average_request(&self) -> usize {
+        self.request_len.load(Ordering::Relaxed)
+    }
+
+    // Returns `true` if any view created from the cached blocks is still
+    // active.
+    pub fn is_viewed(&self) -> bool {
+        self.exclusive_views.load(Ordering::SeqCst) > 0 || self.lent.lock().unwrap().len() > 0
+    }
+
+    // Returns `true` if any mutable view is still active.
+    pub fn is_viewed_mut(&self) -> bool {
+        self.exclusive_views.load(Ordering::SeqCst) > 0
+    }
+
     #[cfg(test)]
     pub fn available(&self) -> usize {
         self.available.read().unwrap().len()
     }
 
+    #[cfg(test)]
     pub fn lent(&self) -> usize {
-        self.lent.read().unwrap().len()
+        self.lent.lock().unwrap().len()
     }
 
     #[cfg(test)]
@@ -49,161 +138,497 @@ impl Cache {
             .available
             .read()
             .unwrap()
+            .hits(off, len)
+            .next()
+            .is_some();
+        in_available || self.lent.lock().unwrap().hits(off, len).next().is_some()
+    }
+
+    pub fn dirty_bytes(&self) -> usize {
+        let exclusive = self.exclusive.lock().unwrap();
+        let available = self.available.read().unwrap();
+        let lent = self.lent.lock().unwrap();
+
+        available
             .iter()
-            .any(|block| block.is_hit(off, len));
+            .chain(lent.iter())
+            .chain(exclusive.iter().map(|exclusive| &exclusive.block))
+            .filter(|block| block.is_dirty())
+            .map(|block| block.block().len())
+            .sum()
+    }
 
-        if in_available {
-            return true;
+    pub fn generation(&self) -> usize {
+        self.generation.load(Ordering::SeqCst)
+    }
+
+    pub fn invalidate(&self, off: usize, len: usize) -> io::Result<()> {
+        let mut available = self.available.write().unwrap();
+        self.generation.fetch_add(1, Ordering::SeqCst);
+
+        // Flush the dirty blocks before discarding them. The first flushing
+        // error is reported, but all the blocks are discarded regardless.
+        let mut result = Ok(());
+        while let Some(block) = available.overlapping(off, len).next().cloned() {
+            available.remove(&block);
+
+            if block.is_dirty() {
+                let flushed = block.block().flush();
+                if result.is_ok() {
+                    result = flushed;
+                }
+            }
         }
 
-        self.lent
-            .read()
-            .unwrap()
+        self.len.store(available.len(), Ordering::SeqCst);
+        result
+    }
+
+    // Flushes all dirty blocks. The order of the blocks is not changed.
+    pub fn flush(&self) -> io::Result<()> {
+        let exclusive = self.exclusive.lock().unwrap();
+        let available = self.available.read().unwrap();
+        let lent = self.lent.lock().unwrap();
+
+        available
             .iter()
-            .any(|block| block.is_hit(off, len))
+            .chain(lent.iter())
+            .chain(exclusive.iter().map(|exclusive| &exclusive.block))
+            .try_for_each(|block| block.flush_if_dirty())
+    }
+
+    // Flushes the dirty blocks that overlap the range. The order of the blocks
+    // is not changed.
+    pub fn flush_range(&self, off: usize, len: usize) -> io::Result<()> {
+        let exclusive = self.exclusive.lock().unwrap();
+        let available = self.available.read().unwrap();
+        let lent = self.lent.lock().unwrap();
+
+        available
+            .overlapping(off, len)
+            .chain(lent.overlapping(off, len))
+            .chain(
+                exclusive
+                    .iter()
+                    .map(|exclusive| &exclusive.block)
+                    .filter(|block| block.is_overlapping(off, len)),
+            )
+            .try_for_each(|block| block.flush_if_dirty())
+    }
+
+    // Drops the pages of the range from the memory of the process in all
+    // blocks that overlap the range. The blocks stay mapped.
+    pub fn release_pages(&self, off: usize, len: usize) -> io::Result<()> {
+        let exclusive = self.exclusive.lock().unwrap();
+        let available = self.available.read().unwrap();
+        let lent = self.lent.lock().unwrap();
+
+        available
+            .overlapping(off, len)
+            .chain(lent.overlapping(off, len))
+            .chain(
+                exclusive
+                    .iter()
+                    .map(|exclusive| &exclusive.block)
+                    .filter(|block| block.is_overlapping(off, len)),
+            )
+            .try_for_each(|block| block.view.release(off, len))
+    }
+
+    // Advises the operating system that the block that holds the view will be
+    // accessed soon.
+    pub fn advise_willneed(&self, view: &ViewRef<'_>) -> io::Result<()> {
+        let lent = self.lent.lock().unwrap();
+
+        lent.get(view.as_base_ptr())
+            .expect("view must be lent from this cache")
+            .view
+            .advise_willneed()
+    }
+
+    // Pins the block that holds the view. Fails if pinning the block would
+    // leave no room in the cache for other blocks.
+    pub fn pin(&self, view: &ViewRef<'_>) -> io::Result<()> {
+        let available = self.available.read().unwrap();
+        let lent = self.lent.lock().unwrap();
+
+        let block = lent
+            .get(view.as_base_ptr())
+            .expect("view must be lent from this cache");
+
+        if block.is_pinned() {
+            return Ok(());
+        }
+
+        let pinned = available
+            .iter()
+            .chain(lent.iter())
+            .filter(|block| block.is_pinned())
+            .count();
+
+        if pinned + 1 >= self.capacity() {
+            return Err(io::Error::other(
+                "cache capacity would be exhausted by pinned blocks",
+            ));
+        }
+
+        block.pinned.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+
+    // Unpins all blocks that overlap the range, they are evicted as usual
+    // again.
+    pub fn unpin(&self, off: usize, len: usize) {
+        let exclusive = self.exclusive.lock().unwrap();
+        let available = self.available.read().unwrap();
+        let lent = self.lent.lock().unwrap();
+
+        available
+            .overlapping(off, len)
+            .chain(lent.overlapping(off, len))
+            .chain(
+                exclusive
+                    .iter()
+                    .map(|exclusive| &exclusive.block)
+                    .filter(|block| block.is_overlapping(off, len)),
+            )
+            .for_each(|block| block.pinned.store(false, Ordering::SeqCst));
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn policy(&self) -> EvictionPolicy {
+        self.policy
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity.load(Ordering::SeqCst)
+    }
+
+    pub fn set_capacity(&self, capacity: NonZeroUsize) -> io::Result<()> {
+        let mut available = self.available.write().unwrap();
+        self.capacity.store(capacity.get(), Ordering::SeqCst);
+        self.evict_to(&mut available, capacity.get()).map(|_| ())
+    }
+
+    pub fn trim(&self, target: usize) -> io::Result<usize> {
+        let mut available = self.available.write().unwrap();
+        self.evict_to(&mut available, target)
+    }
+
+    // Flushes the dirty blocks and unmaps all blocks, returning the first
+    // error. The cache is empty afterwards.
+    pub fn close(&mut self) -> io::Result<()> {
+        self.lent_snapshot.store(Arc::new(Snapshot::default()));
+        self.len.store(0, Ordering::SeqCst);
+
+        let available = std::mem::take(self.available.get_mut().unwrap()).into_iter();
+        let lent = std::mem::take(self.lent.get_mut().unwrap()).into_iter();
+        let exclusive = self
+            .exclusive
+            .get_mut()
+            .unwrap()
+            .drain(..)
+            .map(|exclusive| exclusive.block);
+        self.exclusive_views.store(0, Ordering::SeqCst);
+
+        let mut result = Ok(());
+        for block in available.chain(lent).chain(exclusive) {
+            let closed = block
+                .flush_if_dirty()
+                .and_then(|_| match Arc::try_unwrap(block) {
+                    Ok(block) => block.view.unmap(),
+                    // Someone still holds the block (which should not happen
+                    // given the exclusive reference), so it is unmapped when
+                    // dropped.
+                    Err(_) => Ok(()),
+                });
+
+            if result.is_ok() {
+                result = closed;
+            }
+        }
+
+        result
     }
 
     pub fn take(&self, off: usize, len: usize) -> Take<'_> {
-        // First, search in available blocks. It is more probable that the
-        // request is in available blocks rather than in lent blocks, because
-        // the latter means requesting the same data multiple times.
+        // First, search in lent blocks without locking. This is the hot path
+        // for reading the same region repeatedly (possibly from multiple
+        // threads), so it uses only atomic operations.
+        if self.exclusive_views.load(Ordering::SeqCst) == 0 {
+            let view = self
+                .lent_snapshot
+                .load()
+                .hits(off, len)
+                .find_map(|block| block.try_view_ref(self, off, len));
+
+            // A mutable view may have been created since the check above. If
+            // it was created after the view, it saw the block as lent and
+            // failed. Otherwise, the view may overlap it, so we fall back to
+            // the slow path (the view is dropped and restored normally).
+            if let Some(view) = view {
+                if self.exclusive_views.load(Ordering::SeqCst) == 0 {
+                    // We found the block in lent blocks, that is, it is
+                    // already lent as at least one other ViewRef. We just
+                    // created a new reference to it.
+                    self.hits.fetch_add(1, Ordering::Relaxed);
+                    return Take {
+                        cache: self,
+                        view: Some(view),
+                        overlapping: false,
+                        off,
+                        len,
+                    };
+                }
+            }
+        }
+
+        let exclusive = self.exclusive.lock().unwrap();
+
+        if is_exclusive(&exclusive, off, len) {
+            return Take {
+                cache: self,
+                view: None,
+                overlapping: true,
+                off,
+                len,
+            };
+        }
+
+        // Once there is an exclusive view, lent blocks are not searched
+        // without locking, so they need to be searched here.
+        let view = self
+            .lent
+            .lock()
+            .unwrap()
+            .hits(off, len)
+            .find_map(|block| block.try_view_ref(self, off, len));
+
+        if let Some(view) = view {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Take {
+                cache: self,
+                view: Some(view),
+                overlapping: false,
+                off,
+                len,
+            };
+        }
 
         if let Some(block) = self.acquire_available(off, len) {
-            // Fe were able to acquire a block from available blocks. Now we
-            // create the first reference to it and idd the block to lent
+            // We were able to acquire a block from available blocks. Now we
+            // create the first reference to it and add the block to lent
             // blocks.
-            let view = block.view_ref(self, off, len);
-            self.lent.write().unwrap().push(block);
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            let view = self.add_lent(block, off, len);
             Take {
                 cache: self,
                 view: Some(view),
+                overlapping: false,
                 off,
                 len,
             }
         } else {
-            self.lent
-                .read()
-                .unwrap()
-                .iter()
-                .rev()
-                .find(|view| view.is_hit(off, len))
-                .map(|block| {
-                    // We found the block in lent blocks, that is, it is already
-                    // lent as at least one other ViewRef. We just create a new
-                    // reference to it.
-                    let view = block.view_ref(self, off, len);
-                    Take {
-                        cache: self,
-                        view: Some(view),
-                        off,
-                        len,
-                    }
-                })
-                // There is no satisfying block in the lent either, so we return
-                // an empty Take.
-                .unwrap_or(Take {
-                    cache: self,
-                    view: None,
-                    off,
-                    len,
-                })
+            // There is no satisfying block, so we return an empty Take.
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            Take {
+                cache: self,
+                view: None,
+                overlapping: false,
+                off,
+                len,
+            }
         }
     }
 
-    pub fn take_mut(&mut self, off: usize, len: usize) -> TakeMut<'_> {
-        // No references are living in the outside world - everything is held in
-        // the cache.
-        assert!(self.lent() == 0);
+    pub fn take_mut(&self, off: usize, len: usize) -> io::Result<TakeMut<'_>> {
+        let mut exclusive = self.exclusive.lock().unwrap();
+        self.check_exclusive(&exclusive, off, len)?;
 
-        // We take exclusive reference due to API enforcements, but in the body
-        // we need shared reference instead.
-        let cache = &*self;
+        // Prefer a block that is already lent as ViewMut for a different
+        // range, then search in the available blocks.
+        let found = exclusive
+            .iter()
+            .position(|exclusive| exclusive.block.is_hit(off, len));
+
+        let view = if let Some(index) = found {
+            Some(exclusive[index].add_view(self, off, len))
+        } else if let Some(block) = self.acquire_available(off, len) {
+            // We were able to find a block in available, we move it to the
+            // exclusive blocks and return a mutable reference.
+            let mut block = ExclusiveBlock::new(block);
+            let view = block.add_view(self, off, len);
+            exclusive.push(block);
+            Some(view)
+        } else {
+            None
+        };
 
-        // Search in the available blocks only, the lent collection is empty.
-        self.acquire_available(off, len)
-            .map(|block| {
-                // We were able to find a block in available, we assign it to
-                // the exclusive field and return the only mutable reference.
-                let view = block.view_mut(cache, off, len);
-                *self.exclusive.lock().unwrap() = Some(block);
+        let take = match view {
+            Some(view) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                self.exclusive_views.fetch_add(1, Ordering::SeqCst);
                 TakeMut {
-                    cache,
+                    cache: self,
                     view: Some(view),
                     off,
                     len,
                 }
-            })
-            // There is no satisfying block in the lent, so we return an empty
-            // TakeMut.
-            .unwrap_or(TakeMut {
-                cache,
-                view: None,
-                off,
-                len,
-            })
+            }
+            // There is no satisfying block, so we return an empty TakeMut.
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                TakeMut {
+                    cache: self,
+                    view: None,
+                    off,
+                    len,
+                }
+            }
+        };
+
+        Ok(take)
     }
 
-    fn add_fetched_ref(&self, view: os::RawView, off: usize, len: usize) -> ViewRef<'_> {
-        // New block must have been fetched, we store it and return a reference.
-        let block = CachedBlock::new(view);
-        let view = block.view_ref(self, off, len);
-        self.lent.write().unwrap().push(block);
-        view
+    // Fails if a mutable view of the range cannot be created, because the
+    // range overlaps another mutable view or a lent block.
+    fn check_exclusive(
+        &self,
+        exclusive: &[ExclusiveBlock],
+        off: usize,
+        len: usize,
+    ) -> io::Result<()> {
+        if is_exclusive(exclusive, off, len) {
+            return Err(overlapping_error());
+        }
+
+        // The ranges of the shared views are not tracked, so any lent block
+        // overlapping the range is considered as a conflict.
+        if self
+            .lent
+            .lock()
+            .unwrap()
+            .overlapping(off, len)
+            .next()
+            .is_some()
+        {
+            return Err(io::Error::other(
+                "a shared view of the range may be still active",
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn add_fetched_ref(&self, view: Block, off: usize, len: usize) -> io::Result<ViewRef<'_>> {
+        // New block must have been fetched, we store it and return a
+        // reference. A mutable view of the range may have been created since
+        // the block was requested.
+        let block = Arc::new(self.new_block(view));
+        let exclusive = self.exclusive.lock().unwrap();
+
+        if is_exclusive(&exclusive, off, len) {
+            drop(exclusive);
+            self.add_available(block);
+            return Err(overlapping_error());
+        }
+
+        Ok(self.add_lent(block, off, len))
     }
 
-    fn add_fetched_mut(&self, view: os::RawView, off: usize, len: usize) -> ViewMut<'_> {
-        // New block must have been fetched, we store it and return a reference.
-        // Note that we put that into available blocks. This is an optimization,
-        // see `take_mut` for justification.
-        let block = CachedBlock::new(view);
-        let view = block.view_mut(self, off, len);
-        *self.exclusive.lock().unwrap() = Some(block);
+    fn new_block(&self, view: Block) -> CachedBlock {
+        CachedBlock::new(view, self.fetched.fetch_add(1, Ordering::Relaxed))
+    }
+
+    fn add_lent(&self, block: Arc<CachedBlock>, off: usize, len: usize) -> ViewRef<'_> {
+        // The first reference must be created while holding the lock, see the
+        // invariant of `lent`.
+        let mut lent = self.lent.lock().unwrap();
+        let view = block.view_ref(self, off, len);
+        lent.insert(block);
+        self.lent_snapshot.store(Arc::new(lent.snapshot()));
         view
     }
 
-    fn restore_ref<'a>(&self, view: &ViewRef<'a>) {
-        let mut lent = self.lent.write().unwrap();
-
-        let mut available = None;
-        for (index, block) in lent.iter().enumerate() {
-            if block.holds(view.as_base_ptr()) {
-                if block.restore_ref(view) {
-                    // All references returned, the block is again available.
-                    available = Some(index);
-                }
+    fn add_fetched_mut(&self, view: Block, off: usize, len: usize) -> io::Result<ViewMut<'_>> {
+        // New block must have been fetched, we store it and return a
+        // reference. Another view of the range may have been created since
+        // the block was requested.
+        let block = Arc::new(self.new_block(view));
+        let mut exclusive = self.exclusive.lock().unwrap();
 
-                break;
-            }
+        if let Err(error) = self.check_exclusive(&exclusive, off, len) {
+            drop(exclusive);
+            self.add_available(block);
+            return Err(error);
         }
 
-        if let Some(index) = available {
-            let block = lent.remove(index);
+        let mut block = ExclusiveBlock::new(block);
+        let view = block.add_view(self, off, len);
+        exclusive.push(block);
+        self.exclusive_views.fetch_add(1, Ordering::SeqCst);
+        Ok(view)
+    }
+
+    fn restore_ref<'a>(&self, view: &ViewRef<'a>) {
+        let mut lent = self.lent.lock().unwrap();
+
+        let block = match lent.get(view.as_base_ptr()) {
+            Some(block) => block,
+            None => return,
+        };
+
+        // All references returned, the block is again available.
+        if block.restore_ref(view) {
+            let block = Arc::clone(block);
+            lent.remove(&block);
+            self.lent_snapshot.store(Arc::new(lent.snapshot()));
             std::mem::drop(lent);
             self.add_available(block);
         }
     }
 
     fn restore_mut<'a>(&self, view: &ViewMut<'a>) {
-        // The block must be in self.exclusive.
-        let block = self.exclusive.lock().unwrap().take().unwrap();
-        block.restore_mut(view);
-        self.add_available(block);
+        let mut exclusive = self.exclusive.lock().unwrap();
+
+        // The block must be in the exclusive blocks.
+        let index = exclusive
+            .iter()
+            .position(|exclusive| exclusive.block.holds(view.base_ptr))
+            .unwrap();
+
+        exclusive[index].restore_view(view);
+        self.exclusive_views.fetch_sub(1, Ordering::SeqCst);
+
+        // All mutable views returned, the block is again available.
+        if exclusive[index].ranges.is_empty() {
+            let block = exclusive.swap_remove(index).block;
+            drop(exclusive);
+            self.add_available(block);
+        }
     }
 
-    fn acquire_available(&self, off: usize, len: usize) -> Option<CachedBlock> {
+    fn acquire_available(&self, off: usize, len: usize) -> Option<Arc<CachedBlock>> {
         let mut available = self.available.write().unwrap();
+
+        // Prefer the most recently freed block if there are more hits.
         let found = available
-            .iter()
-            .enumerate()
-            .rev()
-            .find(|(_, view)| view.is_hit(off, len))
-            .map(|(index, _)| index);
+            .hits(off, len)
+            .max_by_key(|block| block.inserted())
+            .cloned();
 
-        if let Some(index) = found {
+        if let Some(block) = found {
             // If we have a hit, we need to move the cached item from the
             // available blocks into the lent blocks.
-            self.len.fetch_sub(1, Ordering::SeqCst);
-            let block = available.remove(index).unwrap();
+            available.remove(&block);
+            self.len.store(available.len(), Ordering::SeqCst);
             debug_assert!(block.is_hit(off, len));
             Some(block)
         } else {
@@ -211,44 +636,377 @@ impl Cache {
         }
     }
 
-    fn add_available(&self, block: CachedBlock) {
+    // Discards the available blocks chosen by the eviction policy until there
+    // are at most `target` of them, flushing the dirty ones. Returns the number
+    // of bytes that were unmapped. The first flushing error is reported, but
+    // all the blocks are discarded regardless.
+    fn evict_to(&self, available: &mut Blocks, target: usize) -> io::Result<usize> {
+        let mut unmapped = 0;
+        let mut result = Ok(());
+
+        while available.len() > target {
+            let dropped = match self.pop_victim(available) {
+                Some(dropped) => dropped,
+                None => break,
+            };
+            unmapped += dropped.block().len();
+            self.evictions.fetch_add(1, Ordering::Relaxed);
+
+            let flushed = dropped.flush_if_dirty();
+            if result.is_ok() {
+                result = flushed;
+            }
+        }
+
+        self.len.store(available.len(), Ordering::SeqCst);
+        result.map(|_| unmapped)
+    }
+
+    fn add_available(&self, block: Arc<CachedBlock>) {
         let mut available = self.available.write().unwrap();
 
         // We need to drop those blocks that overlap with the block being added.
         // This is necessary for keeping consistency when doing mutable views,
         // since the data is being flushed only when the dirty cached block is
         // being dropped.
-        let view = block.raw_view();
-        available.retain(|block| !block.is_overlapping(view.offset(), view.len()));
-        let mut len = available.len();
-
+        //
+        // Pinned blocks are kept regardless. Mappings of the same file are
+        // coherent, so the data seen through them is consistent.
+        let view = block.block();
+        while let Some(overlapping) = available
+            .overlapping(view.offset(), view.len())
+            .find(|block| !block.is_pinned())
+            .cloned()
+        {
+            available.remove(&overlapping);
+        }
         // Check if we are going to exceed the capacity. In such case, we
-        // discard the least recent block.
-        if len < self.capacity.get() {
-            len += 1;
-        } else {
-            let mut dropped = available.pop_front().unwrap();
-            dropped.flush_if_dirty();
+        // discard the blocks chosen by the eviction policy. There may be more
+        // than one if the capacity was lowered in the meantime. Pinned blocks
+        // are skipped, so the capacity may be exceeded if they fill it.
+        let capacity = self.capacity.load(Ordering::SeqCst);
+        let mut evicted = Vec::new();
+        while available.len() >= capacity {
+            match self.pop_victim(&mut available) {
+                Some(dropped) => evicted.push(dropped),
+                None => break,
+            }
+        }
+        self.evictions.fetch_add(evicted.len(), Ordering::Relaxed);
+
+        // Finally, store the block. The length is always derived from the
+        // actual state after all the mutations above, never adjusted
+        // incrementally, so it cannot drift.
+        available.insert(block);
+        self.len.store(available.len(), Ordering::SeqCst);
+
+        // Flushing (and unmapping on drop) may take long, so it is done after
+        // the lock is released to not stall other operations on the cache.
+        // The evicted blocks are no longer reachable through the cache and the
+        // mappings are shared, so a new mapping of the same region sees the
+        // modifications even before they are flushed.
+        drop(available);
+        for dropped in evicted {
+            let _ = dropped.flush_if_dirty();
         }
+    }
 
-        // Finally, store the block.
-        available.push_back(block);
-        self.len.store(len, Ordering::SeqCst);
+    // Removes the block that is not pinned and that should be evicted first
+    // according to the policy. The available blocks are ordered from the
+    // least recently freed, which also breaks the ties.
+    fn pop_victim(&self, available: &mut Blocks) -> Option<Arc<CachedBlock>> {
+        let mut unpinned = available.iter().filter(|block| !block.is_pinned());
+
+        let victim = match self.policy {
+            EvictionPolicy::Lru => unpinned.next(),
+            EvictionPolicy::Fifo => unpinned.min_by_key(|block| block.sequence),
+            EvictionPolicy::Lfu => unpinned.min_by_key(|block| block.accesses()),
+        }?
+        .clone();
+
+        available.remove(&victim)
+    }
+}
+
+// A collection of cached blocks indexed by their offsets, so that the blocks
+// satisfying a request are found by a range query instead of scanning all of
+// them. The blocks are also ordered by the time of their insertion, which is
+// the order of freeing for the available blocks.
+#[derive(Default)]
+struct Blocks {
+    // The blocks by the offset and the sequence number (more blocks may start
+    // at the same offset).
+    by_offset: BTreeMap<(usize, usize), Arc<CachedBlock>>,
+    // Maps the order of insertion to the key in `by_offset`.
+    order: BTreeMap<usize, (usize, usize)>,
+    // Maps the address of the mapped memory to the key in `by_offset`.
+    by_ptr: BTreeMap<usize, (usize, usize)>,
+    // Next key in `order`.
+    next: usize,
+    // Length of the longest block in the collection, which bounds how far
+    // before an offset the block containing it can start. It is reset only
+    // when the collection becomes empty.
+    max_len: usize,
+}
+
+impl Blocks {
+    pub fn len(&self) -> usize {
+        self.by_offset.len()
+    }
+
+    // Iterates the blocks from the least recently inserted.
+    pub fn iter(&self) -> BlocksIter<'_> {
+        BlocksIter {
+            blocks: self,
+            order: self.order.values(),
+        }
+    }
+
+    pub fn insert(&mut self, block: Arc<CachedBlock>) {
+        let order = self.next;
+        self.next += 1;
+
+        let key = block.offset_key();
+        block.inserted.store(order, Ordering::Relaxed);
+        self.max_len = std::cmp::max(self.max_len, block.block().len());
+        self.order.insert(order, key);
+        self.by_ptr.insert(block.block().as_ptr() as usize, key);
+        self.by_offset.insert(key, block);
+    }
+
+    pub fn remove(&mut self, block: &CachedBlock) -> Option<Arc<CachedBlock>> {
+        let block = self.by_offset.remove(&block.offset_key())?;
+        self.order.remove(&block.inserted());
+        self.by_ptr.remove(&(block.block().as_ptr() as usize));
+
+        if self.by_offset.is_empty() {
+            self.max_len = 0;
+        }
+
+        Some(block)
+    }
+
+    // Returns the block whose mapped memory starts at `ptr`.
+    pub fn get(&self, ptr: *const u8) -> Option<&Arc<CachedBlock>> {
+        self.by_ptr
+            .get(&(ptr as usize))
+            .map(|key| &self.by_offset[key])
+    }
+
+    // Iterates the blocks that contain the whole range.
+    pub fn hits(&self, off: usize, len: usize) -> Matching<'_> {
+        let start = hits_start(off, len, self.max_len);
+        self.matching(start, off + 1, off, len, CachedBlock::is_hit)
+    }
+
+    // Iterates the blocks that overlap the range.
+    pub fn overlapping(&self, off: usize, len: usize) -> Matching<'_> {
+        let start = std::cmp::min((off + 1).saturating_sub(self.max_len), off);
+        self.matching(start, off + len, off, len, CachedBlock::is_overlapping)
+    }
+
+    fn matching(
+        &self,
+        start: usize,
+        end: usize,
+        off: usize,
+        len: usize,
+        matches: fn(&CachedBlock, usize, usize) -> bool,
+    ) -> Matching<'_> {
+        let end = std::cmp::max(start, end);
+        Matching {
+            range: self.by_offset.range((start, 0)..(end, 0)),
+            off,
+            len,
+            matches,
+        }
+    }
+
+    // Copies the blocks into a flat sorted vector, which is much cheaper to
+    // create than a clone of the maps.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            blocks: self.by_offset.values().cloned().collect(),
+            max_len: self.max_len,
+        }
+    }
+}
+
+impl IntoIterator for Blocks {
+    type Item = Arc<CachedBlock>;
+    type IntoIter = btree_map::IntoValues<(usize, usize), Arc<CachedBlock>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.by_offset.into_values()
     }
 }
 
+// Iterates the blocks that start in a range of offsets and match the requested
+// range, from the last.
+struct Matching<'a> {
+    range: btree_map::Range<'a, (usize, usize), Arc<CachedBlock>>,
+    off: usize,
+    len: usize,
+    matches: fn(&CachedBlock, usize, usize) -> bool,
+}
+
+impl<'a> Iterator for Matching<'a> {
+    type Item = &'a Arc<CachedBlock>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (off, len, matches) = (self.off, self.len, self.matches);
+        self.range
+            .by_ref()
+            .rev()
+            .map(|(_, block)| block)
+            .find(|block| matches(block, off, len))
+    }
+}
+
+struct BlocksIter<'a> {
+    blocks: &'a Blocks,
+    order: btree_map::Values<'a, usize, (usize, usize)>,
+}
+
+impl<'a> Iterator for BlocksIter<'a> {
+    type Item = &'a Arc<CachedBlock>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.order.next().map(|key| &self.blocks.by_offset[key])
+    }
+}
+
+// An immutable copy of a collection of blocks, sorted by the offset and the
+// sequence number.
+#[derive(Default)]
+struct Snapshot {
+    blocks: Vec<Arc<CachedBlock>>,
+    max_len: usize,
+}
+
+impl Snapshot {
+    // Iterates the blocks that contain the whole range.
+    pub fn hits(&self, off: usize, len: usize) -> impl Iterator<Item = &Arc<CachedBlock>> {
+        let start = hits_start(off, len, self.max_len);
+        let start = self
+            .blocks
+            .partition_point(|block| block.block().offset() < start);
+        let end = self
+            .blocks
+            .partition_point(|block| block.block().offset() <= off);
+
+        self.blocks[start..std::cmp::max(start, end)]
+            .iter()
+            .rev()
+            .filter(move |block| block.is_hit(off, len))
+    }
+}
+
+// Returns the lowest offset at which a block no longer than `max_len` that
+// contains the whole range can start.
+fn hits_start(off: usize, len: usize, max_len: usize) -> usize {
+    std::cmp::min((off + len).saturating_sub(max_len), off)
+}
+
+// A block that is lent as ViewMuts.
+struct ExclusiveBlock {
+    block: Arc<CachedBlock>,
+    // The ranges of the views in the file.
+    ranges: Vec<(usize, usize)>,
+}
+
+impl ExclusiveBlock {
+    pub fn new(block: Arc<CachedBlock>) -> Self {
+        Self {
+            block,
+            ranges: Vec::new(),
+        }
+    }
+
+    pub fn add_view<'a>(&mut self, cache: &'a Cache, off: usize, len: usize) -> ViewMut<'a> {
+        self.ranges.push((off, len));
+        self.block.view_mut(cache, off, len)
+    }
+
+    pub fn restore_view<'a>(&mut self, view: &ViewMut<'a>) {
+        let off = self.block.block().offset() + view.off;
+        let index = self
+            .ranges
+            .iter()
+            .position(|range| *range == (off, view.len))
+            .unwrap();
+        self.ranges.swap_remove(index);
+        self.block.restore_mut(view);
+    }
+
+    pub fn is_overlapping(&self, off: usize, len: usize) -> bool {
+        self.ranges
+            .iter()
+            .any(|&(start, count)| start < off + len && off < start + count)
+    }
+}
+
+// Returns true if the range overlaps any active mutable view.
+fn is_exclusive(exclusive: &[ExclusiveBlock], off: usize, len: usize) -> bool {
+    exclusive
+        .iter()
+        .any(|exclusive| exclusive.is_overlapping(off, len))
+}
+
+// Number of bytes shown in the debug output of the views.
+const PREVIEW_LEN: usize = 16;
+
+// Formats the first few bytes in hexadecimal for the debug output.
+struct Preview<'a>(&'a [u8]);
+
+impl fmt::Debug for Preview<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[")?;
+
+        for (i, byte) in self.0.iter().take(PREVIEW_LEN).enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{:02x}", byte)?;
+        }
+
+        if self.0.len() > PREVIEW_LEN {
+            f.write_str(" ..")?;
+        }
+
+        f.write_str("]")
+    }
+}
+
+fn overlapping_error() -> io::Error {
+    io::Error::other("a mutable view is already active")
+}
+
 struct CachedBlock {
-    view: os::RawView,
+    view: Block,
     refs: AtomicUsize,
+    // Order in which the block was fetched.
+    sequence: usize,
+    // Key of the block in the collection of blocks it was last inserted into.
+    // For the available blocks, this is the order of freeing.
+    inserted: AtomicUsize,
+    // Number of views created from the block, never decreased.
+    accesses: AtomicUsize,
     dirty: AtomicBool,
+    // Pinned blocks are never evicted.
+    pinned: AtomicBool,
 }
 
 impl CachedBlock {
-    pub fn new(view: os::RawView) -> Self {
+    pub fn new(view: Block, sequence: usize) -> Self {
         Self {
             view,
             refs: AtomicUsize::new(0),
+            sequence,
+            inserted: AtomicUsize::new(0),
+            accesses: AtomicUsize::new(0),
             dirty: AtomicBool::new(false),
+            pinned: AtomicBool::new(false),
         }
     }
 
@@ -264,31 +1022,64 @@ impl CachedBlock {
         start < end
     }
 
-    pub fn raw_view(&self) -> &os::RawView {
+    pub fn block(&self) -> &Block {
         &self.view
     }
 
     pub fn view_ref<'a>(&self, cache: &'a Cache, off: usize, len: usize) -> ViewRef<'a> {
         self.refs.fetch_add(1, Ordering::SeqCst);
+        self.accesses.fetch_add(1, Ordering::Relaxed);
         ViewRef {
             cache,
             base_ptr: self.view.as_ptr(),
+            block_off: self.view.offset(),
             off: off - self.view.offset(),
             len,
         }
     }
 
+    // Creates a new reference only if the block is currently lent, i.e., its
+    // reference count is non-zero.
+    pub fn try_view_ref<'a>(
+        &self,
+        cache: &'a Cache,
+        off: usize,
+        len: usize,
+    ) -> Option<ViewRef<'a>> {
+        self.refs
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |refs| {
+                if refs > 0 {
+                    Some(refs + 1)
+                } else {
+                    None
+                }
+            })
+            .ok()
+            .map(|_| {
+                self.accesses.fetch_add(1, Ordering::Relaxed);
+                ViewRef {
+                    cache,
+                    base_ptr: self.view.as_ptr(),
+                    block_off: self.view.offset(),
+                    off: off - self.view.offset(),
+                    len,
+                }
+            })
+    }
+
     pub fn view_mut<'a>(&self, cache: &'a Cache, off: usize, len: usize) -> ViewMut<'a> {
         assert!(self.refs.load(Ordering::SeqCst) == 0);
+        self.accesses.fetch_add(1, Ordering::Relaxed);
         ViewMut {
             cache,
             base_ptr: self.view.as_ptr() as *mut u8,
+            block_off: self.view.offset(),
             off: off - self.view.offset(),
             len,
         }
     }
 
-    pub fn holds<'a>(&self, ptr: *const u8) -> bool {
+    pub fn holds(&self, ptr: *const u8) -> bool {
         self.view.as_ptr() == ptr
     }
 
@@ -301,29 +1092,72 @@ impl CachedBlock {
         self.dirty.store(true, Ordering::SeqCst);
     }
 
-    pub fn flush_if_dirty(&mut self) {
-        if self.dirty.load(Ordering::SeqCst) {
-            let _ = self.view.flush();
+    pub fn inserted(&self) -> usize {
+        self.inserted.load(Ordering::Relaxed)
+    }
+
+    pub fn offset_key(&self) -> (usize, usize) {
+        (self.view.offset(), self.sequence)
+    }
+
+    pub fn accesses(&self) -> usize {
+        self.accesses.load(Ordering::Relaxed)
+    }
+
+    pub fn is_pinned(&self) -> bool {
+        self.pinned.load(Ordering::SeqCst)
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty.load(Ordering::SeqCst)
+    }
+
+    pub fn flush_if_dirty(&self) -> io::Result<()> {
+        // The block is clean after a successful flush. Mutable views are
+        // never active while a block is flushed, so no modification is lost.
+        if self.dirty.swap(false, Ordering::SeqCst) {
+            let flushed = self.view.flush();
+            if flushed.is_err() {
+                self.dirty.store(true, Ordering::SeqCst);
+            }
+            flushed
+        } else {
+            Ok(())
         }
     }
 }
 
+// SAFETY: The block is shared between the cache and the snapshot of lent
+// blocks. The state is tracked by atomics and the memory itself is accessed
+// only through views, whose exclusivity is ensured by the cache.
+unsafe impl Send for CachedBlock {}
+unsafe impl Sync for CachedBlock {}
+
 pub struct Take<'a> {
     cache: &'a Cache,
     view: Option<ViewRef<'a>>,
+    // Whether the range overlaps an active mutable view.
+    overlapping: bool,
     off: usize,
     len: usize,
 }
 
 impl<'a> Take<'a> {
-    pub fn or_fetch<F>(self, fetch: F) -> io::Result<ViewRef<'a>>
+    pub fn or_fetch<F, B>(self, fetch: F) -> io::Result<ViewRef<'a>>
     where
-        F: FnOnce(usize, usize) -> io::Result<os::RawView>,
+        F: FnOnce(usize, usize) -> io::Result<B>,
+        B: Into<Block>,
     {
+        if self.overlapping {
+            return Err(overlapping_error());
+        }
+
         match self.view {
             Some(view) => Ok(view),
-            None => fetch(self.off, self.len)
-                .map(|fetched| self.cache.add_fetched_ref(fetched, self.off, self.len)),
+            None => fetch(self.off, self.len).and_then(|fetched| {
+                self.cache
+                    .add_fetched_ref(fetched.into(), self.off, self.len)
+            }),
         }
     }
 }
@@ -336,18 +1170,38 @@ pub struct TakeMut<'a> {
 }
 
 impl<'a> TakeMut<'a> {
-    pub fn or_fetch<F>(self, fetch: F) -> io::Result<ViewMut<'a>>
+    pub fn or_fetch<F, B>(self, fetch: F) -> io::Result<ViewMut<'a>>
     where
-        F: FnOnce(usize, usize) -> io::Result<os::RawView>,
+        F: FnOnce(usize, usize) -> io::Result<B>,
+        B: Into<Block>,
     {
         match self.view {
             Some(view) => Ok(view),
-            None => fetch(self.off, self.len)
-                .map(|fetched| self.cache.add_fetched_mut(fetched, self.off, self.len)),
+            None => fetch(self.off, self.len).and_then(|fetched| {
+                self.cache
+                    .add_fetched_mut(fetched.into(), self.off, self.len)
+            }),
         }
     }
 }
 
+/// Statistics of the cache behavior, useful for tuning the cache capacity and
+/// block size.
+///
+/// The counters are cumulative since the buffer was opened. They are updated
+/// without synchronization with each other, so a snapshot taken while other
+/// threads access the buffer may be slightly inconsistent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CacheStats {
+    /// The number of requests that were satisfied by a cached block.
+    pub hits: usize,
+    /// The number of requests that required mapping a new block.
+    pub misses: usize,
+    /// The number of blocks that were unmapped to make room for others (or
+    /// because the capacity was lowered or the cache was trimmed).
+    pub evictions: usize,
+}
+
 /// A read-only virtually mapped view into the underlying file.
 ///
 /// Essentially, it represents a shared reference to a slice of bytes `&[u8]`,
@@ -356,11 +1210,30 @@ impl<'a> TakeMut<'a> {
 pub struct ViewRef<'a> {
     cache: &'a Cache,
     base_ptr: *const u8,
+    // Offset of the block in the file.
+    block_off: usize,
+    // Offset of the view in the block.
     off: usize,
     len: usize,
 }
 
 impl<'a> ViewRef<'a> {
+    // Creates an empty view at given offset that does not refer to any cached
+    // block.
+    pub(crate) fn empty(cache: &'a Cache, off: usize) -> Self {
+        Self {
+            cache,
+            base_ptr: NonNull::dangling().as_ptr(),
+            block_off: off,
+            off: 0,
+            len: 0,
+        }
+    }
+
+    fn is_detached(&self) -> bool {
+        self.base_ptr == NonNull::dangling().as_ptr()
+    }
+
     pub(crate) fn as_base_ptr(&self) -> *const u8 {
         self.base_ptr
     }
@@ -390,15 +1263,27 @@ impl<'a> ViewRef<'a> {
         // SAFETY: ViewRef represents a shared reference, nothing can mutate
         // data in cached blocks during the lifetime of this reference. The
         // pointer is still referring to a valid memory, because Cache instance
-        // is owning the source RawView and is keeping it in its `lent` blocks.
-        // The correctness of the pointer and length is guaranteed by RawView.
+        // is owning the source Block and is keeping it in its `lent` blocks.
+        // The correctness of the pointer and length is guaranteed by Block.
         std::slice::from_raw_parts(self.as_ptr(), self.len)
     }
 }
 
+impl fmt::Debug for ViewRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ViewRef")
+            .field("offset", &(self.block_off + self.off))
+            .field("len", &self.len)
+            .field("data", &Preview(self.as_slice()))
+            .finish()
+    }
+}
+
 impl Drop for ViewRef<'_> {
     fn drop(&mut self) {
-        self.cache.restore_ref(self);
+        if !self.is_detached() {
+            self.cache.restore_ref(self);
+        }
     }
 }
 
@@ -423,6 +1308,79 @@ impl AsRef<[u8]> for ViewRef<'_> {
 unsafe impl Send for ViewRef<'_> {}
 unsafe impl Sync for ViewRef<'_> {}
 
+/// Either a read-only virtually mapped view into the underlying file or an
+/// owned copy of the bytes.
+///
+/// This is similar to [`Cow<[u8]>`](std::borrow::Cow), only that the borrowed
+/// variant needs to hold the view due to resource management.
+pub enum CowView<'a> {
+    /// Bytes referenced through a virtually mapped view.
+    Borrowed(ViewRef<'a>),
+    /// Bytes copied into an owned buffer.
+    Owned(Vec<u8>),
+}
+
+impl CowView<'_> {
+    /// Returns `true` if the bytes are referenced through a view.
+    pub fn is_borrowed(&self) -> bool {
+        matches!(self, CowView::Borrowed(_))
+    }
+
+    /// Returns `true` if the bytes are held in an owned buffer.
+    pub fn is_owned(&self) -> bool {
+        !self.is_borrowed()
+    }
+
+    /// Reinterprets the bytes to a slice.
+    pub fn as_slice(&self) -> &[u8] {
+        match self {
+            CowView::Borrowed(view) => view.as_slice(),
+            CowView::Owned(buf) => buf.as_slice(),
+        }
+    }
+
+    /// Extracts the owned bytes, copying them if they are borrowed.
+    pub fn into_owned(self) -> Vec<u8> {
+        match self {
+            CowView::Borrowed(view) => view.as_slice().to_vec(),
+            CowView::Owned(buf) => buf,
+        }
+    }
+}
+
+impl fmt::Debug for CowView<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CowView::Borrowed(view) => f.debug_tuple("Borrowed").field(view).finish(),
+            CowView::Owned(buf) => f
+                .debug_struct("Owned")
+                .field("len", &buf.len())
+                .field("data", &Preview(buf))
+                .finish(),
+        }
+    }
+}
+
+impl Deref for CowView<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        self.as_slice()
+    }
+}
+
+impl AsRef<[u8]> for CowView<'_> {
+    fn as_ref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl Borrow<[u8]> for CowView<'_> {
+    fn borrow(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
 /// A writable virtually mapped view into the underlying file.
 ///
 /// Essentially, it represents an exclusive reference to a slice of bytes `&mut
@@ -431,11 +1389,30 @@ unsafe impl Sync for ViewRef<'_> {}
 pub struct ViewMut<'a> {
     cache: &'a Cache,
     base_ptr: *mut u8,
+    // Offset of the block in the file.
+    block_off: usize,
+    // Offset of the view in the block.
     off: usize,
     len: usize,
 }
 
 impl<'a> ViewMut<'a> {
+    // Creates an empty view at given offset that does not refer to any cached
+    // block.
+    pub(crate) fn empty(cache: &'a Cache, off: usize) -> Self {
+        Self {
+            cache,
+            base_ptr: NonNull::dangling().as_ptr(),
+            block_off: off,
+            off: 0,
+            len: 0,
+        }
+    }
+
+    fn is_detached(&self) -> bool {
+        self.base_ptr == NonNull::dangling().as_ptr()
+    }
+
     pub(crate) fn as_ptr(&self) -> *const u8 {
         // SAFETY: The code that constructs the ViewRef must ensure that
         // base_ptr is valid for len bytes from offset off.
@@ -466,11 +1443,10 @@ impl<'a> ViewMut<'a> {
     /// memory after the view is dropped.
     pub unsafe fn as_slice_dangling(&self) -> &'a [u8] {
         // SAFETY: The pointer is still referring to a valid memory, because
-        // Cache instance is owning the source RawView. Although the RawView is
-        // stored in the available blocks, nothing can actually discard it from
-        // there because no other views (neither shared nor exclusive) can ve
-        // created from the cache during the lifetime of this ViewMut.
-        // The correctness of the pointer and length is guaranteed by RawView.
+        // Cache instance is owning the source Block. The Block is stored in
+        // the exclusive blocks until all mutable views of it are dropped, so
+        // nothing can discard it during the lifetime of this ViewMut.
+        // The correctness of the pointer and length is guaranteed by Block.
         std::slice::from_raw_parts(self.as_ptr(), self.len)
     }
 
@@ -483,17 +1459,30 @@ impl<'a> ViewMut<'a> {
     /// outlive the source `ViewMut`. Otherwise, the slice points to an invalid
     /// memory after the view is dropped.
     pub unsafe fn as_mut_slice_dangling(&mut self) -> &'a mut [u8] {
-        // SAFETY: The pointer validity is explained in `as_slice`. Mutable
-        // views can be acquired from the cache only through exclusive access -
-        // this guarantees that there is no lent block in the outside world. So
-        // indeed, this ViewMut is the only pointer to this block of memory.
+        // SAFETY: The pointer validity is explained in `as_slice`. The cache
+        // creates a mutable view only if its range does not overlap any other
+        // mutable view nor any lent block, and it creates no shared view that
+        // overlaps an active mutable view. So indeed, this ViewMut is the only
+        // pointer to this range of memory.
         std::slice::from_raw_parts_mut(self.as_ptr() as *mut _, self.len)
     }
 }
 
+impl fmt::Debug for ViewMut<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ViewMut")
+            .field("offset", &(self.block_off + self.off))
+            .field("len", &self.len)
+            .field("data", &Preview(self.as_slice()))
+            .finish()
+    }
+}
+
 impl Drop for ViewMut<'_> {
     fn drop(&mut self) {
-        self.cache.restore_mut(self);
+        if !self.is_detached() {
+            self.cache.restore_mut(self);
+        }
     }
 }
 
@@ -523,23 +1512,29 @@ impl AsMut<[u8]> for ViewMut<'_> {
     }
 }
 
-// SAFETY: To acquire ViewMut, which is a mutable chunk of memory, the cache
-// requires to have exclusive access. This exclusive access holds during the
-// whole lifetime of ViewMut, so an instance of ViewMut is a one and only one
-// mutable view to the underlying file. Moreover, ViewMut is not Copy nor Clone,
-// so there is no way how this mutable access can be shared.
+// SAFETY: The cache ensures that the range of ViewMut, which is a mutable chunk
+// of memory, does not overlap any other view during the whole lifetime of
+// ViewMut, so an instance of ViewMut is a one and only one view to this range
+// of the underlying file. Moreover, ViewMut is not Copy nor Clone, so there is
+// no way how this mutable access can be shared.
 unsafe impl Send for ViewMut<'_> {}
 unsafe impl Sync for ViewMut<'_> {}
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::options::OpenOptions;
+    use crate::os;
     use std::mem;
     use std::path::PathBuf;
 
     fn new_file(name: &str, pages: usize) -> os::RawFile {
-        os::RawFile::open_writable(PathBuf::from(name).as_path(), pages * os::get_alignment())
-            .unwrap()
+        os::RawFile::open_writable(
+            PathBuf::from(name).as_path(),
+            pages * os::get_alignment(),
+            &OpenOptions::new(),
+        )
+        .unwrap()
     }
 
     #[test]
@@ -624,10 +1619,11 @@ mod tests {
     #[test]
     fn take_mut_then_return() {
         let file = new_file("take_mut_then_return.tmp", 1);
-        let mut cache = Cache::with_capacity(1);
+        let cache = Cache::with_capacity(1);
 
         let view = cache
             .take_mut(0, os::get_alignment())
+            .unwrap()
             .or_fetch(|off, len| file.view(off, len, false))
             .unwrap();
 
@@ -671,7 +1667,7 @@ mod tests {
     #[test]
     fn take_mut_capacity_limit() {
         let file = new_file("take_mut_capacity_limit.tmp", 2);
-        let mut cache = Cache::with_capacity(1);
+        let cache = Cache::with_capacity(1);
 
         let view = cache
             .take(0, os::get_alignment())
@@ -682,6 +1678,7 @@ mod tests {
 
         let view = cache
             .take_mut(os::get_alignment(), os::get_alignment())
+            .unwrap()
             .or_fetch(|off, len| file.view(off, len, true))
             .unwrap();
 
@@ -733,4 +1730,248 @@ mod tests {
         assert_eq!(cache.available(), 0);
         assert_eq!(cache.lent(), 1);
     }
+
+    #[test]
+    fn take_mut_while_active() {
+        let file = new_file("take_mut_while_active.tmp", 1);
+        let cache = Cache::with_capacity(1);
+
+        let view = cache
+            .take_mut(0, os::get_alignment())
+            .unwrap()
+            .or_fetch(|off, len| file.view(off, len, true))
+            .unwrap();
+
+        // Simulate a view that is still alive while the cache is re-entered.
+        mem::forget(view);
+
+        let result = cache.take_mut(0, os::get_alignment());
+        assert!(result.is_err());
+        assert_eq!(
+            result.err().unwrap().to_string(),
+            "a mutable view is already active"
+        );
+    }
+
+    #[test]
+    fn lower_capacity() {
+        let file = new_file("lower_capacity.tmp", 3);
+        let cache = Cache::with_capacity(3);
+
+        for i in 0..3 {
+            let view = cache
+                .take(i * os::get_alignment(), os::get_alignment())
+                .or_fetch(|off, len| file.view(off, len, false))
+                .unwrap();
+            mem::drop(view);
+        }
+
+        assert_eq!(cache.available(), 3);
+
+        cache.set_capacity(NonZeroUsize::new(1).unwrap()).unwrap();
+
+        assert_eq!(cache.available(), 1);
+        assert!(cache.holds(2 * os::get_alignment(), os::get_alignment()));
+
+        // The new capacity is respected when adding blocks.
+        let view = cache
+            .take(0, os::get_alignment())
+            .or_fetch(|off, len| file.view(off, len, false))
+            .unwrap();
+        mem::drop(view);
+
+        assert_eq!(cache.available(), 1);
+        assert!(cache.holds(0, os::get_alignment()));
+    }
+
+    #[test]
+    fn trim() {
+        let file = new_file("trim.tmp", 3);
+        let cache = Cache::with_capacity(3);
+
+        for i in 0..3 {
+            let view = cache
+                .take(i * os::get_alignment(), os::get_alignment())
+                .or_fetch(|off, len| file.view(off, len, false))
+                .unwrap();
+            mem::drop(view);
+        }
+
+        assert_eq!(cache.trim(1).unwrap(), 2 * os::get_alignment());
+        assert_eq!(cache.available(), 1);
+        assert!(cache.holds(2 * os::get_alignment(), os::get_alignment()));
+
+        // Nothing to trim.
+        assert_eq!(cache.trim(1).unwrap(), 0);
+        assert_eq!(cache.trim(0).unwrap(), os::get_alignment());
+        assert_eq!(cache.available(), 0);
+    }
+
+    #[test]
+    fn overlapping_capacity_limit() {
+        let file = new_file("overlapping_capacity_limit.tmp", 4);
+        let cache = Cache::with_capacity(2);
+        let alignment = os::get_alignment();
+
+        // Interleave the mutable views that span two pages with the shared
+        // views of single pages, so the blocks overlap in various ways.
+        for i in 0..12 {
+            let off = (i % 3) * alignment;
+
+            let view = cache
+                .take_mut(off, 2 * alignment)
+                .unwrap()
+                .or_fetch(|off, len| file.view(off, len, true))
+                .unwrap();
+            mem::drop(view);
+
+            assert!(cache.available() <= 2);
+            assert_eq!(cache.len.load(Ordering::SeqCst), cache.available());
+
+            let view = cache
+                .take(((i + 1) % 4) * alignment, alignment)
+                .or_fetch(|off, len| file.view(off, len, false))
+                .unwrap();
+            mem::drop(view);
+
+            assert!(cache.available() <= 2);
+            assert_eq!(cache.len.load(Ordering::SeqCst), cache.available());
+        }
+    }
+
+    #[test]
+    fn eviction_policies() {
+        let alignment = os::get_alignment();
+        let file = new_file("eviction_policies.tmp", 4);
+
+        let evicted = |policy| {
+            let cache = Cache::with_policy(3, policy);
+
+            // Block 1 is fetched first, block 0 is used the most and block 2
+            // is used the least. Block 1 is freed most recently.
+            for block in [1, 0, 0, 0, 2, 1, 3] {
+                let view = cache
+                    .take(block * alignment, alignment)
+                    .or_fetch(|off, len| file.view(off, len, false))
+                    .unwrap();
+                mem::drop(view);
+            }
+
+            assert_eq!(cache.available(), 3);
+            assert!(cache.holds(3 * alignment, alignment));
+            (0..3)
+                .filter(|block| !cache.holds(block * alignment, alignment))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(evicted(EvictionPolicy::Lru), vec![0]);
+        assert_eq!(evicted(EvictionPolicy::Fifo), vec![1]);
+        assert_eq!(evicted(EvictionPolicy::Lfu), vec![2]);
+    }
+
+    #[test]
+    fn high_capacity() {
+        const BLOCKS: usize = 1024;
+
+        let alignment = os::get_alignment();
+        let file = new_file("high_capacity.tmp", BLOCKS + 8);
+        let cache = Cache::with_capacity(BLOCKS);
+
+        // Half of the blocks stay lent, the other half is available.
+        let mut lent = Vec::new();
+        for block in 0..BLOCKS {
+            let view = cache
+                .take(block * alignment, alignment)
+                .or_fetch(|off, len| file.view(off, len, false))
+                .unwrap();
+
+            if block % 2 == 0 {
+                lent.push(view);
+            }
+        }
+
+        assert_eq!(cache.lent(), BLOCKS / 2);
+        assert_eq!(cache.available(), BLOCKS / 2);
+
+        // Every request inside the blocks is a hit, regardless of where it
+        // lies in the block.
+        for round in 0..4 {
+            for block in 0..BLOCKS {
+                let off = block * alignment + round * (alignment / 4);
+                let view = cache
+                    .take(off, alignment / 4)
+                    .or_fetch(|_, _| -> io::Result<Block> { panic!("the block should be hit") })
+                    .unwrap();
+                assert_eq!(view.len(), alignment / 4);
+            }
+        }
+
+        // A longer block is found even though a shorter block starts closer to
+        // the requested offset.
+        let short = cache
+            .take((BLOCKS + 6) * alignment, alignment)
+            .or_fetch(|off, len| file.view(off, len, false))
+            .unwrap();
+        let long = cache
+            .take(BLOCKS * alignment, 8 * alignment)
+            .or_fetch(|off, len| file.view(off, len, false))
+            .unwrap();
+        let view = cache
+            .take((BLOCKS + 6) * alignment + 1, alignment)
+            .or_fetch(|_, _| -> io::Result<Block> { panic!("the block should be hit") })
+            .unwrap();
+        assert_eq!(view.as_base_ptr(), long.as_base_ptr());
+        mem::drop((short, long, view));
+
+        let stats = cache.stats();
+        assert_eq!(stats.misses, BLOCKS + 2);
+        assert_eq!(stats.hits, 4 * BLOCKS + 1);
+
+        mem::drop(lent);
+        assert_eq!(cache.lent(), 0);
+        assert_eq!(cache.available(), BLOCKS);
+    }
+
+    #[test]
+    fn concurrent_hits() {
+        let file = new_file("concurrent_hits.tmp", 2);
+        let cache = Cache::with_capacity(2);
+        let alignment = os::get_alignment();
+
+        let first = cache
+            .take(0, alignment)
+            .or_fetch(|off, len| file.view(off, len, false))
+            .unwrap();
+
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for i in 0..100 {
+                        let view = cache
+                            .take(i % alignment, 1)
+                            .or_fetch(|_, _| -> io::Result<Block> {
+                                panic!("the block should be hit")
+                            })
+                            .unwrap();
+                        assert_eq!(view.len(), 1);
+                    }
+                });
+            }
+        });
+
+        assert_eq!(cache.lent(), 1);
+        mem::drop(first);
+        assert_eq!(cache.lent(), 0);
+        assert_eq!(cache.available(), 1);
+
+        // The block is reused after it was moved to available blocks, even
+        // though it may be still present in a stale snapshot.
+        let view = cache
+            .take(0, alignment)
+            .or_fetch(|off, len| file.view(off, len, false))
+            .unwrap();
+        assert_eq!(cache.lent(), 1);
+        assert_eq!(cache.available(), 0);
+        mem::drop(view);
+    }
 }
@@ -0,0 +1,227 @@
+//! Typed containers built on top of the buffers.
+
+use std::io;
+use std::marker::PhantomData;
+use std::path::Path;
+
+use crate::FileMut;
+
+/// Types whose values can be stored in a file and read back from arbitrary
+/// bytes, such as integers and floats.
+///
+/// The file may be modified externally, so reading a value must be sound for
+/// any contents. This is implemented for the primitive numeric types and for
+/// arrays of such types.
+///
+/// # Safety
+///
+/// Every bit pattern of `size_of::<Self>()` bytes must be a valid value of the
+/// type, and the type must not contain any padding bytes. This rules out,
+/// e.g., `bool`, `char`, references and most enums. These are the same
+/// requirements as those of `bytemuck::Pod`.
+pub unsafe trait Plain: Copy {}
+
+macro_rules! impl_plain {
+    ($($ty:ty),*) => {
+        $(
+            // SAFETY: Numeric types have no padding and no invalid values.
+            unsafe impl Plain for $ty {}
+        )*
+    };
+}
+
+impl_plain!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+
+// SAFETY: Arrays have no padding between the elements, so they are valid for
+// any bit pattern if the elements are.
+unsafe impl<T: Plain, const N: usize> Plain for [T; N] {}
+
+/// A growable array of values of type `T` stored in a file, similar to `Vec`.
+///
+/// The values are stored one after another in the native memory layout of
+/// `T`. When the file is full, its size is doubled using
+/// [`FileMut::resize`](crate::FileMut::resize). The values are read back from
+/// the bytes of the file, so `T` must implement [`Plain`].
+///
+/// # Examples
+///
+/// ```
+/// use harrow::FileVec;
+///
+/// let mut numbers = FileVec::new("file_vec.bin").unwrap();
+///
+/// for i in 0..1000u64 {
+///     numbers.push(i * i).unwrap();
+/// }
+///
+/// numbers.set(3, 42);
+/// assert_eq!(numbers.len(), 1000);
+/// assert_eq!(numbers.get(3), Some(42));
+/// assert_eq!(numbers.get(999), Some(998001));
+/// assert_eq!(numbers.get(1000), None);
+/// ```
+pub struct FileVec<T: Plain> {
+    data: FileMut,
+    len: usize,
+    _ty: PhantomData<T>,
+}
+
+impl<T: Plain> FileVec<T> {
+    const SIZE: usize = std::mem::size_of::<T>();
+
+    /// Creates new empty vector backed by the file at given `path`. For the
+    /// handling of the file see [`FileMut::new`](crate::FileMut::new).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `T` is a zero-sized type.
+    pub fn new<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::with_capacity(path, 1)
+    }
+
+    /// Creates new empty vector backed by the file at given `path` with room
+    /// for at least `capacity` values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `T` is a zero-sized type.
+    pub fn with_capacity<P: AsRef<Path>>(path: P, capacity: usize) -> io::Result<Self> {
+        assert!(Self::SIZE > 0, "zero-sized types are not supported");
+
+        // The file is never empty, which is required by the buffer.
+        let len = capacity
+            .max(1)
+            .checked_mul(Self::SIZE)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "capacity overflow"))?;
+
+        Ok(Self {
+            data: FileMut::new(path, len)?,
+            len: 0,
+            _ty: PhantomData,
+        })
+    }
+
+    /// Returns the number of values in the vector.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the vector contains no values.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of values the vector can hold without resizing the
+    /// file.
+    pub fn capacity(&self) -> usize {
+        self.data.len() / Self::SIZE
+    }
+
+    /// Appends a value to the end of the vector, doubling the size of the file
+    /// if it is full.
+    pub fn push(&mut self, value: T) -> io::Result<()> {
+        if self.len == self.capacity() {
+            let new_len = std::cmp::max(2 * self.data.len(), (self.len + 1) * Self::SIZE);
+            self.data.resize(new_len)?;
+        }
+
+        self.write(self.len, value)?;
+        self.len += 1;
+
+        Ok(())
+    }
+
+    /// Returns the value at index `i`, or `None` if it is out of bounds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if mapping the part of the file with the value fails.
+    pub fn get(&self, i: usize) -> Option<T> {
+        if i >= self.len {
+            return None;
+        }
+
+        let view = self
+            .data
+            .view(i * Self::SIZE, Self::SIZE)
+            .expect("failed to map the value");
+
+        // SAFETY: The view is exactly `size_of::<T>()` bytes long and any bit
+        // pattern is a valid value of `T`, even if the file was modified
+        // externally. The view may not be aligned for `T`.
+        Some(unsafe { std::ptr::read_unaligned(view.as_slice().as_ptr() as *const T) })
+    }
+
+    /// Replaces the value at index `i`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is out of bounds or if mapping the part of the file with
+    /// the value fails.
+    pub fn set(&mut self, i: usize, value: T) {
+        assert!(i < self.len, "index out of bounds");
+        self.write(i, value).expect("failed to map the value");
+    }
+
+    fn write(&mut self, i: usize, value: T) -> io::Result<()> {
+        let mut view = self.data.view_mut(i * Self::SIZE, Self::SIZE)?;
+
+        // SAFETY: The view is exactly `size_of::<T>()` bytes long and `T` has
+        // no padding, so all written bytes are initialized. The view may not be
+        // aligned for `T`.
+        unsafe { std::ptr::write_unaligned(view.as_mut_slice().as_mut_ptr() as *mut T, value) };
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_million() {
+        const N: u64 = 1_000_000;
+
+        let mut vec = FileVec::new("file_vec_push_million.tmp").unwrap();
+        assert!(vec.is_empty());
+
+        for i in 0..N {
+            vec.push(i).unwrap();
+        }
+
+        assert_eq!(vec.len(), N as usize);
+        assert!(vec.capacity() >= vec.len());
+
+        for i in 0..N {
+            assert_eq!(vec.get(i as usize), Some(i));
+        }
+
+        assert_eq!(vec.get(N as usize), None);
+    }
+
+    #[test]
+    fn set_and_unaligned() {
+        // The size is not a power of two, so some values cross the boundaries
+        // of the cached blocks.
+        let mut vec = FileVec::<[u8; 3]>::with_capacity("file_vec_unaligned.tmp", 2).unwrap();
+
+        for i in 0..5000u32 {
+            let bytes = i.to_ne_bytes();
+            vec.push([bytes[0], bytes[1], bytes[2]]).unwrap();
+        }
+
+        vec.set(4321, [1, 2, 3]);
+        assert_eq!(vec.get(4321), Some([1, 2, 3]));
+        let bytes = 4320u32.to_ne_bytes();
+        assert_eq!(vec.get(4320), Some([bytes[0], bytes[1], bytes[2]]));
+    }
+
+    #[test]
+    #[should_panic(expected = "index out of bounds")]
+    fn set_out_of_bounds() {
+        let mut vec = FileVec::<u32>::new("file_vec_set_out_of_bounds.tmp").unwrap();
+        vec.push(1).unwrap();
+        vec.set(1, 2);
+    }
+}
@@ -0,0 +1,37 @@
+//! Statistics of byte data.
+
+/// Computes the Shannon entropy of the bytes in bits per byte. The result is
+/// between 0 (all bytes are the same) and 8 (all byte values are equally
+/// frequent).
+///
+/// High entropy of a [sample](crate::FileRef::sample) of the file suggests that
+/// the file is compressed or encrypted.
+///
+/// # Examples
+///
+/// ```
+/// use harrow::shannon_entropy;
+///
+/// assert_eq!(shannon_entropy(b"aaaa"), 0.0);
+/// assert_eq!(shannon_entropy(b"abab"), 1.0);
+/// ```
+pub fn shannon_entropy(data: &[u8]) -> f64 {
+    if data.is_empty() {
+        return 0.0;
+    }
+
+    let mut counts = [0usize; 256];
+    for &byte in data {
+        counts[byte as usize] += 1;
+    }
+
+    let len = data.len() as f64;
+    counts
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
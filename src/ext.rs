@@ -14,11 +14,8 @@ impl<T, E> ResultExt<T, E> for Result<T, E> {
     where
         F: FnOnce() -> R,
     {
-        match self {
-            Err(_) => {
-                clean();
-            }
-            _ => {}
+        if self.is_err() {
+            clean();
         }
         self
     }
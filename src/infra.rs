@@ -2,12 +2,29 @@
 //! [`FileRef`](crate::FileRef).
 
 use std::fmt;
+use std::fs;
+use std::hash::Hasher;
 use std::io;
-use std::path::Path;
+use std::num::NonZeroUsize;
+use std::ops::{Bound, Range, RangeBounds};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
 
-use crate::align::{align_add, align_sub, ALIGNMENT};
-use crate::cache::{Cache, ViewMut, ViewRef};
+use crate::align::{align_sub, saturating_align_add, try_align_add, ALIGNMENT};
+use crate::cache::{Cache, CacheStats, ViewMut, ViewRef};
+use crate::ext::ResultExt;
+use crate::options::{
+    AdaptiveBlockSize, FetchCallback, HugePages, OpenOptions, StridedHint, TempStrategy,
+    UnflushedCallback,
+};
 use crate::os;
+use crate::registry::Registration;
+use crate::FileMut;
+
+/// Callback invoked after the file was grown, with the old and new length.
+pub type GrowCallback = Box<dyn FnMut(&mut FileMut, usize, usize) + Send>;
 
 /// File wrapper that manages a cache of virtual mapping used for acquiring
 /// parts of the file.
@@ -15,10 +32,46 @@ use crate::os;
 /// If the file is opened as read-only, all methods taking exclusive reference
 /// panic.
 pub struct File {
-    raw: os::RawFile,
+    source: Source,
     cache: Cache,
     mode: Mode,
+    // Whether the contents of the file should be overwritten with zeros when
+    // dropped.
+    zeroize_on_drop: bool,
+    // Callback invoked when dropped with dirty blocks in the cache.
+    on_unflushed_drop: Option<UnflushedCallback>,
+    // Size of the records stored in the file, if known.
+    record_size: Option<usize>,
+    // Callback invoked after the file was grown.
+    on_grow: Option<GrowCallback>,
+    // Registration of the file in the process-local registry. Temporary files
+    // are not registered, because they cannot be opened again.
+    registration: Option<Registration>,
+    // Path the file was opened with, if it has any.
+    path: Option<PathBuf>,
+    // Whether the file is deleted when dropped.
+    temporary: bool,
+}
+
+/// The underlying file together with the settings of how the blocks are
+/// fetched from it.
+///
+/// This is separated from `File` so it can be borrowed independently from the
+/// cache.
+struct Source {
+    raw: os::RawFile,
+    writable: bool,
     cache_block_size: usize,
+    // Alignment of the mapped blocks. It is the OS granularity unless huge
+    // pages are requested.
+    alignment: usize,
+    huge_pages: HugePages,
+    // Bounds for the block size if it adapts to the access pattern.
+    adaptive: Option<AdaptiveBlockSize>,
+    // Block size for strided access, if it is applicable.
+    strided: Option<StridedHint>,
+    // Callback invoked whenever a new block is mapped.
+    on_fetch: Option<FetchCallback>,
 }
 
 impl File {
@@ -28,6 +81,80 @@ impl File {
         cache_capacity: usize,
         cache_block_size: usize,
     ) -> io::Result<Self> {
+        Self::open_writable_with(
+            path,
+            len,
+            OpenOptions::new()
+                .cache_capacity(cache_capacity)
+                .cache_block_size(cache_block_size),
+        )
+    }
+
+    pub fn open_writable_with(path: &Path, len: usize, options: &OpenOptions) -> io::Result<Self> {
+        options.validate()?;
+
+        let exists = path.exists();
+
+        // Adopt the size of the existing file so its tail is not truncated.
+        let len = if exists && !options.truncate {
+            len.max(path.metadata()?.len() as usize)
+        } else {
+            len
+        };
+
+        if len == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "len must be greater than zero",
+            ));
+        }
+
+        let registration = if exists {
+            Some(Registration::register(path, true)?)
+        } else {
+            None
+        };
+
+        let alignment = block_alignment(options);
+        let len = try_align_add(len, alignment)?;
+        let raw = os::RawFile::open_writable(path, len, options)?;
+
+        // A temporary file that is kept in the directory can be opened again,
+        // so it needs to be registered too.
+        let registration = if !exists && options.temp_strategy != TempStrategy::DeleteOnDrop {
+            Some(Registration::register(path, true)?)
+        } else {
+            registration
+        };
+
+        let mode = Mode::Writable;
+
+        Ok(Self {
+            source: Source {
+                raw,
+                writable: mode.is_writable(),
+                cache_block_size: fix_cache_block_size(options.cache_block_size, alignment),
+                alignment,
+                huge_pages: options.huge_pages,
+                adaptive: fix_adaptive_block_size(options.adaptive_block_size, alignment),
+                strided: fix_strided_hint(options, alignment),
+                on_fetch: options.on_fetch.clone(),
+            },
+            cache: Cache::with_policy(options.cache_capacity, options.eviction_policy),
+            mode,
+            zeroize_on_drop: false,
+            on_unflushed_drop: options.on_unflushed_drop.clone(),
+            record_size: None,
+            on_grow: None,
+            registration,
+            path: Some(path.to_path_buf()),
+            temporary: !exists && options.temp_strategy != TempStrategy::Keep,
+        })
+    }
+
+    pub fn open_anonymous(len: usize, options: &OpenOptions) -> io::Result<Self> {
+        options.validate()?;
+
         if len == 0 {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidInput,
@@ -35,14 +162,33 @@ impl File {
             ));
         }
 
-        let len = align_add(len);
-        let raw = os::RawFile::open_writable(path, len)?;
+        let alignment = block_alignment(options);
+        let len = try_align_add(len, alignment)?;
+        let raw = os::RawFile::open_anonymous(len, options)?;
+
+        let mode = Mode::Writable;
 
         Ok(Self {
-            raw,
-            cache: Cache::with_capacity(cache_capacity),
-            mode: Mode::Writable,
-            cache_block_size: fix_cache_block_size(cache_block_size),
+            source: Source {
+                raw,
+                writable: mode.is_writable(),
+                cache_block_size: fix_cache_block_size(options.cache_block_size, alignment),
+                alignment,
+                huge_pages: options.huge_pages,
+                adaptive: fix_adaptive_block_size(options.adaptive_block_size, alignment),
+                strided: fix_strided_hint(options, alignment),
+                on_fetch: options.on_fetch.clone(),
+            },
+            cache: Cache::with_policy(options.cache_capacity, options.eviction_policy),
+            mode,
+            zeroize_on_drop: false,
+            on_unflushed_drop: options.on_unflushed_drop.clone(),
+            record_size: None,
+            on_grow: None,
+            // There is no path, so the file cannot be opened again.
+            registration: None,
+            path: None,
+            temporary: true,
         })
     }
 
@@ -51,17 +197,202 @@ impl File {
         cache_capacity: usize,
         cache_block_size: usize,
     ) -> io::Result<Self> {
-        let raw = os::RawFile::open_readonly(path)?
+        Self::open_readonly_with(
+            path,
+            OpenOptions::new()
+                .cache_capacity(cache_capacity)
+                .cache_block_size(cache_block_size),
+        )
+    }
+
+    pub fn open_readonly_with(path: &Path, options: &OpenOptions) -> io::Result<Self> {
+        options.validate()?;
+
+        let registration = Registration::register(path, false)?;
+        let raw = os::RawFile::open_readonly(path, options)?
+            .ok_or(io::Error::new(io::ErrorKind::InvalidData, "file is empty"))?;
+
+        let alignment = block_alignment(options);
+
+        let mode = Mode::Readonly;
+
+        Ok(Self {
+            source: Source {
+                raw,
+                writable: mode.is_writable(),
+                cache_block_size: fix_cache_block_size(options.cache_block_size, alignment),
+                alignment,
+                huge_pages: options.huge_pages,
+                adaptive: fix_adaptive_block_size(options.adaptive_block_size, alignment),
+                strided: fix_strided_hint(options, alignment),
+                on_fetch: options.on_fetch.clone(),
+            },
+            cache: Cache::with_policy(options.cache_capacity, options.eviction_policy),
+            mode,
+            zeroize_on_drop: false,
+            on_unflushed_drop: options.on_unflushed_drop.clone(),
+            record_size: None,
+            on_grow: None,
+            registration: Some(registration),
+            path: Some(path.to_path_buf()),
+            temporary: false,
+        })
+    }
+
+    pub fn open_private(path: &Path) -> io::Result<Self> {
+        // The file is never modified, but it must not be modified by a writer
+        // in this process either, because the pages that were not copied yet
+        // would see the changes.
+        let registration = Registration::register(path, false)?;
+        Self::open_overlay(path, Some(registration))
+    }
+
+    pub fn overlay(&self) -> io::Result<Self> {
+        // Temporary files cannot be opened again.
+        let path = self
+            .registration
+            .as_ref()
+            .map(Registration::path)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "the file cannot be opened again",
+                )
+            })?;
+
+        // The file is already registered by this buffer.
+        let mut overlay = Self::open_overlay(path, None)?;
+        overlay.record_size = self.record_size;
+        Ok(overlay)
+    }
+
+    fn open_overlay(path: &Path, registration: Option<Registration>) -> io::Result<Self> {
+        let options = OpenOptions::new();
+        let raw = os::RawFile::open_private(path, &options)?
             .ok_or(io::Error::new(io::ErrorKind::InvalidData, "file is empty"))?;
 
+        let alignment = block_alignment(&options);
+        let mode = Mode::Writable;
+
         Ok(Self {
-            raw,
-            cache: Cache::with_capacity(cache_capacity),
-            mode: Mode::Readonly,
-            cache_block_size: fix_cache_block_size(cache_block_size),
+            source: Source {
+                // The modifications live only in the private mapping, so it
+                // must never be evicted. Hence the whole file is mapped as a
+                // single block.
+                cache_block_size: try_align_add(raw.len(), alignment)?,
+                raw,
+                writable: mode.is_writable(),
+                alignment,
+                huge_pages: options.huge_pages,
+                adaptive: None,
+                strided: None,
+                on_fetch: None,
+            },
+            cache: Cache::with_capacity(1),
+            mode,
+            zeroize_on_drop: false,
+            on_unflushed_drop: None,
+            record_size: None,
+            on_grow: None,
+            registration,
+            path: Some(path.to_path_buf()),
+            temporary: false,
         })
     }
 
+    // Closes the read-only file and opens it again with writable access,
+    // keeping the cache settings.
+    // The error carries the buffer, which is as large as the success value.
+    #[allow(clippy::result_large_err)]
+    pub fn into_writable(self) -> Result<Self, (Option<Self>, io::Error)> {
+        // Temporary files cannot be opened again.
+        let path = match &self.registration {
+            Some(registration) => registration.path().to_path_buf(),
+            None => {
+                let err = io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "the file cannot be opened again",
+                );
+                return Err((Some(self), err));
+            }
+        };
+
+        let len = self.len();
+        let record_size = self.record_size;
+
+        let mut options = OpenOptions::new();
+        options
+            .cache_capacity(self.cache.capacity())
+            .cache_block_size(self.source.cache_block_size)
+            .eviction_policy(self.cache.policy())
+            .alignment(self.source.alignment)
+            .huge_pages(self.source.huge_pages);
+        options.adaptive_block_size = self.source.adaptive;
+        options.strided_hint = self.source.strided;
+        options.on_fetch = self.source.on_fetch.clone();
+
+        // The read lock must be released before the write lock is acquired.
+        // On Unix, the locks are held per process and closing any descriptor
+        // of the file releases all of them, so the old descriptor cannot be
+        // closed after the new one is locked.
+        drop(self);
+
+        match Self::open_writable_with(&path, len, &options) {
+            Ok(mut file) => {
+                file.record_size = record_size;
+                Ok(file)
+            }
+            Err(err) => {
+                // Do not lose the buffer. This fails only if the file became
+                // inaccessible in the meantime.
+                let file = Self::open_readonly_with(&path, &options)
+                    .ok()
+                    .map(|mut file| {
+                        file.record_size = record_size;
+                        file
+                    });
+                Err((file, err))
+            }
+        }
+    }
+
+    // Turns the writable file into a read-only one, keeping the descriptor
+    // and the cached blocks.
+    // The error carries the buffer, which is as large as the success value.
+    #[allow(clippy::result_large_err)]
+    pub fn into_readonly(mut self) -> Result<Self, (Self, io::Error)> {
+        if self.mode == Mode::Readonly {
+            return Ok(self);
+        }
+
+        // The views cannot outlive a borrow of the file, so the only way how a
+        // mutable view can still be active is that it was leaked. It must not
+        // be able to modify a read-only buffer.
+        if self.cache.is_viewed_mut() {
+            let err = io::Error::other("cannot convert while a mutable view is active");
+            return Err((self, err));
+        }
+
+        if let Err(err) = self.cache.flush() {
+            return Err((self, err));
+        }
+
+        if let Err(err) = self.source.raw.downgrade_lock() {
+            return Err((self, err));
+        }
+
+        if let Some(registration) = self.registration.as_mut() {
+            registration.downgrade();
+        }
+
+        // The blocks are still mapped with writable access (so that the cache
+        // is not cold), but no mutable views are handed out anymore.
+        self.mode = Mode::Readonly;
+        self.on_grow = None;
+
+        Ok(self)
+    }
+
     pub fn resize(&mut self, new_len: usize) -> io::Result<()> {
         assert_eq!(
             self.mode,
@@ -69,6 +400,15 @@ impl File {
             "underlying file was opened as read-only"
         );
 
+        self.check_resizable()?;
+
+        // The views cannot outlive a borrow of the file, so the only way how a
+        // view can still be active is that it was leaked. Its mapping must
+        // not be touched by the resize.
+        if self.cache.is_viewed() {
+            return Err(io::Error::other("cannot resize while a view is active"));
+        }
+
         if new_len == 0 {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidInput,
@@ -76,15 +416,147 @@ impl File {
             ));
         }
 
-        let old_len = self.raw.len();
-        let new_len = align_add(new_len);
+        let old_len = self.source.raw.len();
+        let new_len = try_align_add(new_len, self.source.alignment)?;
 
         if old_len == new_len {
             return Ok(());
         }
 
-        // Resize the underlying file.
-        self.raw.resize(new_len)
+        if cfg!(windows) {
+            // The mapping object is recreated by the resize and the file
+            // cannot be resized while any view of it is mapped, so all cached
+            // blocks are unmapped beforehand.
+            self.cache.invalidate(0, old_len)?;
+        } else if new_len < old_len {
+            // The cached blocks in the discarded part would point beyond the
+            // end of the file.
+            self.cache.invalidate(new_len, old_len - new_len)?;
+        }
+
+        // Resize the underlying file. The new part is not touched, so that it
+        // stays sparse where supported.
+        self.source.raw.resize(new_len)
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn is_temporary(&self) -> bool {
+        self.temporary
+    }
+
+    pub fn generation(&self) -> usize {
+        self.cache.generation()
+    }
+
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache.stats()
+    }
+
+    pub fn ensure_len(&mut self, min_len: usize) -> io::Result<()> {
+        if min_len > self.source.raw.len() {
+            self.resize(min_len)
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn split_off(&mut self, at: usize, new_path: &Path) -> io::Result<File> {
+        assert_eq!(
+            self.mode,
+            Mode::Writable,
+            "underlying file was opened as read-only"
+        );
+
+        let len = self.source.raw.len();
+
+        if at > len {
+            panic!("at out of bounds");
+        }
+
+        if at == 0 || at == len {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "at must split the file into two non-empty parts",
+            ));
+        }
+
+        self.check_resizable()?;
+
+        // Create the file first so that it is not considered as a temporary.
+        fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(new_path)?;
+
+        // The new file is removed if any of the following steps fails.
+        let mut split = || {
+            let tail = File::open_writable_with(
+                new_path,
+                len - at,
+                OpenOptions::new()
+                    .cache_capacity(self.cache.capacity())
+                    .cache_block_size(self.source.cache_block_size),
+            )?;
+
+            // Copy the data by cache blocks so we don't map a huge block at
+            // once.
+            let mut off = at;
+            while off < len {
+                let chunk_len = std::cmp::min(len - off, self.source.cache_block_size);
+                tail.view_mut(off - at, chunk_len)?
+                    .copy_from_slice(&self.view(off, chunk_len)?);
+                off += chunk_len;
+            }
+
+            // The length is aligned up, so the bytes between `at` and the new
+            // end would still hold the moved data. Clear them before
+            // truncating, so that no step can fail once this file is
+            // truncated. The bytes are restored if truncating fails.
+            let padding_len = std::cmp::min(try_align_add(at, self.source.alignment)?, len) - at;
+            let padding = self.read_vec(at, padding_len)?;
+            self.view_mut(at, padding_len)?.fill(0);
+
+            if let Err(err) = self.resize(at) {
+                let _ = self
+                    .view_mut(at, padding_len)
+                    .map(|mut view| view.copy_from_slice(&padding));
+                return Err(err);
+            }
+
+            Ok(tail)
+        };
+
+        split().cleanup(|| fs::remove_file(new_path))
+    }
+
+    pub fn compact(&mut self, live_ranges: &[Range<usize>]) -> io::Result<Vec<Range<usize>>> {
+        // Move the ranges in the order of their offsets. Then every range is
+        // moved towards the beginning and cannot overwrite a range that was
+        // not moved yet.
+        let mut order = (0..live_ranges.len()).collect::<Vec<_>>();
+        order.sort_by_key(|&index| live_ranges[index].start);
+
+        let mut new_ranges = vec![0..0; live_ranges.len()];
+        let mut end = 0;
+
+        for index in order {
+            let range = live_ranges[index].clone();
+            assert!(range.start >= end, "live ranges must not overlap");
+
+            let len = range.end - range.start;
+            self.copy_within(range.start, end, len)?;
+
+            new_ranges[index] = end..end + len;
+            end += len;
+        }
+
+        // The file cannot be empty.
+        self.resize(std::cmp::max(end, 1))?;
+
+        Ok(new_ranges)
     }
 
     pub fn copy_within(&mut self, src: usize, dst: usize, count: usize) -> io::Result<()> {
@@ -94,63 +566,154 @@ impl File {
             "underlying file was opened as read-only"
         );
 
-        if src + count > self.raw.len() {
-            panic!("src out of bounds");
+        let len = self.source.raw.len();
+
+        match src.checked_add(count) {
+            Some(end) if end <= len => {}
+            Some(_) => panic!("src out of bounds"),
+            None => panic!("offset + length overflows"),
         }
 
-        if dst + count > self.raw.len() {
-            panic!("dst out of bounds");
+        match dst.checked_add(count) {
+            Some(end) if end <= len => {}
+            Some(_) => panic!("dst out of bounds"),
+            None => panic!("offset + length overflows"),
         }
 
-        if count == 0 {
+        if count == 0 || src == dst {
             return Ok(());
         }
 
-        let src_view = self
-            .cache
-            .take(src, count)
-            .or_fetch(|off, len| self.fetch(off, len))?;
-
-        let dst_view = self
-            .cache
-            .take(dst, count)
-            .or_fetch(|off, len| self.fetch(off, len))?;
+        // The data is copied by chunks through a bounded buffer so we don't
+        // map a huge block at once. The destination is written through mutable
+        // views so that the modified blocks are marked as dirty.
+        let chunk_len = std::cmp::min(
+            std::cmp::min(count, self.source.cache_block_size),
+            COPY_BUFFER_SIZE,
+        );
+        let mut buf = vec![0; chunk_len];
 
-        if src >= dst && src < dst + count || dst >= src && dst < src + count {
-            // Pointers are overlapping.
+        let mut copy_chunk = |file: &mut Self, done: usize, len: usize| -> io::Result<()> {
+            let buf = &mut buf[..len];
+            buf.copy_from_slice(&file.view(src + done, len)?);
+            file.view_mut(dst + done, len)?.copy_from_slice(buf);
+            Ok(())
+        };
 
-            // SAFETY: Destination pointer is valid for writing because this
-            // method requires writable mode and that is checked at the top. We
-            // have pointers to bytes so the pointers is properly aligned
-            // trivially.
-            unsafe {
-                std::ptr::copy(src_view.as_ptr(), dst_view.as_ptr() as *mut _, count);
+        if dst > src {
+            // Copy back to front, otherwise the source bytes could be
+            // overwritten before they are read.
+            let mut remaining = count;
+            while remaining > 0 {
+                let len = std::cmp::min(remaining, chunk_len);
+                remaining -= len;
+                copy_chunk(self, remaining, len)?;
             }
         } else {
-            // Pointers are not overlapping.
-
-            // SAFETY: The safety of pointers is explained above. Moreover, we
-            // just checked the non-overlapping property.
-            unsafe {
-                std::ptr::copy_nonoverlapping(
-                    src_view.as_ptr(),
-                    dst_view.as_ptr() as *mut _,
-                    count,
-                );
+            // Copy front to back for the same reason.
+            let mut done = 0;
+            while done < count {
+                let len = std::cmp::min(count - done, chunk_len);
+                copy_chunk(self, done, len)?;
+                done += len;
             }
         }
 
         Ok(())
     }
 
+    pub fn swap(&mut self, a: usize, b: usize, count: usize) -> io::Result<()> {
+        assert_eq!(
+            self.mode,
+            Mode::Writable,
+            "underlying file was opened as read-only"
+        );
+
+        let len = self.source.raw.len();
+
+        match a.checked_add(count) {
+            Some(end) if end <= len => {}
+            Some(_) => panic!("a out of bounds"),
+            None => panic!("offset + length overflows"),
+        }
+
+        match b.checked_add(count) {
+            Some(end) if end <= len => {}
+            Some(_) => panic!("b out of bounds"),
+            None => panic!("offset + length overflows"),
+        }
+
+        if count > 0 && a < b + count && b < a + count {
+            panic!("regions overlap");
+        }
+
+        // The regions are swapped by chunks through a buffer on the stack. The
+        // chunk of the second region is swapped with the buffer in place, so
+        // only one buffer is needed.
+        let mut buf = [0; SWAP_BUFFER_SIZE];
+        let mut done = 0;
+
+        while done < count {
+            let len = std::cmp::min(count - done, SWAP_BUFFER_SIZE);
+            let buf = &mut buf[..len];
+
+            buf.copy_from_slice(&self.view(a + done, len)?);
+            self.view_mut(b + done, len)?.swap_with_slice(buf);
+            self.view_mut(a + done, len)?.copy_from_slice(buf);
+
+            done += len;
+        }
+
+        Ok(())
+    }
+
+    pub fn fill(&mut self, value: u8, off: usize, len: usize) -> io::Result<()> {
+        assert_eq!(
+            self.mode,
+            Mode::Writable,
+            "underlying file was opened as read-only"
+        );
+
+        let end = self.check_file_bounds(off, len);
+
+        // Fill at most up to the end of the current block, so the views do
+        // not span multiple blocks.
+        let block_size = self.source.cache_block_size;
+        let mut pos = off;
+        while pos < end {
+            let len = std::cmp::min(end - pos, block_size - pos % block_size);
+            self.view_mut(pos, len)?.fill(value);
+            pos += len;
+        }
+
+        Ok(())
+    }
+
     pub fn view(&self, off: usize, len: usize) -> io::Result<ViewRef<'_>> {
         self.check_file_bounds(off, len);
+
+        if len == 0 {
+            // There is nothing to map.
+            return Ok(ViewRef::empty(&self.cache, off));
+        }
+
+        self.cache.record_request(len);
         self.cache
             .take(off, len)
             .or_fetch(|off, len| self.fetch(off, len))
     }
 
-    pub fn view_mut(&mut self, off: usize, len: usize) -> io::Result<ViewMut<'_>> {
+    pub fn try_view(&self, off: usize, len: usize) -> io::Result<ViewRef<'_>> {
+        self.try_check_file_bounds(off, len)?;
+        self.view(off, len)
+    }
+
+    pub fn try_view_mut(&self, off: usize, len: usize) -> io::Result<ViewMut<'_>> {
+        self.try_check_file_bounds(off, len)?;
+        self.view_mut(off, len)
+    }
+
+    pub fn view_mut(&self, off: usize, len: usize) -> io::Result<ViewMut<'_>> {
         assert_eq!(
             self.mode,
             Mode::Writable,
@@ -159,448 +722,3976 @@ impl File {
 
         self.check_file_bounds(off, len);
 
-        let raw = &self.raw;
-        let cache_block_size = self.cache_block_size;
-        let writable = self.mode.is_writable();
+        if len == 0 {
+            // There is nothing to map.
+            return Ok(ViewMut::empty(&self.cache, off));
+        }
+
+        self.cache.record_request(len);
+
+        let source = &self.source;
+        let request_len = self.cache.average_request();
 
         self.cache
-            .take_mut(off, len)
-            .or_fetch(|off, len| Self::fetch_impl(raw, cache_block_size, writable, off, len))
+            .take_mut(off, len)?
+            .or_fetch(|off, len| source.fetch(off, len, request_len))
     }
 
-    pub fn len(&self) -> usize {
-        self.raw.len()
-    }
+    pub fn read_vec(&self, off: usize, len: usize) -> io::Result<Vec<u8>> {
+        self.check_file_bounds(off, len);
 
-    pub fn cache_block_size(&self) -> usize {
-        self.cache_block_size
+        // Read the data by cache blocks so we don't map a huge block at once.
+        let mut buf = Vec::with_capacity(len);
+        while buf.len() < len {
+            let chunk_off = off + buf.len();
+            let chunk_len = std::cmp::min(len - buf.len(), self.source.cache_block_size);
+            buf.extend_from_slice(&self.view(chunk_off, chunk_len)?);
+        }
+
+        Ok(buf)
     }
 
-    fn check_file_bounds(&self, off: usize, len: usize) -> usize {
-        let end = off + len;
-        if end > self.raw.len() {
-            panic!("out of bounds");
-        } else {
-            end
+    pub fn eq_ranges(&self, a: usize, b: usize, count: usize) -> io::Result<bool> {
+        self.check_file_bounds(a, count);
+        self.check_file_bounds(b, count);
+
+        if a == b {
+            return Ok(true);
         }
-    }
 
-    fn fetch(&self, off: usize, len: usize) -> io::Result<os::RawView> {
-        Self::fetch_impl(
-            &self.raw,
-            self.cache_block_size,
-            self.mode.is_writable(),
-            off,
-            len,
-        )
-    }
+        // Compare the data by cache blocks so we don't map a huge block at
+        // once, stopping at the first mismatch.
+        let mut done = 0;
+        while done < count {
+            let len = std::cmp::min(count - done, self.source.cache_block_size);
+            if self.view(a + done, len)?.as_slice() != self.view(b + done, len)?.as_slice() {
+                return Ok(false);
+            }
+            done += len;
+        }
 
-    fn fetch_impl(
-        raw: &os::RawFile,
-        cache_block_size: usize,
-        writable: bool,
-        off: usize,
-        len: usize,
-    ) -> io::Result<os::RawView> {
-        // Determine the end of the block. We allocate a block of size at least
-        // the cache block size setting.
-        let end = std::cmp::max(align_add(off + len), cache_block_size);
-        // Align the offset.
-        let off = align_sub(off);
-        // Don't allocate a block that would exceed the end of the file.
-        let end = std::cmp::min(end, raw.len());
-        let len = end - off;
-        raw.view(off, len, writable)
+        Ok(true)
     }
-}
 
-impl fmt::Debug for File {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "File {{ .. }}")
+    pub fn read_vectored_at(&self, reqs: &mut [(usize, &mut [u8])]) -> io::Result<()> {
+        // Process the requests in the order of their offsets so that the
+        // requests in the same region hit the same cached block.
+        let mut order = (0..reqs.len()).collect::<Vec<_>>();
+        order.sort_by_key(|&index| reqs[index].0);
+
+        for index in order {
+            let (off, buf) = &mut reqs[index];
+            buf.copy_from_slice(&self.view(*off, buf.len())?);
+        }
+
+        Ok(())
     }
-}
 
-// SAFETY: All mutating operations take exclusive reference to File. It also
-// does not implement Copy nor Clone, so there is exactly one file descriptor
-// and cache for the underlying file.
-unsafe impl Send for File {}
-unsafe impl Sync for File {}
+    pub fn checksum_blocks<H: Hasher + Default>(&self, block: usize) -> io::Result<Vec<u64>> {
+        assert!(block > 0, "block must be greater than zero");
 
-/// Iterator over the bytes in the underlying file.
-///
-/// # Panics
-///
-/// If the iteration encounters an I/O error, the iterator simply panics.
-pub struct Iter<'a> {
-    file: &'a File,
-    view: ViewRef<'a>,
-    cur: usize,
-    cum: usize,
-}
+        let len = self.source.raw.len();
+        let mut digests = Vec::with_capacity(len.div_ceil(block));
+        let mut off = 0;
 
-impl<'a> Iter<'a> {
-    pub(crate) fn from_file(file: &'a File) -> io::Result<Self> {
-        let block_size = std::cmp::min(file.len(), file.cache_block_size());
-        let view = file.view(0, block_size)?;
+        while off < len {
+            let end = std::cmp::min(off + block, len);
+            let mut hasher = H::default();
 
-        Ok(Self {
-            file,
-            view,
-            cur: 0,
-            cum: 0,
-        })
+            // A block bigger than the cache block is hashed by parts, so that
+            // it is never mapped at once.
+            while off < end {
+                let part_len = std::cmp::min(end - off, self.source.cache_block_size);
+                hasher.write(&self.view(off, part_len)?);
+                off += part_len;
+            }
+
+            digests.push(hasher.finish());
+        }
+
+        Ok(digests)
     }
-}
 
-impl Iterator for Iter<'_> {
-    type Item = u8;
+    pub fn sample(&self, n_samples: usize, sample_len: usize) -> io::Result<Vec<u8>> {
+        let len = self.source.raw.len();
+        let sample_len = std::cmp::min(sample_len, len);
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.cum == self.file.len() {
-            return None;
+        if n_samples == 0 || sample_len == 0 {
+            return Ok(Vec::new());
         }
 
-        if self.cur == self.view.len() {
-            let block_size =
-                std::cmp::min(self.file.len() - self.cum, self.file.cache_block_size());
-            self.view = self.file.view(self.cur, block_size).unwrap();
-            self.cur = 0;
-        }
+        // The first sample is at the beginning and the last one at the end of
+        // the file, the rest are evenly spaced between them. There are only
+        // so many distinct windows, more samples would just repeat them.
+        let last_off = len - sample_len;
+        let n_samples = std::cmp::min(n_samples, last_off + 1);
+        let total = n_samples.checked_mul(sample_len).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "total length of the samples overflows",
+            )
+        })?;
+        let mut buf = Vec::with_capacity(total);
 
-        let byte = self.view[self.cur];
-        self.cur += 1;
-        self.cum += 1;
+        for i in 0..n_samples {
+            let off = if n_samples == 1 {
+                0
+            } else {
+                (i as u128 * last_off as u128 / (n_samples - 1) as u128) as usize
+            };
 
-        Some(byte)
-    }
-}
+            buf.extend_from_slice(&self.view(off, sample_len)?);
+        }
 
-fn fix_cache_block_size(cache_block_size: usize) -> usize {
-    if cache_block_size == 0 {
-        *ALIGNMENT
-    } else {
-        align_add(cache_block_size)
+        Ok(buf)
     }
-}
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
-enum Mode {
-    Writable,
-    Readonly,
-}
+    pub fn index_by<F>(&self, mut f: F) -> io::Result<Vec<usize>>
+    where
+        F: FnMut(&[u8]) -> usize,
+    {
+        let len = self.source.raw.len();
+        let mut offsets = Vec::new();
+        let mut off = 0;
 
-impl Mode {
-    pub fn is_writable(&self) -> bool {
+        while off < len {
+            // The view always starts at the current record, so a record header
+            // is never split by a block boundary. The rest of a long record
+            // may not fit into the view, though.
+            let view_len = std::cmp::min(len - off, self.source.cache_block_size);
+            let record_len = f(&self.view(off, view_len)?);
+
+            if record_len == 0 || record_len > len - off {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "invalid record length",
+                ));
+            }
+
+            offsets.push(off);
+            off += record_len;
+        }
+
+        Ok(offsets)
+    }
+
+    pub fn find(&self, needle: &[u8], from: usize) -> io::Result<Option<usize>> {
+        let len = self.source.raw.len();
+
+        if from > len {
+            panic!("from out of bounds");
+        }
+
+        if needle.is_empty() {
+            return Ok(Some(from));
+        }
+
+        self.find_in(&memchr::memmem::Finder::new(needle), from, len)
+    }
+
+    pub fn rfind(&self, needle: &[u8], to: usize) -> io::Result<Option<usize>> {
+        if to > self.source.raw.len() {
+            panic!("to out of bounds");
+        }
+
+        if needle.is_empty() {
+            return Ok(Some(to));
+        }
+
+        let finder = memchr::memmem::FinderRev::new(needle);
+        let needle_len = needle.len();
+
+        // Like in find_in, but the windows go from the end to the start.
+        let overlap = needle_len - 1;
+        let window = std::cmp::max(self.source.cache_block_size, 2 * needle_len);
+        let mut end = to;
+
+        while end >= needle_len {
+            let off = end.saturating_sub(window);
+            let view = self.view(off, end - off)?;
+
+            if let Some(pos) = finder.rfind(&view) {
+                return Ok(Some(off + pos));
+            }
+
+            if off == 0 {
+                break;
+            }
+
+            end = off + overlap;
+        }
+
+        Ok(None)
+    }
+
+    pub fn par_find(&self, needle: &[u8]) -> io::Result<Option<usize>> {
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        self.par_find_with(needle, threads)
+    }
+
+    fn par_find_with(&self, needle: &[u8], threads: usize) -> io::Result<Option<usize>> {
+        let len = self.source.raw.len();
+
+        if needle.is_empty() {
+            return Ok(Some(0));
+        }
+
+        let finder = memchr::memmem::Finder::new(needle);
+
+        // Each segment is searched by its own thread. It does not make sense
+        // to split the file into segments smaller than a cache block.
+        let min_segment = std::cmp::max(self.source.cache_block_size, 2 * needle.len());
+        let segments = (len / min_segment).clamp(1, threads);
+        let segment = len.div_ceil(segments);
+
+        let results = thread::scope(|scope| {
+            let workers = (0..segments)
+                .map(|i| {
+                    let finder = &finder;
+                    let start = i * segment;
+                    // The segment is extended by needle.len() - 1 bytes so
+                    // that the matches starting in the segment and spanning
+                    // into the next one are found as well.
+                    let end = std::cmp::min(start + segment + needle.len() - 1, len);
+                    scope.spawn(move || self.find_in(finder, start, end))
+                })
+                .collect::<Vec<_>>();
+
+            workers
+                .into_iter()
+                .map(|worker| worker.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+
+        // The segments are in order, so the first match is the lowest one.
+        for result in results {
+            if let Some(pos) = result? {
+                return Ok(Some(pos));
+            }
+        }
+
+        Ok(None)
+    }
+
+    // Finds the first occurrence of the needle that lies entirely in the range
+    // [from, to).
+    fn find_in(
+        &self,
+        finder: &memchr::memmem::Finder<'_>,
+        from: usize,
+        to: usize,
+    ) -> io::Result<Option<usize>> {
+        let needle_len = finder.needle().len();
+
+        // Consecutive windows overlap by needle.len() - 1 bytes so that the
+        // matches spanning the boundary between them are not missed. The
+        // window must be bigger than the overlap to make progress.
+        let overlap = needle_len - 1;
+        let window = std::cmp::max(self.source.cache_block_size, 2 * needle_len);
+        let mut off = from;
+
+        while to.saturating_sub(off) >= needle_len {
+            let end = std::cmp::min(off + window, to);
+            let view = self.view(off, end - off)?;
+
+            if let Some(pos) = finder.find(&view) {
+                return Ok(Some(off + pos));
+            }
+
+            if end == to {
+                break;
+            }
+
+            off = end - overlap;
+        }
+
+        Ok(None)
+    }
+
+    pub fn replace_all(&mut self, needle: &[u8], replacement: &[u8]) -> io::Result<usize> {
+        assert_eq!(
+            needle.len(),
+            replacement.len(),
+            "needle and replacement must have the same length"
+        );
+
+        if needle.is_empty() {
+            return Ok(0);
+        }
+
+        let mut count = 0;
+        let mut from = 0;
+        // Matches that fit into a single cache block together are written
+        // through a single mutable view.
+        let mut group = Vec::new();
+
+        while let Some(pos) = self.find(needle, from)? {
+            if let Some(&first) = group.first() {
+                if pos + needle.len() - first > self.source.cache_block_size {
+                    self.replace_group(&group, replacement)?;
+                    group.clear();
+                }
+            }
+
+            group.push(pos);
+            count += 1;
+            from = pos + needle.len();
+        }
+
+        if !group.is_empty() {
+            self.replace_group(&group, replacement)?;
+        }
+
+        Ok(count)
+    }
+
+    pub fn release_pages(&self, off: usize, len: usize) -> io::Result<()> {
+        self.check_file_bounds(off, len);
+
+        // The pages of private mappings hold the modifications, which would be
+        // lost.
+        if self.source.raw.is_private() || len == 0 {
+            return Ok(());
+        }
+
+        self.cache.release_pages(off, len)
+    }
+
+    pub fn prefetch(&self, off: usize, len: usize) -> io::Result<()> {
+        self.check_file_bounds(off, len);
+
+        let block_size = self.cache_block_size();
+        let end = off + len;
+        let mut pos = off;
+
+        // Map the range by blocks so that a long range does not end up in a
+        // single huge block. The advice applies to the page cache, so it is
+        // effective even if the blocks are evicted in the meantime.
+        while pos < end {
+            let block_len = std::cmp::min(end - pos, block_size - pos % block_size);
+            let view = self.view(pos, block_len)?;
+            self.cache.advise_willneed(&view)?;
+            pos += block_len;
+        }
+
+        Ok(())
+    }
+
+    pub fn warm(self: &Arc<Self>) {
+        // The thread owns a reference to the file, so the file stays open
+        // until the warming is finished.
+        let file = Arc::clone(self);
+
+        thread::spawn(move || {
+            let len = file.len();
+            let mut off = 0;
+
+            while off < len {
+                let block_len = std::cmp::min(file.source.cache_block_size, len - off);
+
+                // Fresh mappings are used so that the cache is not churned.
+                // Errors are ignored, the warming is just an optimization.
+                if let Ok(view) = file.source.raw.view(off, block_len, false) {
+                    for i in (0..block_len).step_by(PAGE_SIZE_HINT) {
+                        // SAFETY: The index is in bounds of the view.
+                        unsafe { std::ptr::read_volatile(view.as_ptr().add(i)) };
+                    }
+                }
+
+                off += block_len;
+            }
+        });
+    }
+
+    pub fn view_with_timeout(
+        self: &Arc<Self>,
+        off: usize,
+        len: usize,
+        timeout: Duration,
+    ) -> io::Result<ViewRef<'_>> {
+        self.check_file_bounds(off, len);
+
+        // Page faults cannot be interrupted, so we let a worker thread map the
+        // view and pre-touch all its pages. The worker owns a reference to the
+        // file, so it is fine to abandon it when the deadline passes.
+        let file = Arc::clone(self);
+        let (tx, rx) = mpsc::channel();
+
+        thread::spawn(move || {
+            let result = file.view(off, len).map(|view| {
+                for i in (0..view.len()).step_by(PAGE_SIZE_HINT) {
+                    // SAFETY: The index is in bounds of the view.
+                    unsafe { std::ptr::read_volatile(view.as_ptr().add(i)) };
+                }
+            });
+
+            // The receiver may have already given up.
+            let _ = tx.send(result);
+        });
+
+        match rx.recv_timeout(timeout) {
+            // The pages are now resident and the block is most probably still
+            // in the cache.
+            Ok(Ok(())) => self.view(off, len),
+            Ok(Err(error)) => Err(error),
+            Err(mpsc::RecvTimeoutError::Timeout) => Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "mapping the view timed out",
+            )),
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                Err(io::Error::other("mapping the view failed"))
+            }
+        }
+    }
+
+    pub fn set_cache_capacity(&mut self, capacity: NonZeroUsize) -> io::Result<()> {
+        self.cache.set_capacity(capacity)
+    }
+
+    pub fn trim(&mut self, target_blocks: usize) -> io::Result<usize> {
+        if self.source.raw.is_private() {
+            // Unmapping the private block would discard the modifications.
+            return Ok(0);
+        }
+
+        self.cache.trim(target_blocks)
+    }
+
+    pub fn pin(&mut self, off: usize, len: usize) -> io::Result<()> {
+        if len == 0 {
+            self.check_file_bounds(off, len);
+            return Ok(());
+        }
+
+        // Mapping the region puts its block into the cache, where it stays
+        // after the view is dropped.
+        let view = self.view(off, len)?;
+        self.cache.pin(&view)
+    }
+
+    pub fn unpin(&mut self, off: usize, len: usize) {
+        self.check_file_bounds(off, len);
+        self.cache.unpin(off, len);
+    }
+
+    pub fn set_on_fetch(&mut self, on_fetch: Option<FetchCallback>) {
+        self.source.on_fetch = on_fetch;
+    }
+
+    pub fn close(mut self) -> io::Result<()> {
+        let zeroized = if self.zeroize_on_drop {
+            // Do not zeroize again when dropped.
+            self.zeroize_on_drop = false;
+            self.zeroize()
+        } else {
+            Ok(())
+        };
+
+        let unmapped = self.cache.close();
+        let closed = self.source.raw.close();
+
+        zeroized.and(unmapped).and(closed)
+    }
+
+    pub fn persist(mut self) -> io::Result<PathBuf> {
+        assert_eq!(
+            self.mode,
+            Mode::Writable,
+            "underlying file was opened as read-only"
+        );
+
+        if self.source.raw.is_private() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "overlay cannot be persisted",
+            ));
+        }
+
+        // Zeroizing would destroy the data that are meant to be kept.
+        self.zeroize_on_drop = false;
+        self.cache.flush()?;
+
+        let path = match self.source.raw.persist()? {
+            Some(path) => path,
+            // Not a temporary, so it is registered.
+            None => self
+                .registration
+                .as_ref()
+                .map(|registration| registration.path().to_path_buf())
+                .ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::Unsupported, "the file has no path")
+                })?,
+        };
+
+        self.close().map(|_| path)
+    }
+
+    pub fn preallocate(&mut self, len: usize) -> io::Result<()> {
+        assert_eq!(
+            self.mode,
+            Mode::Writable,
+            "underlying file was opened as read-only"
+        );
+
+        self.check_file_bounds(0, len);
+        self.check_resizable()?;
+
+        if len == 0 {
+            return Ok(());
+        }
+
+        self.source.raw.preallocate(len)
+    }
+
+    pub fn punch_hole(&mut self, off: usize, len: usize) -> io::Result<()> {
+        assert_eq!(
+            self.mode,
+            Mode::Writable,
+            "underlying file was opened as read-only"
+        );
+
+        self.check_file_bounds(off, len);
+
+        if self.source.raw.is_private() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "overlay cannot have holes punched",
+            ));
+        }
+
+        if len == 0 {
+            return Ok(());
+        }
+
+        // Discard the cached blocks first, so that their dirty data are not
+        // flushed into the hole later.
+        self.cache.invalidate(off, len)?;
+        self.source.raw.punch_hole(off, len)
+    }
+
+    pub fn sync_file_range(&mut self, off: usize, len: usize) -> io::Result<()> {
+        assert_eq!(
+            self.mode,
+            Mode::Writable,
+            "underlying file was opened as read-only"
+        );
+
+        self.check_file_bounds(off, len);
+
+        // On Linux, sync_file_range writes the pages modified through the
+        // shared mappings too. Elsewhere, the mappings must be flushed first.
+        if cfg!(not(target_os = "linux")) {
+            self.cache.flush_range(off, len)?;
+        }
+
+        self.source.raw.sync_range(off, len)
+    }
+
+    pub fn write_vectored_direct(&mut self, off: usize, bufs: &[&[u8]]) -> io::Result<usize> {
+        assert_eq!(
+            self.mode,
+            Mode::Writable,
+            "underlying file was opened as read-only"
+        );
+
+        // The same slice can be passed many times, so even the sum of the
+        // lengths can overflow.
+        let total = bufs
+            .iter()
+            .try_fold(0usize, |total, buf| total.checked_add(buf.len()))
+            .unwrap_or_else(|| panic!("offset + length overflows"));
+        self.check_file_bounds(off, total);
+
+        if self.source.raw.is_private() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "overlay cannot be written directly",
+            ));
+        }
+
+        if total == 0 {
+            return Ok(0);
+        }
+
+        // Discard the cached blocks first, so that their dirty data are not
+        // flushed over the written data later, and the subsequent views map
+        // the written data.
+        self.cache.invalidate(off, total)?;
+
+        let mut bufs = bufs
+            .iter()
+            .copied()
+            .filter(|buf| !buf.is_empty())
+            .collect::<Vec<_>>();
+        let mut first = 0;
+        let mut done = 0;
+
+        while first < bufs.len() {
+            let mut written = self
+                .source
+                .raw
+                .write_vectored_at(off + done, &bufs[first..])?;
+
+            if written == 0 {
+                return Err(io::Error::from(io::ErrorKind::WriteZero));
+            }
+
+            done += written;
+
+            // Skip the buffers that were written completely and advance in the
+            // one that was written partially.
+            while written > 0 {
+                if written >= bufs[first].len() {
+                    written -= bufs[first].len();
+                    first += 1;
+                } else {
+                    bufs[first] = &bufs[first][written..];
+                    written = 0;
+                }
+            }
+        }
+
+        Ok(done)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.cache.flush()
+    }
+
+    pub fn clone_to(&self, dest: &Path) -> io::Result<Self> {
+        // The views read the same memory as the dirty blocks, but flushing
+        // them first means that the original file is consistent with the
+        // clone.
+        self.cache.flush()?;
+
+        // Create the file first so that an existing file is not overwritten
+        // and the clone is not considered as a temporary.
+        fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(dest)?;
+
+        // The new file is removed if any of the following steps fails.
+        let copy = || {
+            let len = self.source.raw.len();
+            let clone = Self::open_writable_with(dest, len, &OpenOptions::new())?;
+
+            // Copy the data by cache blocks so we don't map a huge block at
+            // once.
+            let mut off = 0;
+            while off < len {
+                let chunk_len = std::cmp::min(len - off, self.source.cache_block_size);
+                clone
+                    .view_mut(off, chunk_len)?
+                    .copy_from_slice(&self.view(off, chunk_len)?);
+                off += chunk_len;
+            }
+
+            Ok(clone)
+        };
+
+        copy().cleanup(|| fs::remove_file(dest))
+    }
+
+    pub fn flush_range(&mut self, off: usize, len: usize) -> io::Result<()> {
+        self.check_file_bounds(off, len);
+        self.cache.flush_range(off, len)
+    }
+
+    pub fn flush_and_release(&mut self, off: usize, len: usize) -> io::Result<()> {
+        assert_eq!(
+            self.mode,
+            Mode::Writable,
+            "underlying file was opened as read-only"
+        );
+
+        self.check_file_bounds(off, len);
+
+        if self.source.raw.is_private() {
+            // Unmapping the private block would discard the modifications.
+            return Ok(());
+        }
+
+        if len == 0 {
+            return Ok(());
+        }
+
+        // The blocks are flushed synchronously and unmapped, so the pages are
+        // clean and unused when the kernel is advised to drop them.
+        self.cache.invalidate(off, len)?;
+        self.source.raw.release(off, len)
+    }
+
+    pub fn set_on_grow(&mut self, on_grow: Option<GrowCallback>) {
+        self.on_grow = on_grow;
+    }
+
+    pub fn take_on_grow(&mut self) -> Option<GrowCallback> {
+        self.on_grow.take()
+    }
+
+    pub fn set_zeroize_on_drop(&mut self, enabled: bool) {
+        assert_eq!(
+            self.mode,
+            Mode::Writable,
+            "underlying file was opened as read-only"
+        );
+
+        self.zeroize_on_drop = enabled;
+    }
+
+    pub fn len(&self) -> usize {
+        self.source.raw.len()
+    }
+
+    pub fn record_size(&self) -> Option<usize> {
+        self.record_size
+    }
+
+    pub fn set_record_size(&mut self, record_size: usize) {
+        self.record_size = Some(record_size);
+    }
+
+    pub fn record_offset(&self, index: usize) -> usize {
+        self.record_size
+            .expect("record size is not set")
+            .checked_mul(index)
+            .expect("out of bounds")
+    }
+
+    pub fn raw(&self) -> &os::RawFile {
+        &self.source.raw
+    }
+
+    pub fn cache_block_size(&self) -> usize {
+        self.source.cache_block_size
+    }
+
+    // Resolves the possibly open range against the file, returning the offset
+    // and the length.
+    pub fn resolve_range<R: RangeBounds<usize>>(&self, range: R) -> (usize, usize) {
+        // A bound past `usize::MAX` cannot be within the file, so the overflow
+        // is reported as out of bounds.
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start
+                .checked_add(1)
+                .unwrap_or_else(|| panic!("out of bounds")),
+            Bound::Unbounded => 0,
+        };
+
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end
+                .checked_add(1)
+                .unwrap_or_else(|| panic!("out of bounds")),
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => self.len(),
+        };
+
+        if start > end {
+            panic!("out of bounds");
+        }
+
+        (start, end - start)
+    }
+
+    fn check_resizable(&self) -> io::Result<()> {
+        if self.source.raw.is_private() {
+            Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "overlay cannot be resized",
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn check_file_bounds(&self, off: usize, len: usize) -> usize {
+        match off.checked_add(len) {
+            Some(end) if end <= self.source.raw.len() => end,
+            Some(_) => panic!("out of bounds"),
+            None => panic!("offset + length overflows"),
+        }
+    }
+
+    fn try_check_file_bounds(&self, off: usize, len: usize) -> io::Result<usize> {
+        match off.checked_add(len) {
+            Some(end) if end <= self.source.raw.len() => Ok(end),
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "offset + length overflows",
+            )),
+            Some(_) => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "range of {} bytes at offset {} is out of bounds of the file of length {}",
+                    len,
+                    off,
+                    self.source.raw.len()
+                ),
+            )),
+        }
+    }
+
+    fn replace_group(&mut self, group: &[usize], replacement: &[u8]) -> io::Result<()> {
+        let first = group[0];
+        let end = group[group.len() - 1] + replacement.len();
+        let mut view = self.view_mut(first, end - first)?;
+
+        for pos in group {
+            let start = pos - first;
+            view[start..start + replacement.len()].copy_from_slice(replacement);
+        }
+
+        Ok(())
+    }
+
+    fn zeroize(&mut self) -> io::Result<()> {
+        // We use fresh mappings so we do not need to care about the state of
+        // the cache. The mappings are shared so all of them see the same
+        // memory.
+        let len = self.source.raw.len();
+        let mut off = 0;
+
+        while off < len {
+            let block_len = std::cmp::min(self.source.cache_block_size, len - off);
+            let view = self.source.raw.view(off, block_len, true)?;
+
+            // SAFETY: The view is writable and valid for block_len bytes. The
+            // subsequent flush takes the pointer, so the write cannot be
+            // optimized away.
+            unsafe { std::ptr::write_bytes(view.as_ptr() as *mut u8, 0, block_len) };
+
+            // Make sure that the zeros reach the storage.
+            view.flush()?;
+            off += block_len;
+        }
+
+        Ok(())
+    }
+
+    fn fetch(&self, off: usize, len: usize) -> io::Result<os::RawView> {
+        self.source.fetch(off, len, self.cache.average_request())
+    }
+}
+
+impl Source {
+    fn fetch(&self, off: usize, len: usize, request_len: usize) -> io::Result<os::RawView> {
+        // Align the offset.
+        let block_off = align_sub(off, self.alignment);
+        // Determine the end of the block. We allocate a block of size at least
+        // the cache block size setting.
+        let end = std::cmp::max(
+            try_align_add(off + len, self.alignment)?,
+            block_off + self.block_size(request_len),
+        );
+        let off = block_off;
+        // Don't allocate a block that would exceed the end of the file.
+        let end = std::cmp::min(end, self.raw.len());
+        let len = end - off;
+        let view = self.raw.view(off, len, self.writable)?;
+
+        if let Some(on_fetch) = &self.on_fetch {
+            on_fetch(off, len);
+        }
+
+        Ok(view)
+    }
+
+    fn block_size(&self, request_len: usize) -> usize {
+        if let Some(StridedHint { width, .. }) = self.strided {
+            // Map only the part around the access, the rest of the block would
+            // be most probably unused.
+            return width;
+        }
+
+        match self.adaptive {
+            // Map enough memory for a bunch of requests of the average size.
+            Some(AdaptiveBlockSize { min, max }) => {
+                let size = request_len.saturating_mul(ADAPTIVE_REQUESTS_PER_BLOCK);
+                saturating_align_add(size.clamp(min, max), self.alignment)
+            }
+            None => self.cache_block_size,
+        }
+    }
+}
+
+impl Drop for File {
+    fn drop(&mut self) {
+        if let Some(on_unflushed_drop) = self.on_unflushed_drop.as_ref() {
+            let dirty = self.cache.dirty_bytes();
+            if dirty > 0 {
+                on_unflushed_drop(dirty);
+            }
+        }
+
+        if self.zeroize_on_drop {
+            let _ = self.zeroize();
+        } else {
+            let _ = self.cache.flush();
+        }
+    }
+}
+
+impl fmt::Debug for File {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("File")
+            .field("path", &self.path)
+            .field("len", &self.len())
+            .field("temporary", &self.temporary)
+            .field("mode", &self.mode)
+            .field("cache_capacity", &self.cache.capacity())
+            .field("cache_block_size", &self.source.cache_block_size)
+            .finish_non_exhaustive()
+    }
+}
+
+// SAFETY: All mutating operations except creating mutable views take exclusive
+// reference to File. The cache ensures that the ranges of the mutable views do
+// not overlap any other view. It also does not implement Copy nor Clone, so
+// there is exactly one file descriptor and cache for the underlying file.
+unsafe impl Send for File {}
+unsafe impl Sync for File {}
+
+/// Iterator over the bytes in the underlying file.
+///
+/// # Panics
+///
+/// If the iteration encounters an I/O error, the iterator simply panics.
+pub struct Iter<'a> {
+    file: &'a File,
+    // The views at the front and the back cursor together with their offsets
+    // in the file.
+    front: Option<(usize, ViewRef<'a>)>,
+    back: Option<(usize, ViewRef<'a>)>,
+    // The range of the bytes that were not yielded yet.
+    start: usize,
+    end: usize,
+}
+
+impl<'a> Iter<'a> {
+    pub(crate) fn from_file(file: &'a File) -> io::Result<Self> {
+        let block_size = std::cmp::min(file.len(), file.cache_block_size());
+        let view = file.view(0, block_size)?;
+
+        Ok(Self {
+            file,
+            front: Some((0, view)),
+            back: None,
+            start: 0,
+            end: file.len(),
+        })
+    }
+}
+
+impl Iterator for Iter<'_> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.start == self.end {
+            return None;
+        }
+
+        let pos = self.start;
+        let in_view = match &self.front {
+            Some((off, view)) => *off <= pos && pos < off + view.len(),
+            None => false,
+        };
+
+        if !in_view {
+            // Drop the old view first so its block can be reused.
+            self.front = None;
+
+            let block_size = std::cmp::min(self.end - pos, self.file.cache_block_size());
+            self.front = Some((pos, self.file.view(pos, block_size).unwrap()));
+        }
+
+        let (off, view) = self.front.as_ref().unwrap();
+        let byte = view[pos - off];
+        self.start += 1;
+
+        Some(byte)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.end - self.start;
+        (remaining, Some(remaining))
+    }
+}
+
+impl DoubleEndedIterator for Iter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.start == self.end {
+            return None;
+        }
+
+        let pos = self.end - 1;
+        let in_view = match &self.back {
+            Some((off, view)) => *off <= pos && pos < off + view.len(),
+            None => false,
+        };
+
+        if !in_view {
+            // Drop the old view first so its block can be reused.
+            self.back = None;
+
+            // Load the block that ends at the cursor, but do not go before the
+            // front cursor.
+            let block_size = self.file.cache_block_size();
+            let off = std::cmp::max(self.start, (pos / block_size) * block_size);
+            let view = self.file.view(off, self.end - off).unwrap();
+            self.back = Some((off, view));
+        }
+
+        let (off, view) = self.back.as_ref().unwrap();
+        let byte = view[pos - off];
+        self.end -= 1;
+
+        Some(byte)
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
+/// Iterator over lines of the file, converted to strings lossily.
+///
+/// This struct is created by [`FileRef::lines_lossy`](crate::FileRef::lines_lossy).
+pub struct LinesLossy<'a> {
+    file: &'a File,
+    pos: usize,
+}
+
+impl<'a> LinesLossy<'a> {
+    pub(crate) fn from_file(file: &'a File) -> Self {
+        Self { file, pos: 0 }
+    }
+
+    fn next_line(&mut self) -> io::Result<String> {
+        let len = self.file.len();
+        let mut line = Vec::new();
+
+        // Scan the file by cache blocks until the newline character is found.
+        // The line may span multiple blocks.
+        while self.pos < len {
+            let block_size = std::cmp::min(len - self.pos, self.file.cache_block_size());
+            let view = self.file.view(self.pos, block_size)?;
+
+            match memchr::memchr(b'\n', &view) {
+                Some(end) => {
+                    line.extend_from_slice(&view[..end]);
+                    self.pos += end + 1;
+                    break;
+                }
+                None => {
+                    line.extend_from_slice(&view);
+                    self.pos += block_size;
+                }
+            }
+        }
+
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+
+        Ok(String::from_utf8_lossy(&line).into_owned())
+    }
+}
+
+impl Iterator for LinesLossy<'_> {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos == self.file.len() {
+            return None;
+        }
+
+        let line = self.next_line();
+
+        if line.is_err() {
+            // Do not try again after an error.
+            self.pos = self.file.len();
+        }
+
+        Some(line)
+    }
+}
+
+/// Iterator over the views of the cache blocks covering the file.
+///
+/// This struct is created by [`FileRef::blocks`](crate::FileRef::blocks).
+pub struct Blocks<'a> {
+    file: &'a File,
+    pos: usize,
+    block_size: usize,
+}
+
+impl<'a> Blocks<'a> {
+    pub(crate) fn from_file(file: &'a File) -> Self {
+        Self {
+            file,
+            pos: 0,
+            block_size: file.cache_block_size(),
+        }
+    }
+}
+
+impl<'a> Iterator for Blocks<'a> {
+    type Item = io::Result<(usize, ViewRef<'a>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let len = self.file.len();
+
+        if self.pos == len {
+            return None;
+        }
+
+        let off = self.pos;
+        let block_size = std::cmp::min(len - off, self.block_size);
+
+        match self.file.view(off, block_size) {
+            Ok(view) => {
+                self.pos += block_size;
+                Some(Ok((off, view)))
+            }
+            Err(error) => {
+                // Do not try again after an error.
+                self.pos = len;
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+/// Iterator over the views of fixed-size chunks of the file.
+///
+/// This struct is created by [`FileRef::chunks`](crate::FileRef::chunks).
+pub struct Chunks<'a> {
+    file: &'a File,
+    pos: usize,
+    chunk_len: usize,
+}
+
+impl<'a> Chunks<'a> {
+    pub(crate) fn from_file(file: &'a File, chunk_len: usize) -> io::Result<Self> {
+        if chunk_len == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "chunk length must be greater than zero",
+            ));
+        }
+
+        Ok(Self {
+            file,
+            pos: 0,
+            chunk_len,
+        })
+    }
+}
+
+impl<'a> Iterator for Chunks<'a> {
+    type Item = io::Result<ViewRef<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let len = self.file.len();
+
+        if self.pos == len {
+            return None;
+        }
+
+        let chunk_len = std::cmp::min(len - self.pos, self.chunk_len);
+
+        match self.file.view(self.pos, chunk_len) {
+            Ok(view) => {
+                self.pos += chunk_len;
+                Some(Ok(view))
+            }
+            Err(error) => {
+                // Do not try again after an error.
+                self.pos = len;
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+/// Iterator over the views of fixed-size chunks of the file in reverse order.
+///
+/// This struct is created by
+/// [`FileRef::iter_rev_chunks`](crate::FileRef::iter_rev_chunks).
+pub struct RevChunks<'a> {
+    file: &'a File,
+    end: usize,
+    chunk_len: usize,
+}
+
+impl<'a> RevChunks<'a> {
+    pub(crate) fn from_file(file: &'a File, chunk_len: usize) -> io::Result<Self> {
+        if chunk_len == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "chunk length must be greater than zero",
+            ));
+        }
+
+        Ok(Self {
+            file,
+            end: file.len(),
+            chunk_len,
+        })
+    }
+}
+
+impl<'a> Iterator for RevChunks<'a> {
+    type Item = io::Result<ViewRef<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.end == 0 {
+            return None;
+        }
+
+        // The chunks start at multiples of the chunk length, so only the chunk
+        // at the end of the file may be shorter.
+        let off = align_sub(self.end - 1, self.chunk_len);
+
+        match self.file.view(off, self.end - off) {
+            Ok(view) => {
+                self.end = off;
+                Some(Ok(view))
+            }
+            Err(error) => {
+                // Do not try again after an error.
+                self.end = 0;
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+// How many requests of the average length should fit into an adaptively-sized
+// block.
+const ADAPTIVE_REQUESTS_PER_BLOCK: usize = 16;
+
+// The maximum size of the buffer used for copying the data within the file.
+const COPY_BUFFER_SIZE: usize = 1024 * 1024;
+
+// The size of the buffer on the stack used for swapping two regions of the
+// file.
+const SWAP_BUFFER_SIZE: usize = 4096;
+
+// The smallest page size on the supported platforms. Touching one byte per this
+// many bytes is enough to fault in all pages of a mapping.
+const PAGE_SIZE_HINT: usize = 4096;
+
+fn fix_cache_block_size(cache_block_size: usize, alignment: usize) -> usize {
+    if cache_block_size == 0 {
+        alignment
+    } else {
+        saturating_align_add(cache_block_size, alignment)
+    }
+}
+
+fn fix_adaptive_block_size(
+    adaptive: Option<AdaptiveBlockSize>,
+    alignment: usize,
+) -> Option<AdaptiveBlockSize> {
+    adaptive.map(|AdaptiveBlockSize { min, max }| {
+        let min = fix_cache_block_size(min, alignment);
+        let max = std::cmp::max(fix_cache_block_size(max, alignment), min);
+        AdaptiveBlockSize { min, max }
+    })
+}
+
+fn fix_strided_hint(options: &OpenOptions, alignment: usize) -> Option<StridedHint> {
+    let cache_block_size = fix_cache_block_size(options.cache_block_size, alignment);
+
+    options
+        .strided_hint
+        .filter(|hint| hint.stride > cache_block_size)
+        .map(|StridedHint { stride, width }| StridedHint {
+            stride,
+            width: saturating_align_add(std::cmp::max(width, 1), alignment),
+        })
+}
+
+fn block_alignment(options: &OpenOptions) -> usize {
+    let alignment = match options.huge_pages {
+        HugePages::Never => *ALIGNMENT,
+        // The huge page size is a multiple of the normal granularity, so using
+        // it is fine even if we eventually fall back to normal pages.
+        HugePages::Prefer | HugePages::Require => {
+            std::cmp::max(os::get_huge_page_size().unwrap_or(*ALIGNMENT), *ALIGNMENT)
+        }
+    };
+
+    match options.alignment {
+        Some(custom) => saturating_align_add(custom, alignment),
+        None => alignment,
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Mode {
+    Writable,
+    Readonly,
+}
+
+impl Mode {
+    pub fn is_writable(&self) -> bool {
         match self {
             Mode::Writable => true,
             Mode::Readonly => false,
         }
     }
-}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::AccessPattern;
+    use std::fs;
+    use std::io::Write;
+    use std::mem;
+    use std::path::PathBuf;
+
+    fn should_panic<F: FnOnce() -> R + std::panic::UnwindSafe, R: std::fmt::Debug>(
+        body: F,
+        message: &str,
+    ) {
+        let result = std::panic::catch_unwind(body).map_err(|m| m.downcast::<&str>().unwrap());
+
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err(), Box::new(message));
+    }
+
+    fn should_fail_assert<F: FnOnce() -> R + std::panic::UnwindSafe, R: std::fmt::Debug>(
+        body: F,
+        message: &str,
+    ) {
+        let result = std::panic::catch_unwind(body).map_err(|m| m.downcast::<String>().unwrap());
+
+        assert!(result.is_err());
+        assert!(result.unwrap_err().contains(message));
+    }
+
+    fn pb(path: &str) -> PathBuf {
+        PathBuf::from(path)
+    }
+
+    #[test]
+    fn cache_consistency_after_resize() {
+        let alignment = os::get_alignment();
+
+        let mut file = File::open_writable(
+            pb("cache_consistency_after_resize.tmp").as_path(),
+            512,
+            2,
+            alignment,
+        )
+        .unwrap();
+
+        // Write a byte to the first position.
+        file.view_mut(0, 1).unwrap()[0] = 3;
+
+        // Resize the file for the first time. This should allocate cache to be
+        // of full cache capacity.
+        file.resize(alignment).unwrap();
+
+        // Write a different byte to the first position.
+        file.view_mut(0, alignment).unwrap()[0] = 5;
+
+        // Resize the file such that it is forced to reallocate cache on slice request to the newly allocated region.
+        file.resize(2 * alignment).unwrap();
+
+        // Access the new region to force cache reallocation.
+        file.view(alignment, alignment).unwrap();
+
+        // Access the original region to force cache reallocation again.
+        let byte = file.view(0, alignment).unwrap()[0];
+
+        assert_eq!(byte, 5);
+    }
+
+    #[test]
+    fn temp_strategy() {
+        let path = pb("temp_strategy_delete_on_drop.tmp");
+        let file = File::open_writable(&path, 16, 1, 16).unwrap();
+        #[cfg(unix)]
+        assert!(!path.exists());
+        mem::drop(file);
+        assert!(!path.exists());
+
+        let path = pb("temp_strategy_delete_on_close.tmp");
+        let file = File::open_writable_with(
+            &path,
+            16,
+            OpenOptions::new().temp_strategy(TempStrategy::DeleteOnClose),
+        )
+        .unwrap();
+        assert!(path.exists());
+        mem::drop(file);
+        assert!(!path.exists());
+
+        let path = pb("temp_strategy_keep.tmp");
+        let file = File::open_writable_with(
+            &path,
+            16,
+            OpenOptions::new().temp_strategy(TempStrategy::Keep),
+        )
+        .unwrap();
+        assert!(path.exists());
+        mem::drop(file);
+        assert!(path.exists());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn resize_failure() {
+        let alignment = os::get_alignment();
+        let mut file = File::open_writable(
+            pb("resize_failure.tmp").as_path(),
+            2 * alignment,
+            2,
+            alignment,
+        )
+        .unwrap();
+
+        file.view_mut(0, 8).unwrap().copy_from_slice(b"survives");
+
+        // A leaked view makes the resize fail regardless of the file system.
+        mem::forget(file.view(alignment, 4).unwrap());
+        assert!(file.resize(4 * alignment).is_err());
+
+        // The file is still usable.
+        assert_eq!(file.len(), 2 * alignment);
+        assert_eq!(file.view(0, 8).unwrap().as_slice(), b"survives");
+        file.view_mut(8, 4).unwrap().copy_from_slice(b"more");
+        assert_eq!(file.view(0, 12).unwrap().as_slice(), b"survivesmore");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn as_fd() {
+        let file = File::open_writable(pb("as_fd.tmp").as_path(), 16, 1, 16).unwrap();
+        let len = fs::File::from(file.raw().as_fd().try_clone_to_owned().unwrap())
+            .metadata()
+            .unwrap()
+            .len();
+
+        assert_eq!(len as usize, file.len());
+    }
+
+    #[test]
+    fn ensure_len() {
+        let alignment = os::get_alignment();
+        let mut file = File::open_writable(pb("ensure_len.tmp").as_path(), 1, 2, 512).unwrap();
+
+        assert_eq!(file.len(), alignment);
+
+        file.ensure_len(alignment + 1).unwrap();
+        assert_eq!(file.len(), 2 * alignment);
+
+        // Never shrinks.
+        file.ensure_len(1).unwrap();
+        assert_eq!(file.len(), 2 * alignment);
+
+        file.ensure_len(2 * alignment).unwrap();
+        assert_eq!(file.len(), 2 * alignment);
+    }
+
+    #[test]
+    fn generation() {
+        let alignment = os::get_alignment();
+        let mut file =
+            File::open_writable(pb("generation.tmp").as_path(), 2 * alignment, 2, alignment)
+                .unwrap();
+
+        let generation = file.generation();
+
+        file.view_mut(alignment, 8)
+            .unwrap()
+            .copy_from_slice(b"modified");
+        file.resize(3 * alignment).unwrap();
+        assert_eq!(file.generation(), generation);
+
+        file.cache.invalidate(alignment, 8).unwrap();
+        assert_ne!(file.generation(), generation);
+        assert!(!file.cache.holds(alignment, 8));
+    }
+
+    #[test]
+    fn copy_within_overlapping() {
+        let mut file =
+            File::open_writable(pb("copy_within_overlapping.tmp").as_path(), 512, 2, 512).unwrap();
+
+        file.view_mut(0, 8).unwrap()[0..8].copy_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+
+        file.copy_within(0, 4, 8).unwrap();
+
+        assert_eq!(
+            &file.view(0, 12).unwrap()[0..12],
+            &[1, 2, 3, 4, 1, 2, 3, 4, 5, 6, 7, 8]
+        );
+    }
+
+    #[test]
+    fn copy_within_non_overlapping() {
+        let alignment = os::get_alignment();
+
+        let mut file = File::open_writable(
+            pb("copy_within_non_overlapping.tmp").as_path(),
+            4 * alignment,
+            2,
+            alignment,
+        )
+        .unwrap();
+
+        file.view_mut(0, 8).unwrap()[0..8].copy_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+
+        file.copy_within(0, 2 * alignment, 8).unwrap();
+
+        assert_eq!(&file.view(0, 8).unwrap()[0..8], &[1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(
+            &file.view(2 * alignment, 8).unwrap()[0..8],
+            &[1, 2, 3, 4, 5, 6, 7, 8]
+        );
+    }
+
+    #[test]
+    fn compact() {
+        let alignment = os::get_alignment();
+        let mut file =
+            File::open_writable(pb("compact.tmp").as_path(), 4 * alignment, 2, alignment).unwrap();
+
+        let live = [
+            3 * alignment..3 * alignment + 3,
+            10..20,
+            alignment - 5..2 * alignment + 5,
+        ];
+
+        for (i, range) in live.iter().enumerate() {
+            file.view_mut(range.start, range.end - range.start)
+                .unwrap()
+                .iter_mut()
+                .for_each(|byte| *byte = i as u8 + 1);
+        }
+
+        let new_ranges = file.compact(&live).unwrap();
+
+        assert_eq!(
+            new_ranges,
+            vec![alignment + 20..alignment + 23, 0..10, 10..alignment + 20]
+        );
+        assert_eq!(file.len(), 2 * alignment);
+
+        for (i, range) in new_ranges.into_iter().enumerate() {
+            assert!(file
+                .view(range.start, range.end - range.start)
+                .unwrap()
+                .iter()
+                .all(|&byte| byte == i as u8 + 1));
+        }
+    }
+
+    #[test]
+    fn copy_within_matrix() {
+        let alignment = os::get_alignment();
+        let len = 8 * alignment;
+        let count = 3 * alignment + 5;
+
+        let cases = [
+            ("forward-overlap", alignment + 3, 7),
+            ("backward-overlap", 7, alignment + 3),
+            ("adjacent-forward", count + 1, 1),
+            ("adjacent-backward", 1, count + 1),
+            ("disjoint-forward", len - count, 2),
+            ("disjoint-backward", 2, len - count),
+        ];
+
+        for (name, src, dst) in cases.iter().copied() {
+            let mut file = File::open_writable(
+                pb(&format!("copy_within_matrix_{}.tmp", name)).as_path(),
+                len,
+                2,
+                alignment,
+            )
+            .unwrap();
+
+            let mut expected = (0..len).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+            for off in (0..len).step_by(alignment) {
+                file.view_mut(off, alignment)
+                    .unwrap()
+                    .copy_from_slice(&expected[off..off + alignment]);
+            }
+
+            file.copy_within(src, dst, count).unwrap();
+            expected.copy_within(src..src + count, dst);
+
+            assert_eq!(file.read_vec(0, len).unwrap(), expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn copy_within_shift_by_one() {
+        // The source and the destination overlap in all but one byte and the
+        // count spans several blocks, with a single block in the cache.
+        let alignment = os::get_alignment();
+        let len = 6 * alignment;
+        let count = 5 * alignment - 3;
+
+        for (src, dst) in [(2, 1), (1, 2)].iter().copied() {
+            let mut file = File::open_writable(
+                pb(&format!("copy_within_shift_by_one_{}.tmp", src)).as_path(),
+                len,
+                1,
+                alignment,
+            )
+            .unwrap();
+
+            let mut expected = (0..len).map(|i| (i % 253) as u8).collect::<Vec<_>>();
+            for off in (0..len).step_by(alignment) {
+                file.view_mut(off, alignment)
+                    .unwrap()
+                    .copy_from_slice(&expected[off..off + alignment]);
+            }
+
+            file.copy_within(src, dst, count).unwrap();
+            expected.copy_within(src..src + count, dst);
+
+            assert_eq!(file.read_vec(0, len).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn fill() {
+        let alignment = os::get_alignment();
+        let mut file =
+            File::open_writable(pb("fill.tmp").as_path(), 4 * alignment, 2, alignment).unwrap();
+
+        // Spans three blocks, starting and ending in the middle of one.
+        let off = alignment / 2;
+        let len = 2 * alignment + 10;
+        file.fill(0xAB, off, len).unwrap();
+        file.fill(0xCD, alignment, 0).unwrap();
+
+        let data = file.read_vec(0, 4 * alignment).unwrap();
+        assert!(data[..off].iter().all(|&byte| byte == 0));
+        assert!(data[off..off + len].iter().all(|&byte| byte == 0xAB));
+        assert!(data[off + len..].iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn swap() {
+        let alignment = os::get_alignment();
+        let mut file =
+            File::open_writable(pb("swap.tmp").as_path(), 4 * alignment, 2, alignment).unwrap();
+
+        let data = (0..4 * alignment)
+            .map(|i| (i % 251) as u8)
+            .collect::<Vec<_>>();
+        file.view_mut(0, 4 * alignment)
+            .unwrap()
+            .copy_from_slice(&data);
+
+        // Adjacent regions, longer than the swapping buffer.
+        let count = SWAP_BUFFER_SIZE + 10;
+        file.swap(1, 1 + count, count).unwrap();
+
+        let mut expected = data.clone();
+        expected[1..1 + 2 * count].rotate_left(count);
+        assert_eq!(file.read_vec(0, 4 * alignment).unwrap(), expected);
+
+        // Far apart regions, in the first and the last block.
+        file.swap(3 * alignment + 5, 7, 100).unwrap();
+        file.swap(0, alignment, 0).unwrap();
+
+        let (head, tail) = expected.split_at_mut(3 * alignment);
+        head[7..107].swap_with_slice(&mut tail[5..105]);
+        assert_eq!(file.read_vec(0, 4 * alignment).unwrap(), expected);
+    }
+
+    #[test]
+    fn swap_checks() {
+        let alignment = os::get_alignment();
+        let mut file =
+            File::open_writable(pb("swap_checks.tmp").as_path(), alignment, 2, alignment).unwrap();
+
+        should_panic(
+            std::panic::AssertUnwindSafe(|| file.swap(0, 10, 11)),
+            "regions overlap",
+        );
+        should_panic(
+            std::panic::AssertUnwindSafe(|| file.swap(10, 0, 11)),
+            "regions overlap",
+        );
+        should_panic(
+            std::panic::AssertUnwindSafe(|| file.swap(alignment, 0, 1)),
+            "a out of bounds",
+        );
+        should_panic(
+            std::panic::AssertUnwindSafe(|| file.swap(0, alignment, 1)),
+            "b out of bounds",
+        );
+
+        // Touching regions do not overlap.
+        file.swap(0, 10, 10).unwrap();
+    }
+
+    #[test]
+    fn eq_ranges() {
+        let alignment = os::get_alignment();
+        let file = File::open_writable(pb("eq_ranges.tmp").as_path(), 4 * alignment, 2, alignment)
+            .unwrap();
+
+        let data = (0..2 * alignment)
+            .map(|i| (i % 251) as u8)
+            .collect::<Vec<_>>();
+        file.view_mut(0, 2 * alignment)
+            .unwrap()
+            .copy_from_slice(&data);
+        file.view_mut(2 * alignment, 2 * alignment)
+            .unwrap()
+            .copy_from_slice(&data);
+
+        // Equal ranges spanning multiple blocks.
+        assert!(file.eq_ranges(0, 2 * alignment, 2 * alignment).unwrap());
+        assert!(file.eq_ranges(10, 2 * alignment + 10, alignment).unwrap());
+        assert!(file.eq_ranges(0, 3 * alignment, 0).unwrap());
+
+        // Mismatch in the last byte.
+        file.view_mut(4 * alignment - 1, 1).unwrap()[0] ^= 1;
+        assert!(!file.eq_ranges(0, 2 * alignment, 2 * alignment).unwrap());
+        assert!(file.eq_ranges(0, 2 * alignment, 2 * alignment - 1).unwrap());
+
+        // Overlapping ranges.
+        assert!(file.eq_ranges(5, 5, alignment).unwrap());
+        assert!(!file.eq_ranges(0, 1, alignment).unwrap());
+        file.view_mut(0, alignment).unwrap().fill(7);
+        assert!(file.eq_ranges(0, 1, alignment - 1).unwrap());
+
+        should_panic(
+            std::panic::AssertUnwindSafe(|| file.eq_ranges(0, 3 * alignment, 2 * alignment)),
+            "out of bounds",
+        );
+    }
+
+    #[test]
+    fn clone_to() {
+        let alignment = os::get_alignment();
+        let mut file =
+            File::open_writable(pb("clone_to.tmp").as_path(), 3 * alignment, 2, alignment).unwrap();
+
+        let data = (0..3 * alignment)
+            .map(|i| (i % 251) as u8)
+            .collect::<Vec<_>>();
+        file.view_mut(0, 3 * alignment)
+            .unwrap()
+            .copy_from_slice(&data);
+
+        let clone_path = pb("clone_to_clone.tmp");
+        let clone = file.clone_to(&clone_path).unwrap();
+        assert_eq!(clone.len(), 3 * alignment);
+        assert_eq!(clone.read_vec(0, 3 * alignment).unwrap(), data);
+
+        // The files are independent.
+        file.fill(0, 0, 3 * alignment).unwrap();
+        file.flush().unwrap();
+        assert_eq!(clone.read_vec(0, 3 * alignment).unwrap(), data);
+
+        // An existing file is never overwritten.
+        assert_eq!(
+            file.clone_to(&clone_path).unwrap_err().kind(),
+            io::ErrorKind::AlreadyExists
+        );
+
+        mem::drop(clone);
+        assert_eq!(fs::read(&clone_path).unwrap(), data);
+        fs::remove_file(&clone_path).unwrap();
+    }
+
+    #[test]
+    fn check_len_overflow() {
+        let alignment = os::get_alignment();
+        let path = pb("check_len_overflow.tmp");
+
+        let file = File::open_writable(path.as_path(), usize::MAX, 2, alignment);
+        let err = file.err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(err.to_string(), "length too large to align");
+        assert!(!path.exists());
+
+        // Already aligned, so it is refused by the operating system instead.
+        let file = File::open_writable(path.as_path(), usize::MAX - alignment + 1, 2, alignment);
+        assert!(file.is_err());
+
+        let mut file =
+            File::open_writable(pb("check_len_overflow2.tmp").as_path(), 512, 2, alignment)
+                .unwrap();
+
+        let err = file.resize(usize::MAX).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(err.to_string(), "length too large to align");
+        assert_eq!(file.len(), alignment);
+
+        assert!(file.resize(usize::MAX - alignment + 1).is_err());
+        assert_eq!(file.len(), alignment);
+        assert_eq!(file.read_vec(0, alignment).unwrap(), vec![0; alignment]);
+    }
+
+    #[test]
+    fn check_zero_len() {
+        let alignment = os::get_alignment();
+
+        let file = File::open_writable(pb("check_zero_len1.tmp").as_path(), 0, 2, alignment);
+        assert!(file.is_err());
+        assert_eq!(
+            file.unwrap_err().to_string(),
+            "len must be greater than zero"
+        );
+
+        let file = File::open_writable(pb("check_zero_len2.tmp").as_path(), 512, 2, 0);
+        assert!(file.is_ok());
+        assert_eq!(file.unwrap().cache_block_size(), alignment);
+
+        let mut file =
+            File::open_writable(pb("check_zero_len3.tmp").as_path(), 512, 2, alignment).unwrap();
+        let result = file.resize(0);
+        assert!(result.is_err());
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "new_len must be greater than zero"
+        );
+        mem::drop(file);
+
+        let file_path = pb("check_zero_len.tmp");
+        let std_file = fs::File::create(file_path.as_path()).unwrap();
+        mem::drop(std_file);
+
+        let file = File::open_readonly(file_path.as_path(), 2, alignment);
+        assert!(file.is_err());
+        assert_eq!(file.unwrap_err().to_string(), "file is empty");
+
+        let mut std_file = fs::File::create(file_path.as_path()).unwrap();
+        std_file.write_all(&[1, 2, 3, 4]).unwrap();
+        std_file.flush().unwrap();
+        mem::drop(std_file);
+
+        let file = File::open_readonly(file_path.as_path(), 2, 0);
+        assert!(file.is_ok());
+        assert_eq!(file.unwrap().cache_block_size(), alignment);
+
+        fs::remove_file(file_path.as_path()).unwrap();
+    }
+
+    #[test]
+    fn bounds_checks() {
+        let alignment = os::get_alignment();
+
+        // Writable access to the file that is supposed to be temporary.
+
+        let file = File::open_writable(pb("bounds_checks1.tmp").as_path(), alignment, 2, alignment)
+            .unwrap();
+        let _ = file.view(0, alignment);
+        mem::drop(file);
+
+        should_panic(
+            || {
+                let file = File::open_writable(
+                    pb("bounds_checks2.tmp").as_path(),
+                    alignment,
+                    2,
+                    alignment,
+                )
+                .unwrap();
+                let _ = file.view(0, alignment + 1);
+            },
+            "out of bounds",
+        );
+
+        should_panic(
+            || {
+                let mut file = File::open_writable(
+                    pb("bounds_checks3.tmp").as_path(),
+                    alignment,
+                    2,
+                    alignment,
+                )
+                .unwrap();
+                let _ = file.copy_within(alignment + 1, 0, 0);
+            },
+            "src out of bounds",
+        );
+
+        should_panic(
+            || {
+                let mut file = File::open_writable(
+                    pb("bounds_checks4.tmp").as_path(),
+                    alignment,
+                    2,
+                    alignment,
+                )
+                .unwrap();
+                let _ = file.copy_within(alignment, 0, 1);
+            },
+            "src out of bounds",
+        );
+
+        should_panic(
+            || {
+                let mut file = File::open_writable(
+                    pb("bounds_checks5.tmp").as_path(),
+                    alignment,
+                    2,
+                    alignment,
+                )
+                .unwrap();
+                let _ = file.copy_within(0, alignment + 1, 0);
+            },
+            "dst out of bounds",
+        );
+
+        should_panic(
+            || {
+                let mut file = File::open_writable(
+                    pb("bounds_checks6.tmp").as_path(),
+                    alignment,
+                    2,
+                    alignment,
+                )
+                .unwrap();
+                let _ = file.copy_within(0, alignment, 1);
+            },
+            "dst out of bounds",
+        );
+
+        should_panic(
+            || {
+                let mut file = File::open_writable(
+                    pb("bounds_checks7.tmp").as_path(),
+                    alignment,
+                    2,
+                    alignment,
+                )
+                .unwrap();
+                let _ = file.copy_within(usize::MAX, 0, 1);
+            },
+            "offset + length overflows",
+        );
+
+        should_panic(
+            || {
+                let file = File::open_writable(
+                    pb("bounds_checks8.tmp").as_path(),
+                    alignment,
+                    2,
+                    alignment,
+                )
+                .unwrap();
+                let _ = file.resolve_range(..=usize::MAX);
+            },
+            "out of bounds",
+        );
+
+        // Readonly access to the file of size 4.
+
+        let file_path = pb("bounds_checks.tmp");
+        let mut std_file = fs::File::create(file_path.as_path()).unwrap();
+        std_file.write_all(&[1, 2, 3, 4]).unwrap();
+        std_file.flush().unwrap();
+        mem::drop(std_file);
+
+        let file = File::open_readonly(file_path.as_path(), 2, alignment);
+
+        let file = file.unwrap();
+        let _ = file.view(0, 4);
+        mem::drop(file);
+
+        should_panic(
+            || {
+                let file = File::open_readonly(file_path.as_path(), 2, alignment).unwrap();
+                let _ = file.view(0, 5);
+            },
+            "out of bounds",
+        );
+
+        fs::remove_file(file_path.as_path()).unwrap();
+    }
+
+    #[test]
+    fn protection_checks() {
+        let alignment = os::get_alignment();
+
+        let file_path = pb("protection_checks.tmp");
+        let mut std_file = fs::File::create(file_path.as_path()).unwrap();
+        std_file.write_all(&[1, 2, 3, 4, 5, 6, 7, 8]).unwrap();
+        std_file.flush().unwrap();
+        mem::drop(std_file);
+
+        should_fail_assert(
+            || {
+                let file = File::open_readonly(file_path.as_path(), 2, alignment).unwrap();
+                let _ = file.view_mut(0, 8);
+            },
+            "underlying file was opened as read-only",
+        );
+
+        should_fail_assert(
+            || {
+                let mut file = File::open_readonly(file_path.as_path(), 2, alignment).unwrap();
+                let _ = file.resize(alignment);
+            },
+            "underlying file was opened as read-only",
+        );
+
+        should_fail_assert(
+            || {
+                let mut file = File::open_readonly(file_path.as_path(), 2, alignment).unwrap();
+                let _ = file.copy_within(0, 4, 4);
+            },
+            "underlying file was opened as read-only",
+        );
+
+        fs::remove_file(file_path.as_path()).unwrap();
+    }
+
+    #[test]
+    fn view_behind_alignment() {
+        let alignment = os::get_alignment();
+        let file = File::open_writable(
+            pb("view_behind_alignment.tmp").as_path(),
+            2 * alignment,
+            2,
+            alignment,
+        )
+        .unwrap();
+
+        assert!(file.view(alignment, alignment).is_ok());
+    }
+
+    #[test]
+    fn huge_pages_fallback() {
+        let file = File::open_writable_with(
+            pb("huge_pages_fallback.tmp").as_path(),
+            512,
+            OpenOptions::new()
+                .cache_capacity(2)
+                .huge_pages(HugePages::Prefer),
+        )
+        .unwrap();
+
+        // The length is aligned to the huge page size (if available).
+        assert_eq!(file.len() % file.source.alignment, 0);
+        assert_eq!(file.cache_block_size() % file.source.alignment, 0);
+
+        // Regular files do not support huge pages on most platforms, so this
+        // should have fallen back to normal pages.
+        file.view_mut(0, 4).unwrap().copy_from_slice(&[1, 2, 3, 4]);
+        assert_eq!(&file.view(0, 4).unwrap()[..], &[1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn view_with_timeout() {
+        let alignment = os::get_alignment();
+
+        let file_path = pb("view_with_timeout.tmp");
+        let mut std_file = fs::File::create(file_path.as_path()).unwrap();
+        std_file.write_all(&[1, 2, 3, 4, 5, 6, 7, 8]).unwrap();
+        std_file.flush().unwrap();
+        mem::drop(std_file);
+
+        let file = Arc::new(File::open_readonly(file_path.as_path(), 2, alignment).unwrap());
+
+        let view = file
+            .view_with_timeout(2, 4, Duration::from_secs(10))
+            .unwrap();
+        assert_eq!(&view[..], &[3, 4, 5, 6]);
+        mem::drop(view);
+        mem::drop(file);
+
+        fs::remove_file(file_path.as_path()).unwrap();
+    }
+
+    #[test]
+    fn zeroize_on_drop() {
+        let alignment = os::get_alignment();
+
+        // The file must exist so it is not temporary.
+        let file_path = pb("zeroize_on_drop.tmp");
+        let mut std_file = fs::File::create(file_path.as_path()).unwrap();
+        std_file.write_all(&[1, 2, 3, 4]).unwrap();
+        std_file.flush().unwrap();
+        mem::drop(std_file);
+
+        let mut file = File::open_writable(file_path.as_path(), 4, 2, alignment).unwrap();
+        file.set_zeroize_on_drop(true);
+        file.view_mut(4, 4).unwrap().copy_from_slice(&[5, 6, 7, 8]);
+        mem::drop(file);
+
+        let contents = fs::read(file_path.as_path()).unwrap();
+        assert!(contents.iter().all(|byte| *byte == 0));
+
+        fs::remove_file(file_path.as_path()).unwrap();
+    }
+
+    #[test]
+    fn read_vec_multiple_blocks() {
+        let alignment = os::get_alignment();
+
+        let file = File::open_writable(
+            pb("read_vec_multiple_blocks.tmp").as_path(),
+            3 * alignment,
+            2,
+            alignment,
+        )
+        .unwrap();
+
+        file.view_mut(alignment - 2, 4)
+            .unwrap()
+            .copy_from_slice(&[1, 2, 3, 4]);
+
+        assert_eq!(file.read_vec(alignment - 2, 4).unwrap(), vec![1, 2, 3, 4]);
+
+        let contents = file.read_vec(0, 3 * alignment).unwrap();
+        assert_eq!(contents.len(), 3 * alignment);
+        assert_eq!(&contents[alignment - 2..alignment + 2], &[1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn on_fetch_callback() {
+        use std::sync::Mutex;
+
+        let alignment = os::get_alignment();
+        let fetched = Arc::new(Mutex::new(Vec::new()));
+        let fetched_clone = Arc::clone(&fetched);
+
+        let file = File::open_writable_with(
+            pb("on_fetch_callback.tmp").as_path(),
+            4 * alignment,
+            OpenOptions::new()
+                .cache_capacity(2)
+                .cache_block_size(alignment)
+                .on_fetch(move |off, len| fetched_clone.lock().unwrap().push((off, len))),
+        )
+        .unwrap();
+
+        // Miss.
+        file.view(1, 2).unwrap();
+        // Hit.
+        file.view(3, 4).unwrap();
+        // Miss.
+        file.view_mut(2 * alignment + 1, 2).unwrap();
+
+        assert_eq!(
+            *fetched.lock().unwrap(),
+            vec![(0, alignment), (2 * alignment, alignment)]
+        );
+    }
+
+    #[test]
+    fn adaptive_block_size() {
+        use std::sync::Mutex;
+
+        let alignment = os::get_alignment();
+        let fetched = Arc::new(Mutex::new(Vec::new()));
+        let fetched_clone = Arc::clone(&fetched);
+
+        let file = File::open_writable_with(
+            pb("adaptive_block_size.tmp").as_path(),
+            64 * alignment,
+            OpenOptions::new()
+                .cache_capacity(1)
+                .adaptive_block_size(AdaptiveBlockSize {
+                    min: alignment,
+                    max: 8 * alignment,
+                })
+                .on_fetch(move |_, len| fetched_clone.lock().unwrap().push(len)),
+        )
+        .unwrap();
+
+        // Tiny requests map the minimal blocks.
+        file.view(0, 1).unwrap();
+        // Huge requests map the maximal blocks.
+        for i in 1..16 {
+            file.view(i * alignment, 2 * alignment).unwrap();
+        }
+        file.view(32 * alignment, 1).unwrap();
+
+        let fetched = fetched.lock().unwrap();
+        assert_eq!(fetched[0], alignment);
+        assert_eq!(*fetched.last().unwrap(), 8 * alignment);
+    }
+
+    #[test]
+    fn find_across_blocks() {
+        let path = pb("find_across_blocks.tmp");
+        let block = os::get_alignment();
+
+        {
+            let mut data = vec![0; 4 * block];
+            data[block - 2..block + 2].copy_from_slice(b"abcd");
+            data[3 * block..3 * block + 4].copy_from_slice(b"abcd");
+
+            let mut file = fs::File::create(&path).unwrap();
+            file.write_all(&data).unwrap();
+        }
+
+        let file = File::open_readonly(&path, 1, block).unwrap();
+
+        assert_eq!(file.find(b"abcd", 0).unwrap(), Some(block - 2));
+        assert_eq!(file.find(b"abcd", block - 1).unwrap(), Some(3 * block));
+        assert_eq!(file.find(b"abcd", 3 * block + 1).unwrap(), None);
+        assert_eq!(file.find(b"abce", 0).unwrap(), None);
+        assert_eq!(file.find(b"", 5).unwrap(), Some(5));
+
+        assert_eq!(file.rfind(b"abcd", 4 * block).unwrap(), Some(3 * block));
+        assert_eq!(file.rfind(b"abcd", 3 * block + 3).unwrap(), Some(block - 2));
+        assert_eq!(file.rfind(b"abcd", block + 2).unwrap(), Some(block - 2));
+        assert_eq!(file.rfind(b"abcd", block + 1).unwrap(), None);
+        assert_eq!(file.rfind(b"abce", 4 * block).unwrap(), None);
+        assert_eq!(file.rfind(b"", 5).unwrap(), Some(5));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn replace_all() {
+        let block = os::get_alignment();
+        let mut file = File::open_writable(&pb("replace_all.tmp"), 3 * block, 1, block).unwrap();
+
+        for &pos in &[0, 10, block - 2, 2 * block + 7] {
+            file.view_mut(pos, 4).unwrap().copy_from_slice(b"abcd");
+        }
+
+        assert_eq!(file.replace_all(b"abcd", b"wxyz").unwrap(), 4);
+
+        for &pos in &[0, 10, block - 2, 2 * block + 7] {
+            assert_eq!(file.view(pos, 4).unwrap().as_slice(), b"wxyz");
+        }
+
+        assert_eq!(file.find(b"abcd", 0).unwrap(), None);
+        assert_eq!(file.replace_all(b"abcd", b"wxyz").unwrap(), 0);
+    }
+
+    #[test]
+    fn replace_all_different_lengths() {
+        should_fail_assert(
+            || {
+                let mut file =
+                    File::open_writable(&pb("replace_all_different_lengths.tmp"), 16, 1, 16)
+                        .unwrap();
+                let _ = file.replace_all(b"abc", b"ab");
+            },
+            "needle and replacement must have the same length",
+        );
+    }
+
+    #[test]
+    fn aliasing() {
+        let path = pb("aliasing.tmp");
+        fs::write(&path, [1; 16]).unwrap();
+
+        {
+            let _first = File::open_readonly(&path, 1, 16).unwrap();
+            let _second = File::open_readonly(&path, 1, 16).unwrap();
+
+            let err = File::open_writable(&path, 16, 1, 16).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        }
+
+        {
+            let _writer = File::open_writable(&path, 16, 1, 16).unwrap();
+
+            let err = File::open_readonly(&path, 1, 16).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        }
+
+        // The handles are unregistered when dropped.
+        File::open_readonly(&path, 1, 16).unwrap();
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn split_off() {
+        let alignment = os::get_alignment();
+        let tail_path = pb("split_off_tail.tmp");
+        let _ = fs::remove_file(&tail_path);
+
+        let mut file =
+            File::open_writable(&pb("split_off.tmp"), 3 * alignment, 1, alignment).unwrap();
+        file.view_mut(alignment - 4, 8)
+            .unwrap()
+            .copy_from_slice(b"headtail");
+        file.view_mut(3 * alignment - 4, 4)
+            .unwrap()
+            .copy_from_slice(b"last");
+
+        let tail = file.split_off(alignment, &tail_path).unwrap();
+
+        assert_eq!(file.len(), alignment);
+        assert_eq!(tail.len(), 2 * alignment);
+        assert_eq!(file.view(alignment - 4, 4).unwrap().as_slice(), b"head");
+        assert_eq!(tail.view(0, 4).unwrap().as_slice(), b"tail");
+        assert_eq!(tail.view(2 * alignment - 4, 4).unwrap().as_slice(), b"last");
+
+        // The new file is not a temporary.
+        mem::drop(tail);
+        assert_eq!(
+            fs::metadata(&tail_path).unwrap().len(),
+            2 * alignment as u64
+        );
+
+        fs::remove_file(&tail_path).unwrap();
+
+        // Splitting at an unaligned offset zeroes the padding of this part.
+        let tail = file.split_off(alignment - 4, &tail_path).unwrap();
+
+        assert_eq!(file.len(), alignment);
+        assert_eq!(file.view(alignment - 4, 4).unwrap().as_slice(), &[0; 4]);
+        assert_eq!(tail.view(0, 4).unwrap().as_slice(), b"head");
+
+        mem::drop(tail);
+        fs::remove_file(&tail_path).unwrap();
+    }
+
+    #[test]
+    fn split_off_failure() {
+        let alignment = os::get_alignment();
+        let tail_path = pb("split_off_failure_tail.tmp");
+        let _ = fs::remove_file(&tail_path);
+
+        let mut file =
+            File::open_writable(&pb("split_off_failure.tmp"), 3 * alignment, 2, alignment).unwrap();
+        file.view_mut(alignment, alignment).unwrap().fill(1);
+
+        // A leaked view makes the final resize fail after the data was
+        // copied and the padding cleared, which must not leave the new file
+        // behind nor lose any data.
+        mem::forget(file.view(0, 4).unwrap());
+        assert!(file.split_off(alignment + 4, &tail_path).is_err());
+        assert!(!tail_path.exists());
+        assert_eq!(file.len(), 3 * alignment);
+        assert!(file
+            .read_vec(alignment, alignment)
+            .unwrap()
+            .iter()
+            .all(|&byte| byte == 1));
+    }
+
+    #[test]
+    fn on_unflushed_drop() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let alignment = os::get_alignment();
+        let unflushed = Arc::new(AtomicUsize::new(0));
+
+        let unflushed_clone = Arc::clone(&unflushed);
+        let options = OpenOptions::new()
+            .cache_capacity(2)
+            .cache_block_size(alignment)
+            .on_unflushed_drop(move |len| {
+                unflushed_clone.fetch_add(len, Ordering::SeqCst);
+            })
+            .clone();
+
+        let file = File::open_writable_with(&pb("on_unflushed_drop1.tmp"), 16, &options).unwrap();
+        file.view(0, 8).unwrap();
+        mem::drop(file);
+
+        // Nothing was modified.
+        assert_eq!(unflushed.load(Ordering::SeqCst), 0);
+
+        let file = File::open_writable_with(&pb("on_unflushed_drop2.tmp"), 16, &options).unwrap();
+        file.view_mut(0, 8).unwrap().copy_from_slice(b"modified");
+        mem::drop(file);
+
+        assert_eq!(unflushed.load(Ordering::SeqCst), alignment);
+
+        // Flushed blocks are not reported.
+        let mut file =
+            File::open_writable_with(&pb("on_unflushed_drop3.tmp"), 16, &options).unwrap();
+        file.view_mut(0, 8).unwrap().copy_from_slice(b"modified");
+        file.trim(0).unwrap();
+        mem::drop(file);
+
+        assert_eq!(unflushed.load(Ordering::SeqCst), alignment);
+    }
+
+    #[test]
+    fn sample() {
+        let path = pb("sample.tmp");
+        let alignment = os::get_alignment();
+
+        {
+            let data = (0..4 * alignment)
+                .map(|i| (i / alignment) as u8)
+                .collect::<Vec<_>>();
+            let mut file = fs::File::create(&path).unwrap();
+            file.write_all(&data).unwrap();
+        }
+
+        let file = File::open_readonly(&path, 1, alignment).unwrap();
+
+        assert_eq!(file.sample(4, 2).unwrap(), vec![0, 0, 1, 1, 2, 2, 3, 3]);
+        assert_eq!(file.sample(1, 3).unwrap(), vec![0, 0, 0]);
+        assert!(file.sample(0, 3).unwrap().is_empty());
+        assert_eq!(file.sample(1, 8 * alignment).unwrap().len(), 4 * alignment);
+        assert_eq!(
+            file.sample(usize::MAX, 4 * alignment - 1).unwrap().len(),
+            2 * (4 * alignment - 1)
+        );
+        assert_eq!(file.sample(usize::MAX / 2, 1).unwrap().len(), 4 * alignment);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn lines_lossy() {
+        let path = pb("lines_lossy.tmp");
+        let alignment = os::get_alignment();
+
+        let long_line = "x".repeat(2 * alignment);
+
+        {
+            let mut file = fs::File::create(&path).unwrap();
+            write!(file, "first\r\n{}\n\n", long_line).unwrap();
+            file.write_all(b"invalid \xff\nlast").unwrap();
+        }
+
+        let file = File::open_readonly(&path, 1, alignment).unwrap();
+        let lines = LinesLossy::from_file(&file)
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(
+            lines,
+            vec![
+                "first".to_string(),
+                long_line,
+                String::new(),
+                "invalid \u{fffd}".to_string(),
+                "last".to_string(),
+            ]
+        );
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn index_by() {
+        let path = pb("index_by.tmp");
+        let alignment = os::get_alignment();
+
+        // Length-prefixed records, one of them spanning multiple blocks.
+        let lens = [3, 2 * alignment, 1, 200];
+
+        {
+            let mut file = fs::File::create(&path).unwrap();
+            for &len in lens.iter() {
+                file.write_all(&(len as u32).to_le_bytes()).unwrap();
+                file.write_all(&vec![0xaa; len]).unwrap();
+            }
+        }
+
+        let file = File::open_readonly(&path, 2, alignment).unwrap();
+        let offsets = file
+            .index_by(|view| {
+                let mut header = [0; 4];
+                header.copy_from_slice(&view[..4]);
+                4 + u32::from_le_bytes(header) as usize
+            })
+            .unwrap();
+
+        assert_eq!(offsets, vec![0, 7, 2 * alignment + 11, 2 * alignment + 16]);
+
+        let err = file.index_by(|_| 0).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn header_footer() {
+        let path = pb("header_footer.tmp");
+
+        {
+            let mut file = fs::File::create(&path).unwrap();
+            file.write_all(b"HEAD--------TAIL").unwrap();
+        }
+
+        let file = crate::FileRef::new(&path).unwrap();
+
+        assert_eq!(file.header(4).unwrap().as_slice(), b"HEAD");
+        assert_eq!(file.footer(4).unwrap().as_slice(), b"TAIL");
+        assert_eq!(file.footer(16).unwrap().len(), 16);
+
+        should_panic(
+            std::panic::AssertUnwindSafe(|| file.footer(17).map(|_| ())),
+            "out of bounds",
+        );
+
+        mem::drop(file);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn read_vectored_at() {
+        let path = pb("read_vectored_at.tmp");
+        let alignment = os::get_alignment();
+
+        {
+            let data = (0..2 * alignment).map(|i| i as u8).collect::<Vec<_>>();
+            let mut file = fs::File::create(&path).unwrap();
+            file.write_all(&data).unwrap();
+        }
+
+        let fetches = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let fetches_clone = Arc::clone(&fetches);
+        let file = File::open_readonly_with(
+            &path,
+            OpenOptions::new()
+                .cache_capacity(1)
+                .cache_block_size(alignment)
+                .on_fetch(move |off, _| fetches_clone.lock().unwrap().push(off)),
+        )
+        .unwrap();
+
+        let mut a = [0; 4];
+        let mut b = [0; 4];
+        let mut c = [0; 4];
+        let mut reqs = [
+            (alignment + 8, &mut a[..]),
+            (2, &mut b[..]),
+            (alignment + 100, &mut c[..]),
+        ];
+
+        file.read_vectored_at(&mut reqs).unwrap();
+
+        assert_eq!(a, [8, 9, 10, 11]);
+        assert_eq!(b, [2, 3, 4, 5]);
+        assert_eq!(c, [100, 101, 102, 103]);
+
+        // The requests in the same block were read together despite the cache
+        // capacity of one block.
+        assert_eq!(*fetches.lock().unwrap(), vec![0, alignment]);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn on_grow() {
+        use std::sync::Mutex;
+
+        let alignment = os::get_alignment();
+        let grown = Arc::new(Mutex::new(Vec::new()));
+        let grown_clone = Arc::clone(&grown);
+
+        let mut file = FileMut::new("on_grow.tmp", alignment).unwrap();
+        file.on_grow(move |file, old_len, new_len| {
+            file.write_at(&(new_len / alignment).to_le_bytes(), 0)
+                .unwrap();
+            grown_clone.lock().unwrap().push((old_len, new_len));
+        });
+
+        file.resize(2 * alignment).unwrap();
+        file.ensure_len(alignment).unwrap();
+        file.ensure_len(3 * alignment).unwrap();
+        file.resize(alignment).unwrap();
+        file.resize_or(4 * alignment, || io::Error::from(io::ErrorKind::Other))
+            .unwrap();
+
+        assert_eq!(
+            *grown.lock().unwrap(),
+            vec![
+                (alignment, 2 * alignment),
+                (2 * alignment, 3 * alignment),
+                (alignment, 4 * alignment)
+            ]
+        );
+        assert_eq!(file.view(0, 1).unwrap()[0], 4);
+    }
+
+    #[test]
+    fn close() {
+        let alignment = os::get_alignment();
+
+        let path = pb("close_keep.tmp");
+        let file = File::open_writable_with(
+            &path,
+            2 * alignment,
+            OpenOptions::new()
+                .cache_block_size(alignment)
+                .temp_strategy(TempStrategy::Keep),
+        )
+        .unwrap();
+
+        file.view_mut(0, 4).unwrap().copy_from_slice(b"head");
+        file.view_mut(alignment, 4)
+            .unwrap()
+            .copy_from_slice(b"tail");
+        file.close().unwrap();
+
+        let contents = fs::read(&path).unwrap();
+        assert_eq!(&contents[..4], b"head");
+        assert_eq!(&contents[alignment..alignment + 4], b"tail");
+        fs::remove_file(&path).unwrap();
+
+        let path = pb("close_delete_on_close.tmp");
+        let file = File::open_writable_with(
+            &path,
+            16,
+            OpenOptions::new().temp_strategy(TempStrategy::DeleteOnClose),
+        )
+        .unwrap();
+        file.close().unwrap();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn view_empty() {
+        let alignment = os::get_alignment();
+        let file =
+            File::open_writable(pb("view_empty.tmp").as_path(), alignment, 2, alignment).unwrap();
+        let len = file.len();
+
+        assert!(file.view(0, 0).unwrap().is_empty());
+        assert!(file.view(len, 0).unwrap().is_empty());
+        assert!(file.view_mut(len, 0).unwrap().is_empty());
+
+        // Empty views do not touch the cache and can coexist with others.
+        assert_eq!(file.cache.lent(), 0);
+        assert_eq!(file.cache.available(), 0);
+        assert_eq!(file.cache.average_request(), 0);
+
+        let empty = file.view(len / 2, 0).unwrap();
+        let view = file.view(0, 4).unwrap();
+        mem::drop(empty);
+        assert_eq!(file.cache.lent(), 1);
+        mem::drop(view);
+
+        let mut view = file.view_mut(0, 4).unwrap();
+        view.copy_from_slice(b"abcd");
+        mem::drop(view);
+        assert!(file.view_mut(1, 0).unwrap().is_empty());
+        assert_eq!(&file.view(0, 4).unwrap()[..], b"abcd");
+
+        should_panic(
+            std::panic::AssertUnwindSafe(|| file.view(len + 1, 0).map(|_| ())),
+            "out of bounds",
+        );
+    }
+
+    #[test]
+    fn blocks() {
+        let alignment = os::get_alignment();
+        let path = pb("blocks.tmp");
+
+        {
+            let mut file = fs::File::create(&path).unwrap();
+            file.write_all(&vec![7; 2 * alignment + 10]).unwrap();
+        }
+
+        let file = File::open_readonly(&path, 4, alignment).unwrap();
+        let blocks = Blocks::from_file(&file)
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+
+        let offsets = blocks.iter().map(|(off, _)| *off).collect::<Vec<_>>();
+        let lens = blocks
+            .iter()
+            .map(|(_, view)| view.len())
+            .collect::<Vec<_>>();
+        assert_eq!(offsets, vec![0, alignment, 2 * alignment]);
+        assert_eq!(lens, vec![alignment, alignment, 10]);
+        assert!(blocks.iter().all(|(_, view)| view.iter().all(|b| *b == 7)));
+
+        // Each view corresponds to exactly one cached block.
+        assert_eq!(file.cache.lent(), 3);
+        mem::drop(blocks);
+        assert_eq!(file.cache.available(), 3);
+
+        mem::drop(file);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn overlay() {
+        let alignment = os::get_alignment();
+        let path = pb("overlay.tmp");
+        fs::write(&path, vec![1; 3 * alignment]).unwrap();
+
+        let base = File::open_readonly(&path, 2, alignment).unwrap();
+        let mut overlay = base.overlay().unwrap();
+
+        overlay
+            .view_mut(alignment - 2, 4)
+            .unwrap()
+            .copy_from_slice(&[2; 4]);
+        overlay.copy_within(0, 2 * alignment, 4).unwrap();
+        assert_eq!(overlay.trim(0).unwrap(), 0);
+
+        assert_eq!(&overlay.view(alignment - 2, 4).unwrap()[..], &[2; 4]);
+        assert_eq!(&base.view(alignment - 2, 4).unwrap()[..], &[1; 4]);
+
+        assert_eq!(
+            overlay.resize(alignment).unwrap_err().kind(),
+            io::ErrorKind::Unsupported
+        );
+        assert_eq!(overlay.len(), 3 * alignment);
+
+        overlay.close().unwrap();
+        mem::drop(base);
+
+        assert_eq!(fs::read(&path).unwrap(), vec![1; 3 * alignment]);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn open_private() {
+        let alignment = os::get_alignment();
+        let path = pb("open_private.tmp");
+        fs::write(&path, vec![1; 2 * alignment]).unwrap();
+
+        let mut file = File::open_private(&path).unwrap();
+        file.view_mut(alignment - 2, 4)
+            .unwrap()
+            .copy_from_slice(&[2; 4]);
+        file.flush().unwrap();
+        assert_eq!(&file.view(alignment - 2, 4).unwrap()[..], &[2; 4]);
+
+        // The file is registered as read, so it cannot be written meanwhile.
+        assert_eq!(
+            File::open_writable(&path, alignment, 1, alignment)
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::AlreadyExists
+        );
+
+        mem::drop(file);
+
+        assert_eq!(fs::read(&path).unwrap(), vec![1; 2 * alignment]);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn strided_hint() {
+        use std::sync::Mutex;
+
+        let alignment = os::get_alignment();
+        let fetched = Arc::new(Mutex::new(Vec::new()));
+        let fetched_clone = Arc::clone(&fetched);
+
+        let file = File::open_writable_with(
+            pb("strided_hint.tmp").as_path(),
+            64 * alignment,
+            OpenOptions::new()
+                .cache_capacity(2)
+                .cache_block_size(4 * alignment)
+                .strided_hint(StridedHint {
+                    stride: 8 * alignment,
+                    width: 16,
+                })
+                .on_fetch(move |off, len| fetched_clone.lock().unwrap().push((off, len))),
+        )
+        .unwrap();
+
+        for i in 0..8 {
+            file.view(i * 8 * alignment + 100, 16).unwrap();
+        }
+
+        // The access crossing the block boundary still gets all its bytes.
+        file.view(alignment - 8, 16).unwrap();
+
+        let fetched = fetched.lock().unwrap();
+        let expected = (0..8)
+            .map(|i| (i * 8 * alignment, alignment))
+            .chain(std::iter::once((0, 2 * alignment)))
+            .collect::<Vec<_>>();
+        assert_eq!(*fetched, expected);
+
+        // Dense strides are served by the whole blocks.
+        let file = File::open_writable_with(
+            pb("strided_hint_dense.tmp").as_path(),
+            16,
+            OpenOptions::new()
+                .cache_block_size(4 * alignment)
+                .strided_hint(StridedHint {
+                    stride: alignment,
+                    width: 16,
+                }),
+        )
+        .unwrap();
+        assert!(file.source.strided.is_none());
+    }
+
+    #[test]
+    fn sync_file_range() {
+        let alignment = os::get_alignment();
+        let path = pb("sync_file_range.tmp");
+        fs::write(&path, vec![0; 2 * alignment]).unwrap();
+
+        let mut file = File::open_writable(&path, 2 * alignment, 2, alignment).unwrap();
+        file.view_mut(alignment, 4)
+            .unwrap()
+            .copy_from_slice(b"sync");
+        file.sync_file_range(alignment, 4).unwrap();
+        file.sync_file_range(0, 2 * alignment).unwrap();
+
+        let contents = fs::read(&path).unwrap();
+        assert_eq!(&contents[alignment..alignment + 4], b"sync");
+
+        mem::drop(file);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn warm() {
+        let alignment = os::get_alignment();
+        let path = pb("warm.tmp");
+        fs::write(&path, vec![3; 4 * alignment]).unwrap();
+
+        let file = Arc::new(File::open_readonly(&path, 1, alignment).unwrap());
+        file.warm();
+
+        // The buffer is usable while warming and its cache is not affected.
+        assert_eq!(&file.view(2 * alignment, 4).unwrap()[..], &[3; 4]);
+        assert_eq!(file.cache.lent() + file.cache.available(), 1);
+
+        mem::drop(file);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn punch_hole() {
+        let alignment = os::get_alignment();
+        let mut file =
+            File::open_writable(pb("punch_hole.tmp").as_path(), 4 * alignment, 2, alignment)
+                .unwrap();
+
+        for i in 0..4 {
+            file.view_mut(i * alignment, alignment)
+                .unwrap()
+                .copy_from_slice(&vec![1; alignment]);
+        }
+
+        let generation = file.generation();
+
+        // Some file systems (e.g., tmpfs on old kernels) do not support it.
+        match file.punch_hole(alignment - 10, alignment + 20) {
+            Err(error) if error.raw_os_error() == Some(libc::EOPNOTSUPP) => return,
+            result => result.unwrap(),
+        }
+
+        assert_ne!(file.generation(), generation);
+        assert_eq!(file.len(), 4 * alignment);
+
+        let contents = file.read_vec(0, file.len()).unwrap();
+        assert!(contents[..alignment - 10].iter().all(|b| *b == 1));
+        assert!(contents[alignment - 10..2 * alignment + 10]
+            .iter()
+            .all(|b| *b == 0));
+        assert!(contents[2 * alignment + 10..].iter().all(|b| *b == 1));
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn preallocate() {
+        use std::os::unix::fs::MetadataExt;
+
+        let alignment = os::get_alignment();
+        let path = pb("preallocate.tmp");
+        fs::write(&path, b"data").unwrap();
+
+        let mut file = File::open_writable(&path, 16 * alignment, 2, alignment).unwrap();
+        let allocated = || fs::metadata(&path).unwrap().blocks() * 512;
+        assert!(allocated() < (16 * alignment) as u64);
+
+        file.preallocate(16 * alignment).unwrap();
+        assert!(allocated() >= (16 * alignment) as u64);
+        assert_eq!(&file.view(0, 4).unwrap()[..], b"data");
+
+        mem::drop(file);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn resolve_range() {
+        let alignment = os::get_alignment();
+        let file = File::open_writable(pb("resolve_range.tmp").as_path(), alignment, 1, alignment)
+            .unwrap();
+        let len = file.len();
+
+        assert_eq!(file.resolve_range(10..20), (10, 10));
+        assert_eq!(file.resolve_range(10..=20), (10, 11));
+        assert_eq!(file.resolve_range(100..), (100, len - 100));
+        assert_eq!(file.resolve_range(..50), (0, 50));
+        assert_eq!(file.resolve_range(..), (0, len));
+        assert_eq!(file.resolve_range(len..), (len, 0));
+
+        #[allow(clippy::reversed_empty_ranges)]
+        should_panic(
+            std::panic::AssertUnwindSafe(|| file.resolve_range(20..10)),
+            "out of bounds",
+        );
+    }
+
+    #[test]
+    fn custom_alignment() {
+        let alignment = os::get_alignment();
+        let file = File::open_writable_with(
+            pb("custom_alignment.tmp").as_path(),
+            1,
+            OpenOptions::new()
+                .cache_block_size(alignment)
+                .alignment(3 * alignment + 1),
+        )
+        .unwrap();
+
+        assert_eq!(file.source.alignment, 4 * alignment);
+        assert_eq!(file.len(), 4 * alignment);
+        assert_eq!(file.cache_block_size(), 4 * alignment);
+
+        let file = File::open_writable_with(
+            pb("custom_alignment_zero.tmp").as_path(),
+            1,
+            OpenOptions::new().alignment(0),
+        )
+        .unwrap();
+
+        assert_eq!(file.source.alignment, alignment);
+    }
+
+    #[test]
+    fn open_without_truncate() {
+        let alignment = os::get_alignment();
+        let path = pb("open_without_truncate.tmp");
+        let data = (0..3 * alignment + 100)
+            .map(|i| (i % 251) as u8)
+            .collect::<Vec<_>>();
+        fs::write(&path, &data).unwrap();
+
+        {
+            let file = File::open_writable_with(
+                path.as_path(),
+                alignment,
+                OpenOptions::new().truncate(false),
+            )
+            .unwrap();
+
+            // The size is rounded up to the alignment.
+            assert_eq!(file.len(), 4 * alignment);
+
+            let view = file.view(3 * alignment, 100).unwrap();
+            assert_eq!(view.as_slice(), &data[3 * alignment..]);
+        }
+
+        {
+            // The requested length wins if it is larger.
+            let file = File::open_writable_with(
+                path.as_path(),
+                5 * alignment,
+                OpenOptions::new().truncate(false),
+            )
+            .unwrap();
+
+            assert_eq!(file.len(), 5 * alignment);
+        }
+
+        {
+            let file =
+                File::open_writable_with(path.as_path(), alignment, &OpenOptions::new()).unwrap();
+            assert_eq!(file.len(), alignment);
+        }
+
+        assert_eq!(fs::metadata(&path).unwrap().len() as usize, alignment);
+        fs::remove_file(&path).unwrap();
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::fs;
-    use std::io::Write;
-    use std::mem;
-    use std::path::PathBuf;
+    #[test]
+    fn persist() {
+        for temp_strategy in [
+            TempStrategy::DeleteOnDrop,
+            TempStrategy::DeleteOnClose,
+            TempStrategy::Keep,
+        ] {
+            let path = pb(&format!("persist_{:?}.tmp", temp_strategy));
+            let mut file = File::open_writable_with(
+                path.as_path(),
+                100,
+                OpenOptions::new().temp_strategy(temp_strategy),
+            )
+            .unwrap();
+            let len = file.len();
 
-    fn should_panic<F: FnOnce() -> R + std::panic::UnwindSafe, R: std::fmt::Debug>(
-        body: F,
-        message: &str,
-    ) {
-        let result = std::panic::catch_unwind(body).map_err(|m| m.downcast::<&str>().unwrap());
+            file.view_mut(10, 9)
+                .unwrap()
+                .as_mut_slice()
+                .copy_from_slice(b"persisted");
+            file.set_zeroize_on_drop(true);
+
+            let persisted = file.persist().unwrap();
+            assert_eq!(
+                fs::canonicalize(persisted).unwrap(),
+                fs::canonicalize(&path).unwrap()
+            );
+
+            let data = fs::read(&path).unwrap();
+            assert_eq!(data.len(), len);
+            assert_eq!(&data[10..19], b"persisted");
+
+            // The file can be opened again.
+            let file = File::open_writable(path.as_path(), 1, 1, 1).unwrap();
+            assert_eq!(file.len(), len);
+            drop(file);
+
+            fs::remove_file(&path).unwrap();
+        }
+
+        let path = pb("persist_existing.tmp");
+        fs::write(&path, b"existing").unwrap();
+
+        let file = File::open_writable(path.as_path(), 1, 1, 1).unwrap();
+        assert_eq!(file.persist().unwrap(), fs::canonicalize(&path).unwrap());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn zero_cache_capacity() {
+        let options = OpenOptions::new().cache_capacity(0).clone();
+
+        let result = File::open_writable_with(pb("zero_cache_capacity.tmp").as_path(), 1, &options);
+        assert_eq!(
+            result.err().map(|err| err.kind()),
+            Some(io::ErrorKind::InvalidInput)
+        );
+
+        let result = File::open_readonly_with(pb("Cargo.toml").as_path(), &options);
+        assert_eq!(
+            result.err().map(|err| err.kind()),
+            Some(io::ErrorKind::InvalidInput)
+        );
+    }
+
+    #[test]
+    fn iter_double_ended() {
+        let alignment = os::get_alignment();
+        let path = pb("iter_double_ended.tmp");
+        let data = (0..3 * alignment + 100)
+            .map(|i| (i % 251) as u8)
+            .collect::<Vec<_>>();
+        fs::write(&path, &data).unwrap();
+
+        let file = File::open_readonly(path.as_path(), 2, alignment).unwrap();
+
+        assert_eq!(file_iter(&file).collect::<Vec<_>>(), data);
+
+        let mut expected = data.clone();
+        expected.reverse();
+        assert_eq!(file_iter(&file).rev().collect::<Vec<_>>(), expected);
+
+        // The cursors meet in the middle of a block.
+        let mut iter = file_iter(&file);
+        let mut front = Vec::new();
+        let mut back = Vec::new();
+        for _ in 0..alignment + 7 {
+            front.push(iter.next().unwrap());
+        }
+        while let Some(byte) = iter.next_back() {
+            back.push(byte);
+        }
+        assert_eq!(iter.next(), None);
+
+        back.reverse();
+        front.extend(back);
+        assert_eq!(front, data);
+
+        // Alternating between the ends.
+        let mut iter = file_iter(&file);
+        let mut front = Vec::new();
+        let mut back = Vec::new();
+        while let Some(byte) = iter.next() {
+            front.push(byte);
+            match iter.next_back() {
+                Some(byte) => back.push(byte),
+                None => break,
+            }
+        }
+
+        back.reverse();
+        front.extend(back);
+        assert_eq!(front, data);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn iter_len() {
+        let alignment = os::get_alignment();
+        let path = pb("iter_len.tmp");
+        fs::write(&path, vec![1; alignment + 10]).unwrap();
+
+        let file = File::open_readonly(path.as_path(), 2, alignment).unwrap();
+        let mut iter = file_iter(&file);
+        assert_eq!(iter.len(), alignment + 10);
+        assert_eq!(iter.size_hint(), (alignment + 10, Some(alignment + 10)));
+
+        iter.nth(alignment + 2).unwrap();
+        iter.next_back().unwrap();
+        assert_eq!(iter.len(), 6);
+
+        iter.by_ref().for_each(drop);
+        assert_eq!(iter.len(), 0);
+
+        drop(iter);
+        drop(file);
+        fs::remove_file(&path).unwrap();
+    }
+
+    fn file_iter(file: &File) -> Iter<'_> {
+        Iter::from_file(file).unwrap()
+    }
+
+    #[test]
+    fn chunks() {
+        let alignment = os::get_alignment();
+        let path = pb("chunks.tmp");
+        let data = (0..2 * alignment + 10)
+            .map(|i| (i % 251) as u8)
+            .collect::<Vec<_>>();
+        fs::write(&path, &data).unwrap();
+
+        let file = File::open_readonly(path.as_path(), 2, alignment).unwrap();
+
+        for chunk_len in [1, 7, alignment, alignment + 3, 10 * alignment] {
+            let chunks = Chunks::from_file(&file, chunk_len)
+                .unwrap()
+                .map(|chunk| chunk.unwrap().to_vec())
+                .collect::<Vec<_>>();
+
+            assert_eq!(chunks.len(), data.len().div_ceil(chunk_len));
+            assert!(chunks[..chunks.len() - 1]
+                .iter()
+                .all(|chunk| chunk.len() == chunk_len));
+            assert_eq!(chunks.concat(), data);
+        }
+
+        assert_eq!(
+            Chunks::from_file(&file, 0).err().map(|err| err.kind()),
+            Some(io::ErrorKind::InvalidInput)
+        );
+
+        drop(file);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn prefetch() {
+        let alignment = os::get_alignment();
+        let path = pb("prefetch.tmp");
+        fs::write(&path, vec![5; 4 * alignment]).unwrap();
+
+        let file = File::open_readonly(path.as_path(), 8, alignment).unwrap();
+
+        file.prefetch(alignment / 2, 2 * alignment).unwrap();
+        file.prefetch(0, 0).unwrap();
+        assert_eq!(file.cache.available(), 3);
+        assert_eq!(file.view(alignment, 1).unwrap()[0], 5);
+
+        should_panic(
+            std::panic::AssertUnwindSafe(|| file.prefetch(3 * alignment, 2 * alignment)),
+            "out of bounds",
+        );
+
+        drop(file);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn access_pattern() {
+        let alignment = os::get_alignment();
+
+        for access_pattern in [
+            AccessPattern::Normal,
+            AccessPattern::Random,
+            AccessPattern::Sequential,
+        ] {
+            let path = pb(&format!("access_pattern_{:?}.tmp", access_pattern));
+            let options = OpenOptions::new()
+                .cache_block_size(alignment)
+                .access_pattern(access_pattern)
+                .temp_strategy(TempStrategy::Keep)
+                .clone();
+
+            let file = File::open_writable_with(path.as_path(), 2 * alignment, &options).unwrap();
+            file.view_mut(alignment - 1, 2)
+                .unwrap()
+                .as_mut_slice()
+                .copy_from_slice(&[1, 2]);
+            drop(file);
+
+            let file = File::open_readonly_with(path.as_path(), &options).unwrap();
+            assert_eq!(file.view(alignment - 1, 2).unwrap().as_slice(), &[1, 2]);
+            drop(file);
+
+            fs::remove_file(&path).unwrap();
+        }
+    }
+
+    #[test]
+    fn release_pages() {
+        let alignment = os::get_alignment();
+        let path = pb("release_pages.tmp");
+        let data = (0..3 * alignment)
+            .map(|i| (i % 251) as u8)
+            .collect::<Vec<_>>();
+        fs::write(&path, &data).unwrap();
+
+        let file = File::open_readonly(path.as_path(), 4, alignment).unwrap();
+
+        // Nothing is mapped yet.
+        file.release_pages(0, file.len()).unwrap();
+
+        let view = file.view(alignment, alignment).unwrap();
+        file.view(0, 10).unwrap();
+
+        file.release_pages(alignment / 2, alignment + 10).unwrap();
+        file.release_pages(0, 0).unwrap();
+
+        // The pages are read again from the file.
+        assert_eq!(view.as_slice(), &data[alignment..2 * alignment]);
+        assert_eq!(file.view(0, 10).unwrap().as_slice(), &data[..10]);
+
+        drop(view);
+        drop(file);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn cache_stats() {
+        let alignment = os::get_alignment();
+        let file =
+            File::open_writable(pb("cache_stats.tmp").as_path(), 4 * alignment, 2, alignment)
+                .unwrap();
+
+        assert_eq!(file.cache_stats(), CacheStats::default());
+
+        file.view(0, 1).unwrap();
+        file.view(1, 1).unwrap();
+        {
+            // Both views share the same block.
+            let _view = file.view(alignment, 1).unwrap();
+            file.view(alignment + 1, 1).unwrap();
+        }
+        file.view_mut(2, 1).unwrap();
+        file.view(2 * alignment, 1).unwrap();
+        file.view_mut(3 * alignment, 1).unwrap();
+
+        assert_eq!(
+            file.cache_stats(),
+            CacheStats {
+                hits: 3,
+                misses: 4,
+                evictions: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn concurrent_view_mut() {
+        const THREADS: usize = 8;
+        const ROUNDS: usize = 200;
+
+        // Two threads share each block, and there are more blocks than the
+        // cache capacity.
+        let alignment = os::get_alignment();
+        let chunk = alignment / 2;
+        let file = File::open_writable_with(
+            pb("concurrent_view_mut.tmp").as_path(),
+            THREADS * chunk,
+            OpenOptions::new()
+                .cache_capacity(2)
+                .cache_block_size(alignment),
+        )
+        .unwrap();
+
+        std::thread::scope(|scope| {
+            for thread in 0..THREADS {
+                let file = &file;
+                scope.spawn(move || {
+                    for round in 0..ROUNDS {
+                        let mut view = file.view_mut(thread * chunk, chunk).unwrap();
+                        assert!(view.iter().all(|&byte| byte == view[0]));
+                        view.fill((thread * 31 + round) as u8);
+                    }
+                });
+            }
+        });
+
+        for thread in 0..THREADS {
+            let view = file.view(thread * chunk, chunk).unwrap();
+            let expected = (thread * 31 + ROUNDS - 1) as u8;
+            assert!(view.iter().all(|&byte| byte == expected));
+        }
+
+        // Overlapping views are refused while the mutable view is active.
+        let view = file.view_mut(0, chunk).unwrap();
+        assert!(file.view_mut(chunk - 1, 2).is_err());
+        assert!(file.view(chunk - 1, 1).is_err());
+        assert!(file.view_mut(chunk, chunk).is_ok());
+        drop(view);
+
+        // A shared view of the block refuses mutable views of the block.
+        let view = file.view(0, 1).unwrap();
+        assert!(file.view_mut(chunk, chunk).is_err());
+        drop(view);
+        assert!(file.view_mut(chunk, chunk).is_ok());
+    }
+
+    #[test]
+    fn rev_chunks() {
+        let alignment = os::get_alignment();
+        let path = pb("rev_chunks.tmp");
+        fs::write(
+            &path,
+            (0..4 * alignment).map(|i| i as u8).collect::<Vec<_>>(),
+        )
+        .unwrap();
+
+        let file = File::open_readonly(&path, 2, alignment).unwrap();
+        let chunk_len = alignment + alignment / 2;
+
+        let chunks = RevChunks::from_file(&file, chunk_len)
+            .unwrap()
+            .map(|view| view.unwrap().to_vec())
+            .collect::<Vec<_>>();
+
+        let expected = fs::read(&path).unwrap();
+        assert_eq!(
+            chunks,
+            vec![
+                expected[2 * chunk_len..].to_vec(),
+                expected[chunk_len..2 * chunk_len].to_vec(),
+                expected[..chunk_len].to_vec(),
+            ]
+        );
+
+        assert_eq!(
+            RevChunks::from_file(&file, 0).err().unwrap().kind(),
+            io::ErrorKind::InvalidInput
+        );
+
+        drop(file);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn path_and_temporary() {
+        let alignment = os::get_alignment();
+        let path = pb("path_and_temporary.tmp");
+
+        let file = File::open_writable(&path, alignment, 1, alignment).unwrap();
+        assert_eq!(file.path(), Some(path.as_path()));
+        assert!(file.is_temporary());
+
+        mem::drop(file);
+
+        fs::write(&path, vec![1; alignment]).unwrap();
+
+        let file = File::open_writable(&path, alignment, 1, alignment).unwrap();
+        assert_eq!(file.path(), Some(path.as_path()));
+        assert!(!file.is_temporary());
+        mem::drop(file);
+
+        let file = File::open_readonly(&path, 1, alignment).unwrap();
+        assert_eq!(file.path(), Some(path.as_path()));
+        assert!(!file.is_temporary());
+
+        let overlay = file.overlay().unwrap();
+        assert!(overlay.path().is_some());
+        assert!(!overlay.is_temporary());
+        mem::drop(overlay);
+        mem::drop(file);
+
+        let file = File::open_anonymous(alignment, &OpenOptions::new()).unwrap();
+        assert_eq!(file.path(), None);
+        assert!(file.is_temporary());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn debug_output() {
+        let alignment = os::get_alignment();
+        let file = File::open_writable(
+            pb("debug_output.tmp").as_path(),
+            2 * alignment,
+            3,
+            alignment,
+        )
+        .unwrap();
+
+        let debug = format!("{:?}", file);
+        assert!(debug.contains("mode: Writable"));
+        assert!(debug.contains("cache_capacity: 3"));
+        assert!(debug.contains(&format!("cache_block_size: {}", alignment)));
+
+        let mut view = file.view_mut(alignment + 2, 20).unwrap();
+        view[..4].copy_from_slice(&[0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(
+            format!("{:?}", view),
+            format!(
+                "ViewMut {{ offset: {}, len: 20, data: [de ad be ef 00 00 00 00 00 00 00 00 00 00 00 00 ..] }}",
+                alignment + 2
+            )
+        );
+        mem::drop(view);
+
+        let view = file.view(alignment + 2, 2).unwrap();
+        assert_eq!(
+            format!("{:?}", view),
+            format!(
+                "ViewRef {{ offset: {}, len: 2, data: [de ad] }}",
+                alignment + 2
+            )
+        );
+        mem::drop(view);
+
+        assert_eq!(
+            format!("{:?}", file.view(5, 0).unwrap()),
+            "ViewRef { offset: 5, len: 0, data: [] }"
+        );
+    }
+
+    #[test]
+    fn anonymous() {
+        let alignment = os::get_alignment();
+        let mut file = File::open_anonymous(
+            2 * alignment,
+            OpenOptions::new()
+                .cache_capacity(1)
+                .cache_block_size(alignment),
+        )
+        .unwrap();
+        assert_eq!(file.len(), 2 * alignment);
+
+        file.view_mut(alignment - 2, 4)
+            .unwrap()
+            .copy_from_slice(b"anon");
+
+        // The data survive the eviction of the blocks.
+        file.view(0, 1).unwrap();
+        file.view(alignment, 1).unwrap();
+        assert_eq!(file.view(alignment - 2, 4).unwrap().as_slice(), b"anon");
+
+        file.resize(4 * alignment).unwrap();
+        file.view_mut(3 * alignment, 4)
+            .unwrap()
+            .copy_from_slice(b"more");
+        assert_eq!(file.read_vec(alignment - 2, 4).unwrap(), b"anon");
+        assert_eq!(file.read_vec(3 * alignment, 4).unwrap(), b"more");
+
+        let err = file.persist().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+
+        assert!(File::open_anonymous(0, &OpenOptions::new()).is_err());
+    }
+
+    #[test]
+    fn into_readonly() {
+        let alignment = os::get_alignment();
+        let path = pb("into_readonly.tmp");
+        fs::write(&path, vec![1; 2 * alignment]).unwrap();
+
+        let file = File::open_writable(&path, 2 * alignment, 3, alignment).unwrap();
+        file.view_mut(alignment, 4)
+            .unwrap()
+            .copy_from_slice(&[2; 4]);
+
+        let file = file.into_readonly().unwrap();
+        assert!(!file.mode.is_writable());
+        assert_eq!(file.cache.capacity(), 3);
+
+        // The dirty block was flushed, but stays in the cache.
+        assert_eq!(file.cache.dirty_bytes(), 0);
+        assert!(file.cache.holds(alignment, 4));
+        assert_eq!(file.view(alignment, 4).unwrap()[..], [2; 4]);
+        assert_eq!(fs::read(&path).unwrap()[alignment..alignment + 4], [2; 4]);
+
+        // Now the file can be opened for reading, but not for writing.
+        let other = File::open_readonly(&path, 1, alignment).unwrap();
+        assert_eq!(other.view(alignment, 4).unwrap()[..], [2; 4]);
+        assert_eq!(
+            File::open_writable(&path, alignment, 1, alignment)
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::AlreadyExists
+        );
+
+        drop(other);
+        drop(file);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn into_readonly_temporary() {
+        let alignment = os::get_alignment();
+        let path = pb("into_readonly_temporary.tmp");
+
+        let file = File::open_writable(&path, alignment, 1, alignment).unwrap();
+        file.view_mut(0, 4).unwrap().copy_from_slice(b"temp");
+
+        // The temporary file is still alive.
+        let file = file.into_readonly().unwrap();
+        file.cache.invalidate(0, alignment).unwrap();
+        assert_eq!(file.view(0, 4).unwrap().as_slice(), b"temp");
+
+        drop(file);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn into_readonly_failure() {
+        let alignment = os::get_alignment();
+        let file = File::open_writable(
+            pb("into_readonly_failure.tmp").as_path(),
+            2 * alignment,
+            2,
+            alignment,
+        )
+        .unwrap();
+        file.view_mut(0, 4).unwrap().copy_from_slice(b"temp");
+
+        // The leaked mutable view prevents the conversion, but the buffer,
+        // together with the temporary file, is returned.
+        mem::forget(file.view_mut(alignment, 4).unwrap());
+        let (file, err) = file.into_readonly().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "cannot convert while a mutable view is active"
+        );
+
+        assert!(file.mode.is_writable());
+        assert_eq!(file.view(0, 4).unwrap().as_slice(), b"temp");
+    }
+
+    #[test]
+    fn into_writable() {
+        let alignment = os::get_alignment();
+        let path = pb("into_writable.tmp");
+        fs::write(&path, vec![1; 2 * alignment]).unwrap();
+
+        let file = File::open_readonly(&path, 3, alignment).unwrap();
+        assert_eq!(file.view(0, 4).unwrap()[..], [1; 4]);
+
+        let file = file.into_writable().unwrap();
+        assert!(file.mode.is_writable());
+        assert_eq!(file.len(), 2 * alignment);
+        assert_eq!(file.cache.capacity(), 3);
+        assert_eq!(file.cache_block_size(), alignment);
+
+        file.view_mut(alignment, 4)
+            .unwrap()
+            .copy_from_slice(&[2; 4]);
+
+        // The writable file is registered, so it cannot be opened again.
+        assert_eq!(
+            File::open_readonly(&path, 1, alignment).unwrap_err().kind(),
+            io::ErrorKind::AlreadyExists
+        );
+
+        drop(file);
+        assert_eq!(fs::read(&path).unwrap()[alignment..alignment + 4], [2; 4]);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn into_writable_failure() {
+        let alignment = os::get_alignment();
+        let path = pb("into_writable_failure.tmp");
+        fs::write(&path, vec![1; 2 * alignment]).unwrap();
+
+        let file = File::open_readonly_with(
+            &path,
+            OpenOptions::new()
+                .cache_capacity(3)
+                .cache_block_size(alignment)
+                .huge_pages(HugePages::Prefer),
+        )
+        .unwrap();
+        let other = File::open_readonly(&path, 1, alignment).unwrap();
+
+        // The other reader prevents opening the file for writing, but the
+        // buffer is opened read-only again with the same settings.
+        let (file, err) = file.into_writable().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+
+        let file = file.unwrap();
+        assert!(!file.mode.is_writable());
+        assert_eq!(file.cache.capacity(), 3);
+        assert_eq!(file.source.huge_pages, HugePages::Prefer);
+        assert_eq!(file.view(0, 4).unwrap()[..], [1; 4]);
+
+        drop(other);
+        let file = file.into_writable().unwrap();
+        assert_eq!(file.source.huge_pages, HugePages::Prefer);
+
+        drop(file);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn pin() {
+        let alignment = os::get_alignment();
+        let mut file =
+            File::open_writable(pb("pin.tmp").as_path(), 8 * alignment, 2, alignment).unwrap();
+
+        file.pin(alignment, 16).unwrap();
 
-        assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), Box::new(message));
-    }
+        for i in (0..8).filter(|i| *i != 1) {
+            file.view_mut(i * alignment, 16).unwrap();
+            file.view(i * alignment, 16).unwrap();
+        }
 
-    fn should_fail_assert<F: FnOnce() -> R + std::panic::UnwindSafe, R: std::fmt::Debug>(
-        body: F,
-        message: &str,
-    ) {
-        let result = std::panic::catch_unwind(body).map_err(|m| m.downcast::<String>().unwrap());
+        assert!(file.cache.holds(alignment, 16));
+        assert_eq!(file.trim(0).unwrap(), alignment);
+        assert!(file.cache.holds(alignment, 16));
 
-        assert!(result.is_err());
-        assert!(result.unwrap_err().contains(message));
-    }
+        // There must be room for the other blocks.
+        assert!(file.pin(0, 16).is_err());
 
-    fn pb(path: &str) -> PathBuf {
-        PathBuf::from(path)
+        // The failed pin mapped the block anyway.
+        file.unpin(alignment, 1);
+        assert_eq!(file.trim(0).unwrap(), 2 * alignment);
+        assert!(!file.cache.holds(alignment, 16));
     }
 
     #[test]
-    fn cache_consistency_after_resize() {
+    fn flush_and_release() {
         let alignment = os::get_alignment();
+        let path = pb("flush_and_release.tmp");
+        fs::write(&path, vec![0; 4 * alignment]).unwrap();
 
-        let mut file = File::open_writable(
-            pb("cache_consistency_after_resize.tmp").as_path(),
-            512,
-            2,
-            alignment,
-        )
-        .unwrap();
+        let mut file = File::open_writable(&path, 4 * alignment, 4, alignment).unwrap();
+        for i in 0..4 {
+            file.view_mut(i * alignment, 4)
+                .unwrap()
+                .copy_from_slice(&[i as u8 + 1; 4]);
+        }
 
-        // Write a byte to the first position.
-        file.view_mut(0, 1).unwrap()[0] = 3;
+        file.flush_and_release(0, 2 * alignment).unwrap();
+        assert!(!file.cache.holds(0, 4));
+        assert!(!file.cache.holds(alignment, 4));
+        assert!(file.cache.holds(2 * alignment, 4));
 
-        // Resize the file for the first time. This should allocate cache to be
-        // of full cache capacity.
-        file.resize(alignment).unwrap();
+        let contents = fs::read(&path).unwrap();
+        assert_eq!(contents[..4], [1; 4]);
+        assert_eq!(contents[alignment..alignment + 4], [2; 4]);
+        assert_eq!(&file.view(alignment, 4).unwrap()[..], &[2; 4]);
 
-        // Write a different byte to the first position.
-        file.view_mut(0, alignment).unwrap()[0] = 5;
+        drop(file);
+        fs::remove_file(&path).unwrap();
+    }
 
-        // Resize the file such that it is forced to reallocate cache on slice request to the newly allocated region.
-        file.resize(2 * alignment).unwrap();
+    #[test]
+    fn par_find() {
+        let alignment = os::get_alignment();
+        let path = pb("par_find.tmp");
+        let mut data = vec![0; 16 * alignment];
+        // Matches spanning the boundaries of the blocks (and possibly of the
+        // segments).
+        data[5 * alignment - 2..5 * alignment + 2].copy_from_slice(b"abcd");
+        data[9 * alignment - 1..9 * alignment + 3].copy_from_slice(b"abcd");
+        fs::write(&path, &data).unwrap();
 
-        // Access the new region to force cache reallocation.
-        file.view(alignment, alignment).unwrap();
+        let file = File::open_readonly(&path, 2, alignment).unwrap();
 
-        // Access the original region to force cache reallocation again.
-        let byte = file.view(0, alignment).unwrap()[0];
+        // With 16 or more threads, each block is a segment, so the matches
+        // span the boundaries of the segments.
+        for threads in [1, 3, 16, 64] {
+            let find = |needle: &[u8]| file.par_find_with(needle, threads).unwrap();
+            assert_eq!(find(b"abcd"), Some(5 * alignment - 2));
+            assert_eq!(find(b"bcd"), Some(5 * alignment - 1));
+            assert_eq!(find(b"abce"), None);
+            assert_eq!(find(b""), Some(0));
+        }
 
-        assert_eq!(byte, 5);
+        assert_eq!(file.par_find(b"abcd").unwrap(), Some(5 * alignment - 2));
+
+        for (i, w) in data.windows(4).enumerate().filter(|(_, w)| w == b"abcd") {
+            assert_eq!(file.find(w, i).unwrap(), Some(i));
+        }
+
+        drop(file);
+        fs::remove_file(&path).unwrap();
     }
 
     #[test]
-    fn copy_within_overlapping() {
-        let mut file =
-            File::open_writable(pb("copy_within_overlapping.tmp").as_path(), 512, 2, 512).unwrap();
+    fn resize_shrink_invalidates() {
+        let alignment = os::get_alignment();
+        let mut file = File::open_writable(
+            pb("resize_shrink_invalidates.tmp").as_path(),
+            4 * alignment,
+            4,
+            alignment,
+        )
+        .unwrap();
 
-        file.view_mut(0, 8).unwrap()[0..8].copy_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+        // Leave dirty blocks on both sides of the new end in the cache.
+        for block in 0..4 {
+            file.view_mut(block * alignment, alignment)
+                .unwrap()
+                .fill(block as u8 + 1);
+        }
 
-        file.copy_within(0, 4, 8).unwrap();
+        file.resize(2 * alignment).unwrap();
+        assert_eq!(file.len(), 2 * alignment);
+        assert!(!file.cache.holds(2 * alignment, 1));
+        assert!(!file.cache.holds(3 * alignment, 1));
 
-        assert_eq!(
-            &file.view(0, 12).unwrap()[0..12],
-            &[1, 2, 3, 4, 1, 2, 3, 4, 5, 6, 7, 8]
-        );
+        // Growing the file again must not expose the data of the stale blocks.
+        file.resize(4 * alignment).unwrap();
+
+        let data = file.read_vec(0, 4 * alignment).unwrap();
+        assert!(data[..alignment].iter().all(|&byte| byte == 1));
+        assert!(data[alignment..2 * alignment].iter().all(|&byte| byte == 2));
+        assert!(data[2 * alignment..].iter().all(|&byte| byte == 0));
     }
 
     #[test]
-    fn copy_within_non_overlapping() {
+    fn resize_with_leaked_view() {
         let alignment = os::get_alignment();
-
         let mut file = File::open_writable(
-            pb("copy_within_non_overlapping.tmp").as_path(),
-            4 * alignment,
-            2,
+            pb("resize_with_leaked_view.tmp").as_path(),
+            2 * alignment,
+            4,
             alignment,
         )
         .unwrap();
 
-        file.view_mut(0, 8).unwrap()[0..8].copy_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+        file.view_mut(0, alignment).unwrap().fill(1);
+        file.view_mut(alignment, alignment).unwrap().fill(2);
 
-        file.copy_within(0, 2 * alignment, 8).unwrap();
+        // A leaked view keeps its block mapped, so resizing must be refused
+        // in both directions.
+        std::mem::forget(file.view(alignment, alignment).unwrap());
+        assert!(file.resize(alignment).is_err());
+        assert!(file.resize(4 * alignment).is_err());
+        assert_eq!(file.len(), 2 * alignment);
 
-        assert_eq!(&file.view(0, 8).unwrap()[0..8], &[1, 2, 3, 4, 5, 6, 7, 8]);
-        assert_eq!(
-            &file.view(2 * alignment, 8).unwrap()[0..8],
-            &[1, 2, 3, 4, 5, 6, 7, 8]
-        );
+        let data = file.read_vec(0, 2 * alignment).unwrap();
+        assert!(data[..alignment].iter().all(|&byte| byte == 1));
+        assert!(data[alignment..].iter().all(|&byte| byte == 2));
+
+        let mut file = File::open_writable(
+            pb("resize_with_leaked_view.tmp").as_path(),
+            2 * alignment,
+            4,
+            alignment,
+        )
+        .unwrap();
+
+        std::mem::forget(file.view_mut(0, alignment).unwrap());
+        assert!(file.resize(alignment).is_err());
+        assert_eq!(file.len(), 2 * alignment);
     }
 
     #[test]
-    fn check_zero_len() {
+    #[cfg(unix)]
+    fn resize_sparse() {
+        use std::os::unix::fs::MetadataExt;
+
         let alignment = os::get_alignment();
+        let path = pb("resize_sparse.tmp");
+        fs::write(&path, vec![1; alignment]).unwrap();
 
-        let file = File::open_writable(pb("check_zero_len1.tmp").as_path(), 0, 2, alignment);
-        assert!(file.is_err());
+        let mut file = File::open_writable(&path, alignment, 2, alignment).unwrap();
+        file.resize(1024 * alignment).unwrap();
+        file.view_mut(512 * alignment, 4)
+            .unwrap()
+            .copy_from_slice(b"data");
+        file.cache.flush_range(0, 1024 * alignment).unwrap();
+
+        // The number of 512-byte blocks that are allocated for the file. Only
+        // the written pages should be allocated.
+        let allocated = fs::metadata(&path).unwrap().blocks() * 512;
         assert_eq!(
-            file.unwrap_err().to_string(),
-            "len must be greater than zero"
+            fs::metadata(&path).unwrap().len(),
+            (1024 * alignment) as u64
+        );
+        assert!(
+            allocated <= (16 * alignment) as u64,
+            "{} allocated",
+            allocated
         );
 
-        let file = File::open_writable(pb("check_zero_len2.tmp").as_path(), 512, 2, 0);
-        assert!(file.is_ok());
-        assert_eq!(file.unwrap().cache_block_size(), alignment);
+        drop(file);
+        fs::remove_file(&path).unwrap();
+    }
 
-        let mut file =
-            File::open_writable(pb("check_zero_len3.tmp").as_path(), 512, 2, alignment).unwrap();
-        let result = file.resize(0);
-        assert!(result.is_err());
-        assert_eq!(
-            result.unwrap_err().to_string(),
-            "new_len must be greater than zero"
-        );
-        mem::drop(file);
+    #[test]
+    fn checksum_blocks() {
+        use std::collections::hash_map::DefaultHasher;
 
-        let file_path = pb("check_zero_len.tmp");
-        let std_file = fs::File::create(file_path.as_path()).unwrap();
-        mem::drop(std_file);
+        let alignment = os::get_alignment();
+        let path = pb("checksum_blocks.tmp");
+        fs::write(&path, vec![0; 4 * alignment]).unwrap();
 
-        let file = File::open_readonly(file_path.as_path(), 2, alignment);
-        assert!(file.is_err());
-        assert_eq!(file.unwrap_err().to_string(), "file is empty");
+        let file = File::open_writable(&path, 4 * alignment, 2, alignment).unwrap();
+        let block = alignment + alignment / 2;
 
-        let mut std_file = fs::File::create(file_path.as_path()).unwrap();
-        std_file.write(&[1, 2, 3, 4]).unwrap();
-        std_file.flush().unwrap();
-        mem::drop(std_file);
+        let before = file.checksum_blocks::<DefaultHasher>(block).unwrap();
+        assert_eq!(before.len(), 3);
 
-        let file = File::open_readonly(file_path.as_path(), 2, 0);
-        assert!(file.is_ok());
-        assert_eq!(file.unwrap().cache_block_size(), alignment);
+        file.view_mut(2 * alignment, 1).unwrap()[0] = 1;
+        let after = file.checksum_blocks::<DefaultHasher>(block).unwrap();
 
-        fs::remove_file(file_path.as_path()).unwrap();
+        // Only the block containing the modified byte differs.
+        assert_eq!(before[0], after[0]);
+        assert_ne!(before[1], after[1]);
+        assert_eq!(before[2], after[2]);
+
+        let contents = file.read_vec(0, 4 * alignment).unwrap();
+        let expected = contents
+            .chunks(block)
+            .map(|chunk| {
+                let mut hasher = DefaultHasher::default();
+                hasher.write(chunk);
+                hasher.finish()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(after, expected);
+
+        drop(file);
+        fs::remove_file(&path).unwrap();
     }
 
     #[test]
-    fn bounds_checks() {
+    fn write_vectored_direct() {
         let alignment = os::get_alignment();
+        let path = pb("write_vectored_direct.tmp");
+        fs::write(&path, vec![0; 2 * alignment]).unwrap();
 
-        // Writable access to the file that is supposed to be temporary.
+        let mut file = File::open_writable(&path, 2 * alignment, 2, alignment).unwrap();
+        file.view_mut(alignment - 4, 8)
+            .unwrap()
+            .copy_from_slice(b"mmapmmap");
 
-        let file = File::open_writable(pb("bounds_checks1.tmp").as_path(), alignment, 2, alignment)
+        let written = file
+            .write_vectored_direct(alignment - 2, &[b"ab", b"", b"cd", b"e"])
             .unwrap();
-        let _ = file.view(0, alignment);
-        mem::drop(file);
+        assert_eq!(written, 5);
 
-        should_panic(
-            || {
-                let file = File::open_writable(
-                    pb("bounds_checks2.tmp").as_path(),
-                    alignment,
-                    2,
-                    alignment,
-                )
-                .unwrap();
-                let _ = file.view(0, alignment + 1);
-            },
-            "out of bounds",
-        );
+        // The dirty block was flushed before, so it did not overwrite the data.
+        assert_eq!(&file.view(alignment - 4, 8).unwrap()[..], b"mmabcdep");
 
-        should_panic(
-            || {
-                let mut file = File::open_writable(
-                    pb("bounds_checks3.tmp").as_path(),
-                    alignment,
-                    2,
-                    alignment,
-                )
-                .unwrap();
-                let _ = file.copy_within(alignment + 1, 0, 0);
-            },
-            "src out of bounds",
+        drop(file);
+        assert_eq!(
+            fs::read(&path).unwrap()[alignment - 4..alignment + 4],
+            *b"mmabcdep"
         );
+        fs::remove_file(&path).unwrap();
+    }
 
-        should_panic(
-            || {
-                let mut file = File::open_writable(
-                    pb("bounds_checks4.tmp").as_path(),
-                    alignment,
-                    2,
-                    alignment,
-                )
-                .unwrap();
-                let _ = file.copy_within(alignment, 0, 1);
-            },
-            "src out of bounds",
+    #[test]
+    fn try_view() {
+        let alignment = os::get_alignment();
+        let file =
+            File::open_writable(pb("try_view.tmp").as_path(), alignment, 2, alignment).unwrap();
+
+        assert!(file.try_view(0, alignment).is_ok());
+        assert!(file.try_view(alignment, 0).is_ok());
+        assert!(file.try_view_mut(alignment - 1, 1).is_ok());
+
+        for (off, len) in [(0, alignment + 1), (alignment + 1, 0), (usize::MAX, 1)] {
+            assert_eq!(
+                file.try_view(off, len).err().map(|err| err.kind()),
+                Some(io::ErrorKind::InvalidInput)
+            );
+            assert_eq!(
+                file.try_view_mut(off, len).err().map(|err| err.kind()),
+                Some(io::ErrorKind::InvalidInput)
+            );
+        }
+
+        assert_eq!(
+            file.try_view(usize::MAX, 1).err().unwrap().to_string(),
+            "offset + length overflows"
         );
 
         should_panic(
-            || {
-                let mut file = File::open_writable(
-                    pb("bounds_checks5.tmp").as_path(),
-                    alignment,
-                    2,
-                    alignment,
-                )
-                .unwrap();
-                let _ = file.copy_within(0, alignment + 1, 0);
-            },
-            "dst out of bounds",
+            std::panic::AssertUnwindSafe(|| {
+                let _ = file.view(usize::MAX, 1);
+            }),
+            "offset + length overflows",
         );
-
         should_panic(
-            || {
-                let mut file = File::open_writable(
-                    pb("bounds_checks6.tmp").as_path(),
-                    alignment,
-                    2,
-                    alignment,
-                )
-                .unwrap();
-                let _ = file.copy_within(0, alignment, 1);
-            },
-            "dst out of bounds",
+            std::panic::AssertUnwindSafe(|| {
+                let _ = file.view(1, usize::MAX);
+            }),
+            "offset + length overflows",
         );
-
-        // Readonly access to the file of size 4.
-
-        let file_path = pb("bounds_checks.tmp");
-        let mut std_file = fs::File::create(file_path.as_path()).unwrap();
-        std_file.write(&[1, 2, 3, 4]).unwrap();
-        std_file.flush().unwrap();
-        mem::drop(std_file);
-
-        let file = File::open_readonly(file_path.as_path(), 2, alignment);
-
-        let file = file.unwrap();
-        let _ = file.view(0, 4);
-        mem::drop(file);
-
         should_panic(
-            || {
-                let file = File::open_readonly(file_path.as_path(), 2, alignment).unwrap();
-                let _ = file.view(0, 5);
-            },
+            std::panic::AssertUnwindSafe(|| {
+                let _ = file.view(alignment, 1);
+            }),
             "out of bounds",
         );
-
-        fs::remove_file(file_path.as_path()).unwrap();
     }
 
     #[test]
-    fn protection_checks() {
+    fn flush() {
         let alignment = os::get_alignment();
+        let path = pb("flush.tmp");
+        fs::write(&path, vec![0; 2 * alignment]).unwrap();
 
-        let file_path = pb("protection_checks.tmp");
-        let mut std_file = fs::File::create(file_path.as_path()).unwrap();
-        std_file.write(&[1, 2, 3, 4, 5, 6, 7, 8]).unwrap();
-        std_file.flush().unwrap();
-        mem::drop(std_file);
+        let mut file = File::open_writable(&path, 2 * alignment, 2, alignment).unwrap();
+        file.view_mut(alignment - 2, 4)
+            .unwrap()
+            .copy_from_slice(b"data");
+        assert_eq!(file.cache.dirty_bytes(), 2 * alignment);
 
-        should_fail_assert(
-            || {
-                let mut file = File::open_readonly(file_path.as_path(), 2, alignment).unwrap();
-                let _ = file.view_mut(0, 8);
-            },
-            "underlying file was opened as read-only",
-        );
+        file.flush().unwrap();
+        assert_eq!(file.cache.dirty_bytes(), 0);
+        assert!(file.cache.holds(alignment - 2, 4));
 
-        should_fail_assert(
-            || {
-                let mut file = File::open_readonly(file_path.as_path(), 2, alignment).unwrap();
-                let _ = file.resize(alignment);
-            },
-            "underlying file was opened as read-only",
-        );
+        drop(file);
 
-        should_fail_assert(
-            || {
-                let mut file = File::open_readonly(file_path.as_path(), 2, alignment).unwrap();
-                let _ = file.copy_within(0, 4, 4);
-            },
-            "underlying file was opened as read-only",
-        );
+        let file = File::open_readonly(&path, 2, alignment).unwrap();
+        assert_eq!(&file.view(alignment - 2, 4).unwrap()[..], b"data");
 
-        fs::remove_file(file_path.as_path()).unwrap();
+        drop(file);
+        fs::remove_file(&path).unwrap();
     }
 
     #[test]
-    fn view_behind_alignment() {
+    fn flush_range() {
         let alignment = os::get_alignment();
-        let file = File::open_writable(
-            pb("view_behind_alignment.tmp").as_path(),
-            2 * alignment,
-            2,
-            alignment,
-        )
-        .unwrap();
+        let mut file =
+            File::open_writable(pb("flush_range.tmp").as_path(), 4 * alignment, 4, alignment)
+                .unwrap();
 
-        assert!(file.view(alignment, alignment).is_ok());
+        for i in 0..3 {
+            file.view_mut(i * alignment, 4)
+                .unwrap()
+                .copy_from_slice(b"data");
+        }
+
+        // The range spans the first two blocks.
+        file.flush_range(alignment - 2, 4).unwrap();
+        assert_eq!(file.cache.dirty_bytes(), alignment);
+
+        // No cached block covers the range.
+        file.flush_range(3 * alignment, alignment).unwrap();
+        file.flush_range(0, 0).unwrap();
+        assert_eq!(file.cache.dirty_bytes(), alignment);
+
+        // The order of the blocks is not changed, so the least recently used
+        // one is evicted first.
+        file.view(3 * alignment, 4).unwrap();
+        file.trim(3).unwrap();
+        assert!(!file.cache.holds(0, 4));
+        assert!(file.cache.holds(2 * alignment, 4));
     }
 }
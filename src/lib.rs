@@ -68,7 +68,8 @@
 //!
 //! When a non-existing file with writable access is opened, it is considered as
 //! a temporary, and thus is deleted when the owner goes out of scope. Thanks to
-//! this, the user doesn't need to worry about cleaning.
+//! this, the user doesn't need to worry about cleaning. This can be changed
+//! using [`OpenOptions::temp_strategy`](crate::OpenOptions::temp_strategy).
 //!
 //! # Caching
 //!
@@ -78,8 +79,10 @@
 //! maintain a cache of such mapped blocks with of larger size and use them if a
 //! request is in bounds of one of the blocks.
 //!
-//! The strategy is basically a traditional least-recently used (LRU), or more
-//! precisely, least-recently freed.
+//! The strategy is by default a traditional least-recently used (LRU), or more
+//! precisely, least-recently freed. First-in first-out (FIFO) and
+//! least-frequently used (LFU) strategies can be selected using
+//! [`OpenOptions::eviction_policy`](crate::OpenOptions::eviction_policy).
 //!
 //! The cache implementation is currently quite naive, but (hopefully) correct.
 //! The further work should be put to improve its efficiency.
@@ -93,6 +96,12 @@
 //! file locking (even if it is only
 //! [advisory](https://www.baeldung.com/linux/file-locking)), etc.
 //!
+//! Since the locks do not prevent opening the same file twice within one
+//! process, *harrow* additionally keeps track of the files opened in the
+//! process. Opening an existing file that is already opened with writable
+//! access, or opening it with writable access while it is already opened,
+//! fails with an error of kind [`AlreadyExists`](std::io::ErrorKind).
+//!
 //! # Name
 //!
 //! [Harrow](https://en.wikipedia.org/wiki/Harrow_(tool)) is an agricultural
@@ -104,25 +113,51 @@
 #![doc(html_root_url = "https://docs.rs/harrow/0.1.0")]
 #![deny(missing_docs)]
 
+use std::hash::Hasher;
 use std::io;
-use std::ops::Range;
-use std::path::Path;
+use std::num::NonZeroUsize;
+use std::ops::{Range, RangeBounds};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 
 mod align;
 mod cache;
+mod collections;
+mod entropy;
 mod ext;
 mod infra;
+mod options;
 mod os;
+#[cfg(feature = "bytemuck")]
+mod pod;
+mod reader;
+mod registry;
+mod scalar;
+mod source;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 
 #[cfg(unix)]
 mod unix;
 #[cfg(windows)]
 mod windows;
+mod writer;
 
-pub use cache::{ViewMut, ViewRef};
-pub use infra::Iter;
-pub use os::granularity;
+pub use cache::{CacheStats, CowView, ViewMut, ViewRef};
+pub use collections::{FileVec, Plain};
+pub use entropy::shannon_entropy;
+pub use infra::{Blocks, Chunks, Iter, LinesLossy, RevChunks};
+pub use options::{
+    AccessPattern, AdaptiveBlockSize, EvictionPolicy, HugePages, OpenOptions, StridedHint,
+    TempStrategy,
+};
+pub use os::{granularity, swap_files};
+#[cfg(feature = "bytemuck")]
+pub use pod::{StructViewMut, StructViewRef};
+pub use reader::Reader;
+pub use source::{Block, BlockSource, CachedSource};
+pub use writer::Writer;
 
 use infra::File;
 
@@ -174,6 +209,7 @@ pub const DEFAULT_CACHE_BLOCK_SIZE: usize = 128 * 1024 * 1024;
 /// numbers.read_at(&mut buf, 2 * mem::size_of::<u64>()).unwrap();
 /// assert_eq!(u64::from_ne_bytes(buf), 2);
 /// ```
+#[derive(Debug)]
 pub struct FileMut(File);
 
 impl FileMut {
@@ -182,9 +218,14 @@ impl FileMut {
     ///
     /// The `len` argument must not be zero. If the file does not exist, it is
     /// automatically created and then automatically removed when `FileMut` is
-    /// dropped. If the file exists, it may be **truncated** to the aligned
-    /// size. To avoid destroying any existing data, make sure to call this
-    /// constructor with `len` at least of the size of the file.
+    /// dropped. To create a file that is kept, use
+    /// [`FileMut::with_options`](crate::FileMut::with_options) with
+    /// [`TempStrategy::Keep`](crate::TempStrategy::Keep), or call
+    /// [`persist`](FileMut::persist) later. If the file exists, it may be
+    /// **truncated** to the aligned size. To avoid destroying any existing
+    /// data, make sure to call this constructor with `len` at least of the size
+    /// of the file, or use
+    /// [`FileMut::open_existing`](crate::FileMut::open_existing).
     ///
     /// The length is actually rounded to the closest bigger number that is
     /// aligned with the alignment that is required or recommended by the
@@ -209,45 +250,888 @@ impl FileMut {
         File::open_writable(path.as_ref(), len, cache_capacity, cache_block_size).map(Self)
     }
 
+    /// Creates new writable buffer for scratch data that is not backed by any
+    /// file visible to the user, with the default options.
+    ///
+    /// On Linux, the buffer is backed by an anonymous file created by
+    /// `memfd_create`, so the data live only in the memory (and swap). On
+    /// other Unix platforms, it is a temporary file that is removed from the
+    /// directory right after it is created. On Windows, it is a temporary file
+    /// with a unique name that is deleted when the buffer is dropped; the
+    /// mappings backed by the paging file are not used, because they cannot
+    /// be resized.
+    ///
+    /// Otherwise, the buffer behaves like any other. Since there is no path,
+    /// [`persist`](FileMut::persist) fails with an error of kind
+    /// [`Unsupported`](io::ErrorKind::Unsupported).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harrow::FileMut;
+    ///
+    /// let mut scratch = FileMut::anonymous(4096).unwrap();
+    /// scratch.write_at(b"scratch", 0).unwrap();
+    /// assert_eq!(scratch.view(0, 7).unwrap().as_slice(), b"scratch");
+    /// ```
+    pub fn anonymous(len: usize) -> io::Result<Self> {
+        File::open_anonymous(len, &OpenOptions::new()).map(Self)
+    }
+
+    /// Creates new writable buffer for the file at given `path` configured by
+    /// given `options`. For more information see
+    /// [`FileMut::new`](crate::FileMut::new) and
+    /// [`OpenOptions`](crate::OpenOptions).
+    pub fn with_options<P: AsRef<Path>>(
+        path: P,
+        len: usize,
+        options: &OpenOptions,
+    ) -> io::Result<Self> {
+        File::open_writable_with(path.as_ref(), len, options).map(Self)
+    }
+
+    /// Opens writable buffer for the existing file at given `path` without
+    /// truncating it. The length of the buffer is the size of the file, or
+    /// `min_len` if the file is smaller. For more information see
+    /// [`FileMut::new`](crate::FileMut::new) and
+    /// [`OpenOptions::truncate`](crate::OpenOptions::truncate).
+    ///
+    /// Fails with an error of kind [`NotFound`](io::ErrorKind::NotFound) if
+    /// the file does not exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harrow::{granularity, FileMut};
+    ///
+    /// let len = 3 * granularity();
+    /// std::fs::write("open_existing.bin", vec![1u8; len]).unwrap();
+    ///
+    /// let file = FileMut::open_existing("open_existing.bin", 1).unwrap();
+    /// assert_eq!(file.len(), len);
+    /// assert_eq!(file.view(len - 1, 1).unwrap()[0], 1);
+    /// # drop(file);
+    /// # std::fs::remove_file("open_existing.bin").unwrap();
+    /// ```
+    pub fn open_existing<P: AsRef<Path>>(path: P, min_len: usize) -> io::Result<Self> {
+        let path = path.as_ref();
+
+        if !path.exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "the file does not exist",
+            ));
+        }
+
+        File::open_writable_with(path, min_len, OpenOptions::new().truncate(false)).map(Self)
+    }
+
+    /// Opens a copy-on-write buffer for the existing file at given `path`.
+    ///
+    /// The buffer is writable, but the modifications are private to it. They
+    /// are never written to the file (flushing does nothing) and are discarded
+    /// when the buffer is dropped. This is the same as opening the file by
+    /// [`FileRef::new`](crate::FileRef::new) and creating an
+    /// [`overlay`](crate::FileRef::overlay) of it, without keeping the
+    /// read-only buffer around. See there for the limitations.
+    ///
+    /// Fails with an error of kind [`InvalidData`](io::ErrorKind::InvalidData)
+    /// if the file is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::fs;
+    /// use harrow::FileMut;
+    ///
+    /// fs::write("open_private.bin", b"immutable").unwrap();
+    ///
+    /// let mut file = FileMut::open_private("open_private.bin").unwrap();
+    /// file.write_at(b"e", 0).unwrap();
+    /// assert_eq!(&file.view(0, 9).unwrap()[..], b"emmutable");
+    ///
+    /// drop(file);
+    /// assert_eq!(fs::read("open_private.bin").unwrap(), b"immutable");
+    /// # fs::remove_file("open_private.bin").unwrap();
+    /// ```
+    pub fn open_private<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        File::open_private(path.as_ref()).map(Self)
+    }
+
+    /// Creates new writable buffer for the file at given `path` with enough
+    /// space for `capacity` records of `record_size` bytes, with the default
+    /// cache capacity and block size. For more information see
+    /// [`FileMut::new`](crate::FileMut::new).
+    ///
+    /// The record size is remembered, so the records can be then accessed by
+    /// their index using [`record`](FileMut::record) and
+    /// [`record_mut`](FileMut::record_mut). Fails with an error of kind
+    /// [`InvalidInput`](io::ErrorKind::InvalidInput) if the total size
+    /// overflows or is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harrow::FileMut;
+    ///
+    /// let mut file = FileMut::with_record_capacity("records.bin", 8, 1000).unwrap();
+    /// file.record_mut(999).unwrap().copy_from_slice(&42u64.to_ne_bytes());
+    ///
+    /// assert!(file.len() >= 8 * 1000);
+    /// assert_eq!(&file.record(999).unwrap()[..], &42u64.to_ne_bytes());
+    /// ```
+    pub fn with_record_capacity<P: AsRef<Path>>(
+        path: P,
+        record_size: usize,
+        capacity: usize,
+    ) -> io::Result<Self> {
+        let len = record_size.checked_mul(capacity).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "record capacity overflows")
+        })?;
+
+        let mut file = Self::new(path, len)?;
+        file.0.set_record_size(record_size);
+        Ok(file)
+    }
+
+    /// Returns the size of the records if the buffer was created by
+    /// [`with_record_capacity`](FileMut::with_record_capacity).
+    pub fn record_size(&self) -> Option<usize> {
+        self.0.record_size()
+    }
+
+    /// Acquires a view to the record at given index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the record size is not known or the record is out of bounds.
+    pub fn record(&self, index: usize) -> io::Result<ViewRef<'_>> {
+        let off = self.0.record_offset(index);
+        self.0.view(off, self.0.record_size().unwrap())
+    }
+
+    /// Acquires a mutable view to the record at given index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the record size is not known or the record is out of bounds.
+    pub fn record_mut(&mut self, index: usize) -> io::Result<ViewMut<'_>> {
+        let off = self.0.record_offset(index);
+        let record_size = self.0.record_size().unwrap();
+        self.0.view_mut(off, record_size)
+    }
+
     /// Returns the size of the underlying file.
     pub fn len(&self) -> usize {
         self.0.len()
     }
 
+    /// Returns the path the underlying file was opened with, or `None` for
+    /// [anonymous](FileMut::anonymous) buffers.
+    ///
+    /// The path is not canonicalized. Note that a
+    /// [temporary](FileMut::is_temporary) file may already be removed from the
+    /// directory (see [`TempStrategy`](crate::TempStrategy)), so the path may
+    /// not resolve to the file anymore.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::Path;
+    /// use harrow::FileMut;
+    ///
+    /// let file = FileMut::new("path.bin", 4096).unwrap();
+    /// assert_eq!(file.path(), Some(Path::new("path.bin")));
+    /// assert!(file.is_temporary());
+    /// ```
+    pub fn path(&self) -> Option<&Path> {
+        self.0.path()
+    }
+
+    /// Returns `true` if the underlying file is deleted when the buffer is
+    /// dropped, which is the case for the files that did not exist before
+    /// (unless [`TempStrategy::Keep`](crate::TempStrategy::Keep) is used) and
+    /// for [anonymous](FileMut::anonymous) buffers.
+    pub fn is_temporary(&self) -> bool {
+        self.0.is_temporary()
+    }
+
+    /// Returns `true` if the buffer has zero length.
+    ///
+    /// Zero-length files are currently rejected when opening and resizing, so
+    /// this always returns `false`. It is provided for completeness and for
+    /// the case that zero-length buffers become representable.
+    pub fn is_empty(&self) -> bool {
+        self.0.len() == 0
+    }
+
     /// Acquires a view to bytes at given offset and of given length.
+    ///
+    /// This is like [`try_view`](Self::try_view), only that it panics if the
+    /// range is out of bounds.
     pub fn view(&self, off: usize, len: usize) -> io::Result<ViewRef<'_>> {
         self.0.view(off, len)
     }
 
-    /// Acquires a view to bytes at given range.
-    pub fn view_range(&self, range: Range<usize>) -> io::Result<ViewRef<'_>> {
-        self.0.view(range.start, range.end - range.start)
+    /// Acquires a view to bytes at given offset and of given length, failing
+    /// with an error of kind [`InvalidInput`](io::ErrorKind::InvalidInput) if
+    /// the range is out of bounds (including when `off + len` overflows).
+    ///
+    /// Use this instead of [`view`](Self::view) when the offsets come from an
+    /// untrusted source.
+    pub fn try_view(&self, off: usize, len: usize) -> io::Result<ViewRef<'_>> {
+        self.0.try_view(off, len)
+    }
+
+    /// Acquires a view to bytes at given range. The range may be open (e.g.,
+    /// `100..`, `..50` or `..`), the open bounds are resolved against zero
+    /// and the length of the buffer.
+    pub fn view_range<R: RangeBounds<usize>>(&self, range: R) -> io::Result<ViewRef<'_>> {
+        let (off, len) = self.0.resolve_range(range);
+        self.0.view(off, len)
     }
 
     /// Acquires a mutable view to bytes at given offset and of given length.
-    pub fn view_mut(&mut self, off: usize, len: usize) -> io::Result<ViewMut<'_>> {
+    ///
+    /// Multiple mutable views can be active at the same time as long as their
+    /// ranges do not overlap, so disjoint regions of the buffer can be written
+    /// in parallel from multiple threads. An error is returned if the range
+    /// overlaps another active mutable view, or a cached block that is
+    /// currently lent as a shared view (the ranges of the shared views are not
+    /// tracked, so this is conservative). Panics if the range is out of
+    /// bounds, see [`try_view_mut`](FileMut::try_view_mut) for a fallible
+    /// variant.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harrow::FileMut;
+    ///
+    /// let file = FileMut::new("view_mut.bin", 4 * 4096).unwrap();
+    ///
+    /// std::thread::scope(|scope| {
+    ///     for i in 0..4 {
+    ///         let file = &file;
+    ///         scope.spawn(move || {
+    ///             let mut view = file.view_mut(i * 4096, 4096).unwrap();
+    ///             view.fill(i as u8);
+    ///         });
+    ///     }
+    /// });
+    ///
+    /// let first = file.view_mut(0, 10).unwrap();
+    /// assert!(file.view_mut(5, 10).is_err());
+    /// assert!(file.view(5, 10).is_err());
+    /// drop(first);
+    ///
+    /// assert_eq!(file.read_vec(3 * 4096, 2).unwrap(), [3, 3]);
+    /// ```
+    pub fn view_mut(&self, off: usize, len: usize) -> io::Result<ViewMut<'_>> {
         self.0.view_mut(off, len)
     }
 
-    /// Acquires a mutable view to bytes at given range.
-    pub fn view_range_mut(&mut self, range: Range<usize>) -> io::Result<ViewMut<'_>> {
-        self.0.view_mut(range.start, range.end - range.start)
+    /// Acquires a mutable view to bytes at given offset and of given length,
+    /// failing with an error of kind
+    /// [`InvalidInput`](io::ErrorKind::InvalidInput) if the range is out of
+    /// bounds (including when `off + len` overflows).
+    pub fn try_view_mut(&self, off: usize, len: usize) -> io::Result<ViewMut<'_>> {
+        self.0.try_view_mut(off, len)
+    }
+
+    /// Acquires a mutable view to bytes at given range. The range may be open
+    /// like in [`view_range`](FileMut::view_range).
+    pub fn view_range_mut<R: RangeBounds<usize>>(&self, range: R) -> io::Result<ViewMut<'_>> {
+        let (off, len) = self.0.resolve_range(range);
+        self.0.view_mut(off, len)
     }
 
     /// Resizes the underlying file to `new_len`.
     ///
     /// The new size must be greater than zero.
+    ///
+    /// Growing the buffer only changes the length of the file, the new bytes
+    /// are neither written nor mapped. On file systems that support sparse
+    /// files (most of them on Unix), the storage is therefore allocated only
+    /// for the pages that are actually written later. On Windows, files are
+    /// not sparse unless made so (e.g., by [`punch_hole`](FileMut::punch_hole)),
+    /// so the file system may allocate the storage immediately. Use
+    /// [`preallocate`](FileMut::preallocate) to allocate it explicitly.
+    ///
+    /// Shrinking the buffer flushes and unmaps the cached blocks that overlap
+    /// the discarded part, so no mapping points beyond the end of the file.
+    /// On Windows, all cached blocks are flushed and unmapped before any
+    /// resize, because the file mapping is recreated.
+    ///
+    /// No view may be active across a resize. This is ensured by the borrow
+    /// checker, but if a view was leaked (e.g., by [`std::mem::forget`]), the
+    /// resize fails with an error instead of invalidating its memory.
     pub fn resize(&mut self, new_len: usize) -> io::Result<()> {
-        self.0.resize(new_len)
+        let old_len = self.0.len();
+        self.0.resize(new_len)?;
+        self.grown(old_len);
+        Ok(())
+    }
+
+    /// Resizes the underlying file to `new_len` like [`resize`](FileMut::resize),
+    /// but the failure caused by insufficient space on the storage device is
+    /// reported as the error returned by `on_no_space`. Other errors are
+    /// converted into `E`.
+    ///
+    /// If resizing fails, the buffer is left intact and usable.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io;
+    /// use harrow::FileMut;
+    ///
+    /// #[derive(Debug)]
+    /// enum StorageError {
+    ///     DiskFull,
+    ///     Io(io::Error),
+    /// }
+    ///
+    /// impl From<io::Error> for StorageError {
+    ///     fn from(err: io::Error) -> Self {
+    ///         StorageError::Io(err)
+    ///     }
+    /// }
+    ///
+    /// let mut file = FileMut::new("resize_or.bin", 4096).unwrap();
+    /// file.resize_or(8192, || StorageError::DiskFull).unwrap();
+    /// ```
+    pub fn resize_or<E, F>(&mut self, new_len: usize, on_no_space: F) -> Result<(), E>
+    where
+        E: From<io::Error>,
+        F: FnOnce() -> E,
+    {
+        let old_len = self.0.len();
+        self.0.resize(new_len).map_err(|err| {
+            if os::is_no_space(&err) {
+                on_no_space()
+            } else {
+                E::from(err)
+            }
+        })?;
+        self.grown(old_len);
+        Ok(())
+    }
+
+    /// Returns the current generation of the cache. It changes whenever cached
+    /// blocks are invalidated, e.g., when the buffer is shrunk by
+    /// [`resize`](FileMut::resize).
+    ///
+    /// The views borrow the buffer, so they cannot outlive such invalidation.
+    /// However, the code that keeps raw pointers or offsets derived from the
+    /// views can record the generation and compare it later to detect that
+    /// they became stale.
+    pub fn generation(&self) -> usize {
+        self.0.generation()
+    }
+
+    /// Grows the underlying file to at least `min_len` bytes. If the buffer is
+    /// already long enough, nothing happens, so unlike
+    /// [`resize`](FileMut::resize) this never discards any data.
+    pub fn ensure_len(&mut self, min_len: usize) -> io::Result<()> {
+        let old_len = self.0.len();
+        self.0.ensure_len(min_len)?;
+        self.grown(old_len);
+        Ok(())
+    }
+
+    /// Changes the cache capacity.
+    ///
+    /// If the capacity is lowered, the blocks chosen by the eviction policy
+    /// that exceed the new capacity are unmapped immediately (flushing those
+    /// that are dirty). This is useful for responding to memory pressure.
+    /// Flushing errors are reported, but the blocks are unmapped regardless.
+    pub fn set_cache_capacity(&mut self, capacity: NonZeroUsize) -> io::Result<()> {
+        self.0.set_cache_capacity(capacity)
+    }
+
+    /// Unmaps the cached blocks chosen by the eviction policy until at most
+    /// `target_blocks` of them remain, flushing those that are dirty. Returns
+    /// the number of bytes that were unmapped.
+    ///
+    /// The cache capacity is not changed, so the cache may grow again later.
+    /// This is intended to be called in response to memory pressure signals
+    /// (e.g., cgroup events on Linux or `QueryMemoryResourceNotification` on
+    /// Windows), which harrow does not subscribe to itself.
+    pub fn trim(&mut self, target_blocks: usize) -> io::Result<usize> {
+        self.0.trim(target_blocks)
+    }
+
+    /// Returns the statistics of the cache behavior. See
+    /// [`FileRef::cache_stats`] for details.
+    pub fn cache_stats(&self) -> CacheStats {
+        self.0.cache_stats()
+    }
+
+    /// Maps the region and pins its cached block, so that it is never evicted
+    /// (not even by [`trim`](FileMut::trim)). This guarantees that accessing
+    /// a hot region (e.g., a header) never needs a new mapping.
+    ///
+    /// The pinned blocks count towards the cache capacity. Fails if pinning
+    /// the block would leave no room for other blocks. The pin is lost if the
+    /// region is discarded from the cache (e.g., by shrinking the buffer or
+    /// by [`punch_hole`](FileMut::punch_hole)).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the region is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harrow::{FileMut, OpenOptions};
+    ///
+    /// let mut file = FileMut::with_options(
+    ///     "pin.bin",
+    ///     16 * 4096,
+    ///     OpenOptions::new().cache_capacity(2).cache_block_size(4096),
+    /// )
+    /// .unwrap();
+    ///
+    /// file.pin(0, 64).unwrap();
+    ///
+    /// // Scanning the rest of the buffer does not evict the header block.
+    /// for i in 1..16 {
+    ///     file.write_at(b"data", i * 4096).unwrap();
+    /// }
+    ///
+    /// file.unpin(0, 64);
+    /// ```
+    pub fn pin(&mut self, off: usize, len: usize) -> io::Result<()> {
+        self.0.pin(off, len)
+    }
+
+    /// Unpins the cached blocks overlapping the region, so that they are
+    /// evicted as usual.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the region is out of bounds.
+    pub fn unpin(&mut self, off: usize, len: usize) {
+        self.0.unpin(off, len)
+    }
+
+    /// Sets a callback that is invoked whenever the cache misses and a new
+    /// block is mapped. See [`OpenOptions::on_fetch`](crate::OpenOptions::on_fetch)
+    /// for details.
+    pub fn on_fetch<F>(&mut self, on_fetch: F)
+    where
+        F: Fn(usize, usize) + Send + Sync + 'static,
+    {
+        self.0.set_on_fetch(Some(Arc::new(on_fetch)))
+    }
+
+    /// Allocates the storage for the first `len` bytes of the buffer, growing
+    /// it if needed (like [`ensure_len`](FileMut::ensure_len)).
+    ///
+    /// Resizing the buffer creates a sparse file whose storage is allocated
+    /// only when the pages are written. Then the insufficient space is
+    /// reported late (possibly as a crash when touching a page) and the file
+    /// gets fragmented. Preallocation reserves the storage up front, so the
+    /// insufficient space is reported immediately.
+    ///
+    /// This uses `fallocate` on Linux and `SetFileInformationByHandle` with
+    /// `FileAllocationInfo` on Windows. On other platforms, an error of kind
+    /// [`Unsupported`](io::ErrorKind::Unsupported) is returned (after the
+    /// buffer was grown).
+    pub fn preallocate(&mut self, len: usize) -> io::Result<()> {
+        self.ensure_len(len)?;
+        self.0.preallocate(len)
+    }
+
+    /// Deallocates the storage of the range, turning it into a hole that
+    /// reads as zeros. The length of the buffer is not changed. This allows
+    /// reclaiming the space of deleted regions without rewriting the file.
+    ///
+    /// This uses `fallocate` with `FALLOC_FL_PUNCH_HOLE` on Linux and
+    /// `FSCTL_SET_ZERO_DATA` on Windows (the file is made sparse first). The
+    /// file system may deallocate only whole blocks, the rest of the range is
+    /// zeroed. On other platforms, an error of kind
+    /// [`Unsupported`](io::ErrorKind::Unsupported) is returned.
+    ///
+    /// The cached blocks overlapping the range are discarded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
+    pub fn punch_hole(&mut self, range: Range<usize>) -> io::Result<()> {
+        self.0.punch_hole(range.start, range.end - range.start)
+    }
+
+    /// Writes all modified cached blocks to the file, so that the
+    /// modifications are persisted even if the process crashes afterwards.
+    ///
+    /// The blocks stay in the cache. Dropping the buffer flushes the blocks
+    /// too, but the errors are silently ignored.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+
+    /// Writes the modified cached blocks that overlap the range to the file
+    /// (`msync` on Unix, `FlushViewOfFile` on Windows). This is useful for
+    /// persisting a checkpoint while continuing to use the buffer.
+    ///
+    /// Each overlapping block is flushed as a whole. If no cached block
+    /// overlaps the range, this does nothing. The order of the blocks in the
+    /// cache is not changed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
+    pub fn flush_range(&mut self, off: usize, len: usize) -> io::Result<()> {
+        self.0.flush_range(off, len)
+    }
+
+    /// Writes the modified data in the range to the storage device.
+    ///
+    /// On Linux, this uses `sync_file_range`, which writes only the data of
+    /// the range and not the file metadata. Note that it does not flush the
+    /// write cache of the device either, so it is not a replacement of
+    /// `fsync` for durability against power loss. On other platforms, the
+    /// cached blocks overlapping the range are flushed and the whole file is
+    /// synchronized (`fsync` on Unix, `FlushFileBuffers` on Windows).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
+    pub fn sync_file_range(&mut self, range: Range<usize>) -> io::Result<()> {
+        self.0.sync_file_range(range.start, range.end - range.start)
+    }
+
+    /// Writes the buffers to the file consecutively starting at offset `off`,
+    /// bypassing the mapped blocks, and returns the number of bytes written.
+    ///
+    /// The writes go directly to the file (`pwritev` on Linux, `pwrite` on
+    /// other Unix platforms and `WriteFile` on Windows), so they reach the
+    /// operating system immediately and in order, unlike the modifications
+    /// through the mapped blocks, which are written lazily. The cached blocks
+    /// overlapping the range are flushed and discarded beforehand, so
+    /// subsequent views see the written data. Note that the data are not
+    /// synchronized to the storage device, use
+    /// [`sync_file_range`](FileMut::sync_file_range) for that.
+    ///
+    /// Fails with an error of kind
+    /// [`Unsupported`](io::ErrorKind::Unsupported) for overlays.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffers do not fit into the buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harrow::FileMut;
+    ///
+    /// let mut file = FileMut::new("write_vectored_direct.bin", 4096).unwrap();
+    ///
+    /// let written = file
+    ///     .write_vectored_direct(10, &[b"header", b"payload"])
+    ///     .unwrap();
+    ///
+    /// assert_eq!(written, 13);
+    /// assert_eq!(&file.view(10, 13).unwrap()[..], b"headerpayload");
+    /// ```
+    pub fn write_vectored_direct(&mut self, off: usize, bufs: &[&[u8]]) -> io::Result<usize> {
+        self.0.write_vectored_direct(off, bufs)
+    }
+
+    /// Flushes the cached blocks overlapping the range, unmaps them and
+    /// advises the operating system to drop the pages of the range from the
+    /// memory. This is meant for streaming writes, where a completed region is
+    /// not accessed again, to keep the memory usage flat.
+    ///
+    /// On Linux, the pages are dropped from the page cache using
+    /// `posix_fadvise` with `POSIX_FADV_DONTNEED`. On other platforms, only
+    /// the blocks are unmapped, which removes the pages from the memory of
+    /// the process, and the rest is left to the operating system. For
+    /// overlays, this does nothing, because unmapping would discard the
+    /// modifications.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
+    pub fn flush_and_release(&mut self, range: Range<usize>) -> io::Result<()> {
+        self.0
+            .flush_and_release(range.start, range.end - range.start)
+    }
+
+    /// Maps the range into the cache and advises the operating system to read
+    /// it ahead. See [`FileRef::prefetch`] for details.
+    pub fn prefetch(&self, off: usize, len: usize) -> io::Result<()> {
+        self.0.prefetch(off, len)
+    }
+
+    /// Flushes and unmaps all cached blocks and closes the underlying file,
+    /// returning the first error that occurred.
+    ///
+    /// Dropping the buffer does the same, but the errors are silently
+    /// ignored. Use this if you need to know whether the modifications
+    /// reached the storage (e.g., before reporting a write as durable).
+    pub fn close(self) -> io::Result<()> {
+        self.0.close()
+    }
+
+    /// Keeps the file on disk after the buffer is closed, even if it is a
+    /// temporary, and returns its path. The modifications are flushed and the
+    /// file is closed, so it can be opened again right away.
+    ///
+    /// If the file is not a temporary, this is the same as
+    /// [`close`](FileMut::close). [Zeroizing](FileMut::zeroize_on_drop) is
+    /// disabled. Fails with an error of kind
+    /// [`Unsupported`](io::ErrorKind::Unsupported) for overlays.
+    ///
+    /// There are platform caveats:
+    ///
+    /// * On Unix, a temporary file with the default
+    ///   [`TempStrategy::DeleteOnDrop`](crate::TempStrategy::DeleteOnDrop) is
+    ///   already removed from the directory and it cannot be linked back.
+    ///   Therefore, its contents are copied to a new file at the original
+    ///   path, which takes time and space proportional to the length. This
+    ///   fails if another file was created at the path in the meantime.
+    /// * On Windows, the delete-on-close state is cleared using
+    ///   `SetFileInformationByHandle` with `FileDispositionInfoEx`, which
+    ///   requires Windows 10 version 1809 or later.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harrow::FileMut;
+    ///
+    /// let mut file = FileMut::new("persist.bin", 4096).unwrap();
+    /// file.write_at(b"keep me", 0).unwrap();
+    ///
+    /// let path = file.persist().unwrap();
+    /// assert_eq!(&std::fs::read(&path).unwrap()[..7], b"keep me");
+    /// # std::fs::remove_file(path).unwrap();
+    /// ```
+    pub fn persist(self) -> io::Result<PathBuf> {
+        self.0.persist()
+    }
+
+    /// Converts the writable buffer into a read-only one that can be shared
+    /// across threads.
+    ///
+    /// Unlike dropping the buffer and opening the file again, this keeps the
+    /// same file descriptor (handle on Windows) and the cached blocks, which
+    /// are flushed first. Therefore, it works for temporary files too, which
+    /// stay alive as long as any handle to the returned buffer exists. The
+    /// callback set by [`on_grow`](FileMut::on_grow) is discarded.
+    ///
+    /// On failure, the writable buffer is returned together with the error, so
+    /// a temporary file is not lost. The conversion fails, e.g., if flushing
+    /// fails or if a mutable view was leaked.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use harrow::FileMut;
+    ///
+    /// let mut file = FileMut::new("into_ref.bin", 4096).unwrap();
+    /// file.write_at(b"shared", 0).unwrap();
+    ///
+    /// let file = file.into_ref().unwrap();
+    /// let other = file.clone();
+    ///
+    /// thread::spawn(move || assert_eq!(other.view(0, 6).unwrap().as_slice(), b"shared"))
+    ///     .join()
+    ///     .unwrap();
+    /// ```
+    // The error carries the buffer, which is as large as the success value.
+    #[allow(clippy::result_large_err)]
+    pub fn into_ref(self) -> Result<FileRef, (FileMut, io::Error)> {
+        self.0
+            .into_readonly()
+            .map(|file| FileRef(Arc::new(file)))
+            .map_err(|(file, err)| (FileMut(file), err))
+    }
+
+    /// Copies the contents of the buffer into a new file at `dest` and
+    /// returns a writable buffer for it. The new buffer is independent of this
+    /// one and is opened with the default options.
+    ///
+    /// The file must not exist, otherwise an error of kind
+    /// [`AlreadyExists`](io::ErrorKind::AlreadyExists) is returned. The new
+    /// file is *not* considered as a temporary. If copying fails, it is
+    /// removed again.
+    ///
+    /// The modified cached blocks are flushed first. The contents are then
+    /// copied block by block, so the whole buffer is never mapped at once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harrow::FileMut;
+    ///
+    /// let mut file = FileMut::new("clone_to.bin", 4096).unwrap();
+    /// file.write_at(b"original", 0).unwrap();
+    ///
+    /// let clone = file.clone_to("clone_to_clone.bin").unwrap();
+    /// file.write_at(b"modified", 0).unwrap();
+    ///
+    /// assert_eq!(clone.view(0, 8).unwrap().as_slice(), b"original");
+    /// # drop(clone);
+    /// # std::fs::remove_file("clone_to_clone.bin").unwrap();
+    /// ```
+    pub fn clone_to<P: AsRef<Path>>(&self, dest: P) -> io::Result<FileMut> {
+        self.0.clone_to(dest.as_ref()).map(Self)
+    }
+
+    /// Sets a callback that is invoked after the buffer was successfully grown
+    /// by [`resize`](FileMut::resize), [`resize_or`](FileMut::resize_or) or
+    /// [`ensure_len`](FileMut::ensure_len). The callback gets the buffer
+    /// together with the old and the new length. This is useful for
+    /// maintaining a header that describes the contents.
+    ///
+    /// The callback may access the buffer (e.g., by
+    /// [`write_at`](FileMut::write_at)), but it must not resize it. The
+    /// callback is not invoked recursively.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harrow::FileMut;
+    ///
+    /// let mut file = FileMut::new("on_grow.bin", 4096).unwrap();
+    /// file.on_grow(|file, _, new_len| {
+    ///     file.write_at(&(new_len as u64).to_le_bytes(), 0).unwrap();
+    /// });
+    ///
+    /// file.resize(8192).unwrap();
+    /// assert_eq!(file.read_u64_le_at(0).unwrap(), 8192);
+    /// ```
+    pub fn on_grow<F>(&mut self, on_grow: F)
+    where
+        F: FnMut(&mut FileMut, usize, usize) + Send + 'static,
+    {
+        self.0.set_on_grow(Some(Box::new(on_grow)))
+    }
+
+    fn grown(&mut self, old_len: usize) {
+        let new_len = self.0.len();
+
+        if new_len > old_len {
+            // The callback is taken out for the time of the call so that it
+            // can get the exclusive reference to the buffer.
+            if let Some(mut on_grow) = self.0.take_on_grow() {
+                on_grow(self, old_len, new_len);
+
+                // Keep the callback that was set from within the callback, if
+                // any.
+                let replaced = self.0.take_on_grow();
+                self.0.set_on_grow(replaced.or(Some(on_grow)));
+            }
+        }
+    }
+
+    /// Sets whether the contents of the file are overwritten with zeros when
+    /// the buffer is dropped. This is useful for holding sensitive data.
+    ///
+    /// The contents are zeroed through the virtual mappings and then flushed
+    /// to the storage before the file is closed (and deleted if it is a
+    /// temporary). Note that this wipes the file even if it is not a
+    /// temporary.
+    ///
+    /// Evicted cache blocks are not zeroed, because the mappings are shared
+    /// with the file and zeroing them would destroy the data that are still
+    /// in use.
+    ///
+    /// This is a best-effort measure only. The operating system may have
+    /// already written the pages elsewhere (e.g., swap or journal) and the file
+    /// system or the storage device may not overwrite the data in place.
+    pub fn zeroize_on_drop(&mut self, enabled: bool) {
+        self.0.set_zeroize_on_drop(enabled)
     }
 
     /// Copies `count` bytes from index `src` to index `dst`.
     ///
-    /// Overlapping regions are properly handled.
+    /// Overlapping regions are properly handled. The bytes are copied by
+    /// chunks no longer than the cache block size through an intermediate
+    /// buffer, in the direction that does not overwrite the source bytes before
+    /// they are read, so `count` may span any number of cache blocks.
     pub fn copy_within(&mut self, src: usize, dst: usize, count: usize) -> io::Result<()> {
         self.0.copy_within(src, dst, count)
     }
 
+    /// Swaps `count` bytes starting from offset `a` with `count` bytes
+    /// starting from offset `b`.
+    ///
+    /// The bytes are exchanged by chunks through a small buffer on the stack,
+    /// so `count` may be arbitrarily large without allocating.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the regions is out of bounds or if the regions
+    /// overlap.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harrow::FileMut;
+    ///
+    /// let mut file = FileMut::new("swap.bin", 4096).unwrap();
+    ///
+    /// file.write_at(b"hello world", 0).unwrap();
+    /// file.swap(0, 6, 5).unwrap();
+    /// assert_eq!(file.view(0, 11).unwrap().as_slice(), b"world hello");
+    /// ```
+    pub fn swap(&mut self, a: usize, b: usize, count: usize) -> io::Result<()> {
+        self.0.swap(a, b, count)
+    }
+
+    /// Sets `len` bytes starting from offset `off` to `value`.
+    ///
+    /// The bytes are written directly to the mapped memory, block by block,
+    /// so no intermediate buffer is allocated regardless of `len`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harrow::FileMut;
+    ///
+    /// let mut file = FileMut::new("fill.bin", 4096).unwrap();
+    ///
+    /// file.fill(0xAB, 10, 5).unwrap();
+    /// assert_eq!(file.view(10, 5).unwrap().as_slice(), &[0xAB; 5]);
+    /// ```
+    pub fn fill(&mut self, value: u8, off: usize, len: usize) -> io::Result<()> {
+        self.0.fill(value, off, len)
+    }
+
+    /// Returns the byte at offset `i`.
+    ///
+    /// The buffer does not implement [`Index`](std::ops::Index), because
+    /// indexing must return a reference, but the byte lives only in a mapped
+    /// block that can be unmapped as soon as the view acquired for the access
+    /// is dropped. For ranges of bytes, use [`view_range`](Self::view_range)
+    /// which returns a view that keeps the block mapped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harrow::FileMut;
+    ///
+    /// let mut file = FileMut::new("get.bin", 4096).unwrap();
+    ///
+    /// file.write_at(&[42], 100).unwrap();
+    /// assert_eq!(file.get(100).unwrap(), 42);
+    /// ```
+    pub fn get(&self, i: usize) -> io::Result<u8> {
+        Ok(self.0.view(i, 1)?[0])
+    }
+
     /// Reads the bytes from the buffer starting from offset `off` into buffer
     /// `buf`. The size of the bytes read is determined by `buf.len()`.
     pub fn read_at(&self, buf: &mut [u8], off: usize) -> io::Result<()> {
@@ -255,13 +1139,169 @@ impl FileMut {
         Ok(())
     }
 
+    /// Reads `len` bytes from the buffer starting from offset `off` into a
+    /// newly allocated vector.
+    pub fn read_vec(&self, off: usize, len: usize) -> io::Result<Vec<u8>> {
+        self.0.read_vec(off, len)
+    }
+
+    /// Returns `true` if `count` bytes starting from offset `a` are equal to
+    /// `count` bytes starting from offset `b`.
+    ///
+    /// The ranges are compared block by block and the comparison stops at the
+    /// first mismatch, so the bytes are never copied. The ranges may overlap.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the ranges is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harrow::FileMut;
+    ///
+    /// let mut file = FileMut::new("eq_ranges.bin", 4096).unwrap();
+    ///
+    /// file.write_at(b"abcabd", 0).unwrap();
+    /// assert!(file.eq_ranges(0, 3, 2).unwrap());
+    /// assert!(!file.eq_ranges(0, 3, 3).unwrap());
+    /// ```
+    pub fn eq_ranges(&self, a: usize, b: usize, count: usize) -> io::Result<bool> {
+        self.0.eq_ranges(a, b, count)
+    }
+
     /// Writes the bytes in `buf` to the buffer starting from offset `off`.
     pub fn write_at(&mut self, buf: &[u8], off: usize) -> io::Result<()> {
-        Ok(self
-            .0
+        self.0
             .view_mut(off, buf.len())?
             .as_mut_slice()
-            .copy_from_slice(buf))
+            .copy_from_slice(buf);
+        Ok(())
+    }
+
+    /// Writes as many bytes from `buf` as fit into the buffer starting from
+    /// offset `off` and returns the number of bytes written.
+    ///
+    /// Unlike [`write_at`](FileMut::write_at), this does not panic if `buf`
+    /// does not fit, only its prefix is written. This matches the semantics of
+    /// [`Write::write`](std::io::Write::write).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harrow::FileMut;
+    ///
+    /// let mut file = FileMut::new("write_at_all.bin", 4096).unwrap();
+    /// let len = file.len();
+    ///
+    /// assert_eq!(file.write_at_all(b"hello", len - 2).unwrap(), 2);
+    /// assert_eq!(file.write_at_all(b"hello", len).unwrap(), 0);
+    /// ```
+    pub fn write_at_all(&mut self, buf: &[u8], off: usize) -> io::Result<usize> {
+        let count = std::cmp::min(buf.len(), self.len().saturating_sub(off));
+
+        if count > 0 {
+            self.write_at(&buf[..count], off)?;
+        }
+
+        Ok(count)
+    }
+
+    /// Writes the bytes in `src` into the destination range of the buffer.
+    ///
+    /// Unlike [`write_at`](FileMut::write_at), the destination is given
+    /// explicitly, so writing a slice of a different length fails with an
+    /// error of kind [`InvalidInput`](io::ErrorKind::InvalidInput) instead of
+    /// silently writing to a different region. The source must not be a view
+    /// of this buffer (which the borrow checker enforces anyway); to move
+    /// bytes from one region of the buffer to another, use
+    /// [`copy_within`](FileMut::copy_within).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harrow::FileMut;
+    ///
+    /// let mut file = FileMut::new("splice.bin", 4096).unwrap();
+    ///
+    /// file.splice(10..15, b"hello").unwrap();
+    /// assert_eq!(file.view(10, 5).unwrap().as_slice(), b"hello");
+    ///
+    /// assert!(file.splice(10..12, b"hello").is_err());
+    /// ```
+    pub fn splice<R: RangeBounds<usize>>(&mut self, dst_range: R, src: &[u8]) -> io::Result<()> {
+        let (off, len) = self.0.resolve_range(dst_range);
+
+        if len != src.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "source length {} does not match destination length {}",
+                    src.len(),
+                    len
+                ),
+            ));
+        }
+
+        self.0
+            .view_mut(off, len)?
+            .as_mut_slice()
+            .copy_from_slice(src);
+        Ok(())
+    }
+
+    /// Splits the buffer into two at the given offset. The bytes `[at, len)`
+    /// are copied into a new file at `new_path`, this buffer is truncated to
+    /// `at` bytes and the new buffer is returned. This mirrors
+    /// [`Vec::split_off`] and is useful, e.g., for rotating log segments.
+    ///
+    /// The new file must not exist, and it is *not* considered as a
+    /// temporary. It uses the same cache settings as this buffer. This buffer
+    /// is truncated as the last step, so if any step fails, it keeps its
+    /// original length and contents and the new file is removed again. Note
+    /// that the lengths of both parts are aligned as usual, so the tail of
+    /// either part may be padded with zeros. In this buffer, the bytes from
+    /// `at` up to the aligned length are overwritten with zeros.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at` is greater than the buffer length.
+    pub fn split_off<P: AsRef<Path>>(&mut self, at: usize, new_path: P) -> io::Result<FileMut> {
+        self.0.split_off(at, new_path.as_ref()).map(Self)
+    }
+
+    /// Compacts the buffer by moving the live ranges to its beginning and
+    /// truncating the rest. Returns the new locations of the ranges, in the
+    /// same order as given, so that the caller can fix up its pointers.
+    ///
+    /// The ranges keep their relative order in the buffer and are placed next
+    /// to each other without any gaps. This is useful for allocators built on
+    /// top of the buffer that need to reclaim the freed space.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the ranges overlap or are out of bounds.
+    pub fn compact(&mut self, live_ranges: &[Range<usize>]) -> io::Result<Vec<Range<usize>>> {
+        self.0.compact(live_ranges)
+    }
+
+    /// Replaces all non-overlapping occurrences of `needle` with
+    /// `replacement` in place and returns the number of replacements.
+    ///
+    /// The needle and the replacement must have the same length, so that no
+    /// data needs to be shifted. Replacing with a string of different length
+    /// requires moving the rest of the buffer, e.g., using
+    /// [`copy_within`](FileMut::copy_within) and [`resize`](FileMut::resize).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the needle and the replacement have different lengths.
+    pub fn replace_all(&mut self, needle: &[u8], replacement: &[u8]) -> io::Result<usize> {
+        self.0.replace_all(needle, replacement)
     }
 
     /// Returns an iterator over bytes.
@@ -272,6 +1312,39 @@ impl FileMut {
     pub fn iter(&self) -> io::Result<Iter<'_>> {
         Iter::from_file(&self.0)
     }
+
+    /// Returns a reading cursor at the beginning of the buffer that implements
+    /// [`Read`](std::io::Read), [`BufRead`](std::io::BufRead) and
+    /// [`Seek`](std::io::Seek).
+    pub fn reader(&self) -> Reader<'_> {
+        Reader::from_file(&self.0)
+    }
+
+    /// Returns a writing cursor at the beginning of the buffer that implements
+    /// [`Write`](std::io::Write). The buffer is grown when a write would
+    /// exceed its length, see [`Writer`](crate::Writer) for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Write;
+    /// use harrow::FileMut;
+    ///
+    /// let mut file = FileMut::new("writer.bin", 1).unwrap();
+    /// let len = file.len();
+    ///
+    /// let mut writer = file.writer();
+    /// for i in 0..len {
+    ///     write!(writer, "{}", i).unwrap();
+    /// }
+    /// let written = writer.position();
+    ///
+    /// assert!(file.len() >= written);
+    /// assert_eq!(&file.view(0, 10).unwrap()[..], b"0123456789");
+    /// ```
+    pub fn writer(&mut self) -> Writer<'_> {
+        Writer::from_file(self)
+    }
 }
 
 /// A read-only file-backed buffer.
@@ -328,7 +1401,7 @@ impl FileMut {
 ///
 /// # let _ = fs::remove_file("text.txt");
 /// ```
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub struct FileRef(Arc<File>);
 
 impl FileRef {
@@ -337,7 +1410,7 @@ impl FileRef {
     ///
     /// If the file is empty, an error is returned.
     pub fn new<P: AsRef<Path>>(path: P) -> io::Result<Self> {
-        Self::with_cache(path, DEFAULT_CACHE_BLOCK_SIZE, DEFAULT_CACHE_CAPACITY)
+        Self::with_options(path, &OpenOptions::new())
     }
 
     /// Creates new read-only buffer for the file at given `path` with specified
@@ -357,19 +1430,192 @@ impl FileRef {
             .map(Self)
     }
 
+    /// Creates new read-only buffer for the file at given `path` configured by
+    /// given `options`. For more information see
+    /// [`FileRef::new`](crate::FileRef::new) and
+    /// [`OpenOptions`](crate::OpenOptions).
+    pub fn with_options<P: AsRef<Path>>(path: P, options: &OpenOptions) -> io::Result<Self> {
+        File::open_readonly_with(path.as_ref(), options)
+            .map(Arc::new)
+            .map(Self)
+    }
+
+    /// Creates new read-only buffer like [`FileRef::new`](crate::FileRef::new)
+    /// and starts warming the page cache of the operating system in the
+    /// background. It returns immediately and the buffer can be used while
+    /// the warming proceeds.
+    ///
+    /// A detached thread touches every page of the file through its own
+    /// mappings, so the cache of the buffer is not affected. This is useful
+    /// for "load it all, then access randomly" patterns, trading memory for
+    /// latency of the subsequent accesses. If the file does not fit into the
+    /// available memory, the operating system evicts the pages again and the
+    /// warming is mostly wasted work.
+    pub fn new_warmed<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = Self::new(path)?;
+        File::warm(&file.0);
+        Ok(file)
+    }
+
     /// Returns the size of the underlying file.
     pub fn len(&self) -> usize {
         self.0.len()
     }
 
+    /// Returns the path the underlying file was opened with, or `None` for a
+    /// buffer converted from an [anonymous](FileMut::anonymous) one.
+    ///
+    /// The path is not canonicalized. For a buffer converted from a temporary
+    /// [`FileMut`](FileMut::into_ref), the path may not resolve to the file
+    /// anymore.
+    pub fn path(&self) -> Option<&Path> {
+        self.0.path()
+    }
+
+    /// Returns `true` if the underlying file is deleted when the last handle
+    /// is dropped, which is possible only for a buffer converted from a
+    /// temporary [`FileMut`](FileMut::into_ref).
+    pub fn is_temporary(&self) -> bool {
+        self.0.is_temporary()
+    }
+
+    /// Returns `true` if the buffer has zero length.
+    ///
+    /// Zero-length files are currently rejected when opening and resizing, so
+    /// this always returns `false`. It is provided for completeness and for
+    /// the case that zero-length buffers become representable.
+    pub fn is_empty(&self) -> bool {
+        self.0.len() == 0
+    }
+
+    /// Returns the statistics of the cache behavior: the number of requests
+    /// satisfied by a cached block (hits), the number of requests that needed
+    /// a new mapping (misses) and the number of evicted blocks. This is useful
+    /// for tuning the cache capacity and block size.
+    ///
+    /// The statistics are shared by all handles of the buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harrow::FileRef;
+    ///
+    /// # std::fs::write("cache_stats.bin", vec![0u8; 4096]).unwrap();
+    /// let file = FileRef::new("cache_stats.bin").unwrap();
+    ///
+    /// file.view(0, 10).unwrap();
+    /// file.view(10, 10).unwrap();
+    ///
+    /// let stats = file.cache_stats();
+    /// assert_eq!((stats.hits, stats.misses), (1, 1));
+    /// # drop(file);
+    /// # std::fs::remove_file("cache_stats.bin").unwrap();
+    /// ```
+    pub fn cache_stats(&self) -> CacheStats {
+        self.0.cache_stats()
+    }
+
+    /// Returns the number of `FileRef` handles (including this one) that
+    /// share the underlying file and its cache. A buffer created by
+    /// [`new_warmed`](FileRef::new_warmed) is also shared with the warming
+    /// thread until it finishes.
+    ///
+    /// Cloning a `FileRef` is cheap, it only increments the reference count.
+    /// Wrapper types can therefore hold a clone together with derived metadata
+    /// and hand out views borrowed from themselves, without opening the file
+    /// again.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harrow::{FileRef, ViewRef};
+    ///
+    /// struct Records {
+    ///     file: FileRef,
+    ///     size: usize,
+    /// }
+    ///
+    /// impl Records {
+    ///     fn get(&self, index: usize) -> ViewRef<'_> {
+    ///         self.file.view(index * self.size, self.size).unwrap()
+    ///     }
+    /// }
+    ///
+    /// # std::fs::write("strong_count.bin", [7u8; 64]).unwrap();
+    /// let file = FileRef::new("strong_count.bin").unwrap();
+    /// let records = Records { file: file.clone(), size: 16 };
+    ///
+    /// assert_eq!(file.strong_count(), 2);
+    /// assert_eq!(records.get(1).as_slice(), &[7u8; 16]);
+    ///
+    /// drop(records);
+    /// assert_eq!(file.strong_count(), 1);
+    /// # drop(file);
+    /// # let _ = std::fs::remove_file("strong_count.bin");
+    /// ```
+    pub fn strong_count(&self) -> usize {
+        Arc::strong_count(&self.0)
+    }
+
     /// Acquires a view to bytes at given offset and of given length.
+    ///
+    /// This is like [`try_view`](Self::try_view), only that it panics if the
+    /// range is out of bounds.
     pub fn view(&self, off: usize, len: usize) -> io::Result<ViewRef<'_>> {
         self.0.view(off, len)
     }
 
-    /// Acquires a view to bytes at given range.
-    pub fn view_range(&self, range: Range<usize>) -> io::Result<ViewRef<'_>> {
-        self.0.view(range.start, range.end - range.start)
+    /// Acquires a view to bytes at given offset and of given length, failing
+    /// with an error of kind [`InvalidInput`](io::ErrorKind::InvalidInput) if
+    /// the range is out of bounds (including when `off + len` overflows).
+    ///
+    /// Use this instead of [`view`](Self::view) when the offsets come from an
+    /// untrusted source.
+    pub fn try_view(&self, off: usize, len: usize) -> io::Result<ViewRef<'_>> {
+        self.0.try_view(off, len)
+    }
+
+    /// Acquires a view to bytes at given range. The range may be open (e.g.,
+    /// `100..`, `..50` or `..`), the open bounds are resolved against zero
+    /// and the length of the buffer.
+    pub fn view_range<R: RangeBounds<usize>>(&self, range: R) -> io::Result<ViewRef<'_>> {
+        let (off, len) = self.0.resolve_range(range);
+        self.0.view(off, len)
+    }
+
+    /// Acquires a view to bytes at given offset and of given length, failing
+    /// with [`TimedOut`](std::io::ErrorKind::TimedOut) error if the pages of
+    /// the view cannot be brought into memory within `timeout`.
+    ///
+    /// Creating a mapping is fast, but on slow (e.g., network) file systems the
+    /// first access to a page can block for a long time. This method maps the
+    /// view and touches all its pages on a worker thread and waits for it at
+    /// most `timeout`.
+    ///
+    /// # Limitations
+    ///
+    /// Page faults cannot be cancelled. When the deadline passes, the worker
+    /// thread is detached and keeps bringing the pages in, holding a reference
+    /// to the buffer until it finishes. Moreover, the operating system is free
+    /// to evict the pages again, so a subsequent access to the view may still
+    /// block.
+    pub fn view_with_timeout(
+        &self,
+        off: usize,
+        len: usize,
+        timeout: Duration,
+    ) -> io::Result<ViewRef<'_>> {
+        self.0.view_with_timeout(off, len, timeout)
+    }
+
+    /// Returns the byte at offset `i`. See [`FileMut::get`] for why the
+    /// buffer does not implement [`Index`](std::ops::Index).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is out of bounds.
+    pub fn get(&self, i: usize) -> io::Result<u8> {
+        Ok(self.0.view(i, 1)?[0])
     }
 
     /// Reads the bytes from the buffer starting from offset `off` into buffer
@@ -379,12 +1625,473 @@ impl FileRef {
         Ok(())
     }
 
+    /// Reads `len` bytes from the buffer starting from offset `off` into a
+    /// newly allocated vector.
+    pub fn read_vec(&self, off: usize, len: usize) -> io::Result<Vec<u8>> {
+        self.0.read_vec(off, len)
+    }
+
+    /// Returns `true` if `count` bytes starting from offset `a` are equal to
+    /// `count` bytes starting from offset `b`. See
+    /// [`FileMut::eq_ranges`](FileMut::eq_ranges) for details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the ranges is out of bounds.
+    pub fn eq_ranges(&self, a: usize, b: usize, count: usize) -> io::Result<bool> {
+        self.0.eq_ranges(a, b, count)
+    }
+
+    /// Reads several possibly non-contiguous ranges at once. Each request is a
+    /// pair of the offset and the buffer to fill, whose length determines the
+    /// size of the bytes read.
+    ///
+    /// The requests are processed in the order of their offsets, so that the
+    /// requests clustered in the same region reuse the same cached block
+    /// instead of mapping it repeatedly.
+    pub fn read_vectored_at(&self, reqs: &mut [(usize, &mut [u8])]) -> io::Result<()> {
+        self.0.read_vectored_at(reqs)
+    }
+
+    /// Hashes each `block`-sized region of the buffer independently by a
+    /// fresh hasher of type `H` and returns the digests. The last region may
+    /// be shorter.
+    ///
+    /// Comparing the digests of two versions of a file reveals the regions
+    /// that changed, so only those need to be verified or transferred. The
+    /// bytes of a region are fed to the hasher by [`Hasher::write`] calls for
+    /// parts of at most the cache block size, so the hasher must give the same
+    /// result regardless of how the input is split (which holds, e.g., for
+    /// [`DefaultHasher`](std::collections::hash_map::DefaultHasher)).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `block` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::hash_map::DefaultHasher;
+    /// use harrow::FileRef;
+    ///
+    /// # std::fs::write("checksum_blocks.bin", vec![0u8; 3 * 4096]).unwrap();
+    /// let file = FileRef::new("checksum_blocks.bin").unwrap();
+    /// let digests = file.checksum_blocks::<DefaultHasher>(4096).unwrap();
+    ///
+    /// assert_eq!(digests.len(), file.len() / 4096);
+    /// assert_eq!(digests[0], digests[1]);
+    /// # drop(file);
+    /// # let _ = std::fs::remove_file("checksum_blocks.bin");
+    /// ```
+    pub fn checksum_blocks<H: Hasher + Default>(&self, block: usize) -> io::Result<Vec<u64>> {
+        self.0.checksum_blocks::<H>(block)
+    }
+
+    /// Reads `n_samples` evenly spaced windows of `sample_len` bytes and
+    /// returns them concatenated. The first window is at the beginning and the
+    /// last window is at the end of the buffer.
+    ///
+    /// This is a cheap way of getting a representative sample of a huge file,
+    /// e.g., for guessing its format using
+    /// [`shannon_entropy`](crate::shannon_entropy).
+    ///
+    /// The number of samples is capped by the number of distinct windows,
+    /// i.e., `len - sample_len + 1`, so the windows are never repeated. Fails
+    /// with an error of kind [`InvalidInput`](io::ErrorKind::InvalidInput) if
+    /// the total length of the samples overflows.
+    pub fn sample(&self, n_samples: usize, sample_len: usize) -> io::Result<Vec<u8>> {
+        self.0.sample(n_samples, sample_len)
+    }
+
+    /// Builds an index of variable-length records by scanning the buffer once.
+    /// Returns the offsets of the beginnings of the records.
+    ///
+    /// The function `f` receives a view that starts at the beginning of the
+    /// next record and returns the length of that record (including any
+    /// header). The view spans at most one cache block, so a record that is
+    /// longer is not seen whole. This is meant for formats that store the
+    /// length in a header at the beginning of each record (e.g.,
+    /// length-prefixed records), which only requires the header to fit into
+    /// a cache block. Delimited formats work only if no record, including its
+    /// delimiter, is longer than the cache block size.
+    ///
+    /// Fails with an error of kind [`InvalidData`](io::ErrorKind::InvalidData)
+    /// if `f` returns zero or a length that exceeds the end of the buffer.
+    pub fn index_by<F>(&self, f: F) -> io::Result<Vec<usize>>
+    where
+        F: FnMut(&[u8]) -> usize,
+    {
+        self.0.index_by(f)
+    }
+
+    /// Returns the offset of the first occurrence of `needle` that starts at
+    /// or after offset `from`, or `None` if there is no such occurrence.
+    ///
+    /// The buffer is scanned by cache blocks, so the whole buffer is never
+    /// mapped at once. The occurrences that span the boundaries of the blocks
+    /// are found as well.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `from` is greater than the buffer length.
+    pub fn find(&self, needle: &[u8], from: usize) -> io::Result<Option<usize>> {
+        self.0.find(needle, from)
+    }
+
+    /// Returns the offset of the last occurrence of `needle` that ends at or
+    /// before offset `to`, or `None` if there is no such occurrence.
+    ///
+    /// Like [`find`](FileRef::find), the buffer is scanned by cache blocks,
+    /// only from the end to the start.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `to` is greater than the buffer length.
+    pub fn rfind(&self, needle: &[u8], to: usize) -> io::Result<Option<usize>> {
+        self.0.rfind(needle, to)
+    }
+
+    /// Returns the offset of the first occurrence of `needle` in the buffer,
+    /// or `None` if there is no occurrence.
+    ///
+    /// This is a parallel variant of [`find`](FileRef::find) for searching
+    /// huge buffers. The buffer is split into segments (at most one per
+    /// available CPU) which are searched by separate threads. The occurrences
+    /// that span the boundaries of the segments are found as well. All
+    /// segments are searched to the end or to their first occurrence, so this
+    /// is not faster than `find` if the occurrence is expected near the start.
+    pub fn par_find(&self, needle: &[u8]) -> io::Result<Option<usize>> {
+        self.0.par_find(needle)
+    }
+
+    /// Acquires a view to the first `len` bytes of the buffer, typically a
+    /// header of a binary format.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len` is greater than the buffer length.
+    pub fn header(&self, len: usize) -> io::Result<ViewRef<'_>> {
+        self.0.view(0, len)
+    }
+
+    /// Acquires a view to the last `len` bytes of the buffer, typically a
+    /// footer of a binary format.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len` is greater than the buffer length.
+    pub fn footer(&self, len: usize) -> io::Result<ViewRef<'_>> {
+        if len > self.len() {
+            panic!("out of bounds");
+        }
+
+        self.0.view(self.len() - len, len)
+    }
+
+    /// Acquires the bytes at given range either as a view or as an owned copy.
+    ///
+    /// If the range fits into a single cache block, the bytes are returned as
+    /// a view without any copying. Otherwise, they are copied into an owned
+    /// buffer, so that no huge block is mapped at once. This is useful for
+    /// API boundaries that need to handle both small and large ranges.
+    pub fn range_cow(&self, range: Range<usize>) -> io::Result<CowView<'_>> {
+        let len = range.end - range.start;
+
+        if len <= self.0.cache_block_size() {
+            self.0.view(range.start, len).map(CowView::Borrowed)
+        } else {
+            self.0.read_vec(range.start, len).map(CowView::Owned)
+        }
+    }
+
     /// Returns an iterator over bytes.
     ///
     /// Note that even if the creation of the iterator succeeds, and I/O error
     /// iteration can happen during iteration. In that case, the iterator
     /// panics.
     pub fn iter(&self) -> io::Result<Iter<'_>> {
-        Iter::from_file(&*self.0)
+        Iter::from_file(&self.0)
+    }
+
+    /// Returns a reading cursor at the beginning of the buffer that implements
+    /// [`Read`](std::io::Read), [`BufRead`](std::io::BufRead) and
+    /// [`Seek`](std::io::Seek), so it can be passed to code that consumes a
+    /// stream without loading the whole file into memory.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::{self, Read, Seek, SeekFrom};
+    /// use harrow::FileRef;
+    ///
+    /// // Reads the trailer at the end of a stream.
+    /// fn trailer<R: Read + Seek>(mut stream: R) -> io::Result<[u8; 4]> {
+    ///     let mut buf = [0; 4];
+    ///     stream.seek(SeekFrom::End(-4))?;
+    ///     stream.read_exact(&mut buf)?;
+    ///     Ok(buf)
+    /// }
+    ///
+    /// # std::fs::write("reader.bin", b"header, data, EOF!").unwrap();
+    /// let file = FileRef::new("reader.bin").unwrap();
+    /// assert_eq!(&trailer(file.reader()).unwrap(), b"EOF!");
+    /// # drop(file);
+    /// # std::fs::remove_file("reader.bin").unwrap();
+    /// ```
+    pub fn reader(&self) -> Reader<'_> {
+        Reader::from_file(&self.0)
+    }
+
+    /// Returns an iterator over the lines of the buffer, converted to strings
+    /// with invalid UTF-8 sequences replaced by
+    /// [`U+FFFD REPLACEMENT CHARACTER`](std::char::REPLACEMENT_CHARACTER).
+    ///
+    /// The lines are split on the newline character (`\n`) or CRLF
+    /// (`\r\n`), which are not included in the strings. Each line is copied
+    /// into an owned string, so it may span multiple cache blocks. If an I/O
+    /// error happens, it is yielded and the iteration ends.
+    pub fn lines_lossy(&self) -> LinesLossy<'_> {
+        LinesLossy::from_file(&self.0)
+    }
+
+    /// Creates a writable overlay of the buffer. The overlay initially has
+    /// the same contents, but the modifications made through it are private
+    /// to it. They are never written to the file and are discarded when the
+    /// overlay is dropped. The buffer itself does not see them either.
+    ///
+    /// The overlay is backed by a copy-on-write mapping of the whole file
+    /// (`MAP_PRIVATE` on Unix, `FILE_MAP_COPY` on Windows), so only the
+    /// modified pages take additional memory. It cannot be resized (such
+    /// operations fail with an error of kind
+    /// [`Unsupported`](io::ErrorKind::Unsupported)) and
+    /// [`trim`](FileMut::trim) does nothing on it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::fs;
+    /// use harrow::FileRef;
+    ///
+    /// fs::write("overlay.bin", b"immutable").unwrap();
+    ///
+    /// let base = FileRef::new("overlay.bin").unwrap();
+    /// let mut overlay = base.overlay().unwrap();
+    /// overlay.write_at(b"e", 0).unwrap();
+    ///
+    /// assert_eq!(&overlay.view(0, 9).unwrap()[..], b"emmutable");
+    /// assert_eq!(&base.view(0, 9).unwrap()[..], b"immutable");
+    /// # drop(overlay);
+    /// # drop(base);
+    /// # fs::remove_file("overlay.bin").unwrap();
+    /// ```
+    pub fn overlay(&self) -> io::Result<FileMut> {
+        self.0.overlay().map(FileMut)
+    }
+
+    /// Converts the read-only buffer into a writable one, keeping the cache
+    /// settings.
+    ///
+    /// A read-only descriptor cannot be upgraded, so the file is closed and
+    /// opened again with writable access. Between the two steps, the file is
+    /// not locked and another process may open it. It is the responsibility of
+    /// the caller to ensure that there are no external writers. The cached
+    /// blocks are not carried over.
+    ///
+    /// Fails with an error of kind [`WouldBlock`](io::ErrorKind::WouldBlock)
+    /// if there are other handles to the file (see
+    /// [`strong_count`](FileRef::strong_count)), which are necessarily
+    /// read-only. On failure, the read-only buffer is returned together with
+    /// the error. If reopening for writing fails, the file is opened read-only
+    /// again, and the buffer is `None` only if even that fails, e.g., because
+    /// the file was removed in the meantime.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harrow::FileRef;
+    ///
+    /// # std::fs::write("try_into_mut.bin", [0u8; 4096]).unwrap();
+    /// let file = FileRef::new("try_into_mut.bin").unwrap();
+    /// let mut file = file.try_into_mut().unwrap();
+    ///
+    /// file.write_at(b"hello", 0).unwrap();
+    /// # drop(file);
+    /// # let _ = std::fs::remove_file("try_into_mut.bin");
+    /// ```
+    pub fn try_into_mut(self) -> Result<FileMut, (Option<FileRef>, io::Error)> {
+        let file = Arc::try_unwrap(self.0).map_err(|file| {
+            let err = io::Error::new(
+                io::ErrorKind::WouldBlock,
+                "the file is shared by other handles",
+            );
+            (Some(FileRef(file)), err)
+        })?;
+
+        file.into_writable()
+            .map(FileMut)
+            .map_err(|(file, err)| (file.map(Arc::new).map(FileRef), err))
+    }
+
+    /// Drops the pages of the range from the memory of the process, keeping
+    /// the resident set size in check when scanning a file that is larger
+    /// than the available memory. The cached blocks stay mapped and the pages
+    /// are transparently read from the file again if they are accessed later.
+    ///
+    /// On Unix, this uses `madvise` with `MADV_DONTNEED`, which requires the
+    /// range to be extended to whole pages. On Windows, the pages are removed
+    /// from the working set using `VirtualUnlock`. Only the parts of the range
+    /// that are currently mapped are affected, so this does nothing if no
+    /// cached block overlaps the range. For overlays, this does nothing,
+    /// because the modifications would be lost.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harrow::FileRef;
+    ///
+    /// # std::fs::write("release.bin", vec![1u8; 4 * 4096]).unwrap();
+    /// let file = FileRef::new("release.bin").unwrap();
+    ///
+    /// let mut sum = 0u64;
+    /// for chunk in file.chunks(4096).unwrap() {
+    ///     sum += chunk.unwrap().iter().map(|&b| b as u64).sum::<u64>();
+    /// }
+    ///
+    /// // The data were processed and will not be needed again.
+    /// file.release(0, file.len()).unwrap();
+    /// assert_eq!(sum, 4 * 4096);
+    /// # drop(file);
+    /// # std::fs::remove_file("release.bin").unwrap();
+    /// ```
+    pub fn release(&self, off: usize, len: usize) -> io::Result<()> {
+        self.0.release_pages(off, len)
+    }
+
+    /// Maps the range into the cache and advises the operating system that it
+    /// will be accessed soon, so the pages can be read ahead while the caller
+    /// does other work. This is useful for overlapping I/O with computation in
+    /// sequential scans.
+    ///
+    /// On Unix, this uses `madvise` with `MADV_WILLNEED`, on Windows,
+    /// `PrefetchVirtualMemory` (available since Windows 8). The advice covers
+    /// the whole cached blocks that hold the range. It is only a hint that the
+    /// operating system may ignore, but the errors of the calls are reported.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harrow::FileRef;
+    ///
+    /// # std::fs::write("prefetch.bin", vec![1u8; 3 * 4096]).unwrap();
+    /// let file = FileRef::new("prefetch.bin").unwrap();
+    ///
+    /// // Read the next part ahead while processing the current one.
+    /// file.prefetch(4096, 2 * 4096).unwrap();
+    /// let sum = file.view(0, 4096).unwrap().iter().map(|&b| b as u64).sum::<u64>();
+    /// assert_eq!(sum, 4096);
+    /// # drop(file);
+    /// # std::fs::remove_file("prefetch.bin").unwrap();
+    /// ```
+    pub fn prefetch(&self, off: usize, len: usize) -> io::Result<()> {
+        self.0.prefetch(off, len)
+    }
+
+    /// Returns an iterator over the cache blocks covering the buffer. Each
+    /// item is the offset of the block together with the view of it.
+    ///
+    /// The blocks are aligned to the cache block size, so each view
+    /// corresponds exactly to a unit that is cached (only the last one may be
+    /// shorter). It is up to the caller whether to keep the views alive or
+    /// drop them and let the blocks be evicted. If an I/O error happens, it
+    /// is yielded and the iteration ends.
+    pub fn blocks(&self) -> Blocks<'_> {
+        Blocks::from_file(&self.0)
+    }
+
+    /// Returns an iterator over the views of the successive chunks of
+    /// `chunk_len` bytes of the buffer. The last chunk may be shorter. If an
+    /// I/O error happens, it is yielded and the iteration ends.
+    ///
+    /// The views are backed by the cache, so no data are copied. The chunks
+    /// that are not aligned to the cache block size may span multiple blocks.
+    /// Fails with an error of kind
+    /// [`InvalidInput`](io::ErrorKind::InvalidInput) if `chunk_len` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::hash_map::DefaultHasher;
+    /// use std::hash::Hasher;
+    /// use harrow::FileRef;
+    ///
+    /// # std::fs::write("chunks.bin", vec![7u8; 10000]).unwrap();
+    /// let file = FileRef::new("chunks.bin").unwrap();
+    ///
+    /// let mut hasher = DefaultHasher::new();
+    /// let mut lens = Vec::new();
+    /// for chunk in file.chunks(4096).unwrap() {
+    ///     let chunk = chunk.unwrap();
+    ///     hasher.write(&chunk);
+    ///     lens.push(chunk.len());
+    /// }
+    ///
+    /// assert_eq!(lens, [4096, 4096, 1808]);
+    /// # drop(file);
+    /// # std::fs::remove_file("chunks.bin").unwrap();
+    /// ```
+    pub fn chunks(&self, chunk_len: usize) -> io::Result<Chunks<'_>> {
+        Chunks::from_file(&self.0, chunk_len)
+    }
+
+    /// Returns an iterator over the views of the chunks of the buffer, from
+    /// the last one to the first one. This is useful for parsing formats that
+    /// are read from the end.
+    ///
+    /// The chunks start at multiples of `chunk_len` like when iterating
+    /// forward, so only the first yielded chunk (at the end of the buffer) may
+    /// be shorter. When the chunk length is aligned to the cache block size,
+    /// the chunks correspond to the cached blocks. If an I/O error happens, it
+    /// is yielded and the iteration ends.
+    ///
+    /// Fails with an error of kind
+    /// [`InvalidInput`](io::ErrorKind::InvalidInput) if `chunk_len` is zero.
+    pub fn iter_rev_chunks(&self, chunk_len: usize) -> io::Result<RevChunks<'_>> {
+        RevChunks::from_file(&self.0, chunk_len)
+    }
+}
+
+#[cfg(unix)]
+impl std::os::unix::io::AsFd for FileMut {
+    fn as_fd(&self) -> std::os::unix::io::BorrowedFd<'_> {
+        self.0.raw().as_fd()
+    }
+}
+
+#[cfg(unix)]
+impl std::os::unix::io::AsFd for FileRef {
+    fn as_fd(&self) -> std::os::unix::io::BorrowedFd<'_> {
+        self.0.raw().as_fd()
+    }
+}
+
+#[cfg(windows)]
+impl std::os::windows::io::AsHandle for FileMut {
+    fn as_handle(&self) -> std::os::windows::io::BorrowedHandle<'_> {
+        self.0.raw().as_handle()
+    }
+}
+
+#[cfg(windows)]
+impl std::os::windows::io::AsHandle for FileRef {
+    fn as_handle(&self) -> std::os::windows::io::BorrowedHandle<'_> {
+        self.0.raw().as_handle()
     }
 }
@@ -0,0 +1,441 @@
+//! Configuration of [`FileMut`](crate::FileMut) and
+//! [`FileRef`](crate::FileRef) construction.
+
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::{FileMut, FileRef, DEFAULT_CACHE_BLOCK_SIZE, DEFAULT_CACHE_CAPACITY};
+
+pub(crate) type FetchCallback = Arc<dyn Fn(usize, usize) + Send + Sync>;
+pub(crate) type UnflushedCallback = Arc<dyn Fn(usize) + Send + Sync>;
+
+/// Options and flags which can be used to configure how a file-backed buffer
+/// is opened.
+///
+/// The unspecified options default to the values used by
+/// [`FileMut::new`](crate::FileMut::new) and
+/// [`FileRef::new`](crate::FileRef::new).
+///
+/// # Examples
+///
+/// ```
+/// use harrow::{FileMut, HugePages, OpenOptions};
+///
+/// let file = FileMut::with_options(
+///     "options.bin",
+///     4096,
+///     OpenOptions::new()
+///         .cache_capacity(2)
+///         .cache_block_size(4096)
+///         .huge_pages(HugePages::Prefer),
+/// )
+/// .unwrap();
+///
+/// assert!(file.len() >= 4096);
+/// ```
+///
+/// The buffers can be also opened directly from the options:
+///
+/// ```
+/// use harrow::OpenOptions;
+///
+/// let options = OpenOptions::new().cache_capacity(8).cache_block_size(64 << 20).clone();
+///
+/// let mut file = options.open_mut("options_open.bin", 4096).unwrap();
+/// file.write_at(b"hello", 0).unwrap();
+///
+/// # std::fs::write("options_open_ref.bin", b"hello").unwrap();
+/// let file = options.open_ref("options_open_ref.bin").unwrap();
+/// assert_eq!(file.len(), 5);
+/// # drop(file);
+/// # std::fs::remove_file("options_open_ref.bin").unwrap();
+/// ```
+#[derive(Clone)]
+pub struct OpenOptions {
+    pub(crate) cache_capacity: usize,
+    pub(crate) cache_block_size: usize,
+    pub(crate) eviction_policy: EvictionPolicy,
+    pub(crate) huge_pages: HugePages,
+    pub(crate) access_pattern: Option<AccessPattern>,
+    pub(crate) alignment: Option<usize>,
+    pub(crate) adaptive_block_size: Option<AdaptiveBlockSize>,
+    pub(crate) strided_hint: Option<StridedHint>,
+    pub(crate) on_fetch: Option<FetchCallback>,
+    pub(crate) on_unflushed_drop: Option<UnflushedCallback>,
+    pub(crate) temp_strategy: TempStrategy,
+    pub(crate) truncate: bool,
+}
+
+impl OpenOptions {
+    /// Creates a blank new set of options with default values.
+    pub fn new() -> Self {
+        Self {
+            cache_capacity: DEFAULT_CACHE_CAPACITY,
+            cache_block_size: DEFAULT_CACHE_BLOCK_SIZE,
+            eviction_policy: EvictionPolicy::Lru,
+            huge_pages: HugePages::Never,
+            access_pattern: None,
+            alignment: None,
+            adaptive_block_size: None,
+            strided_hint: None,
+            on_fetch: None,
+            on_unflushed_drop: None,
+            temp_strategy: TempStrategy::DeleteOnDrop,
+            truncate: true,
+        }
+    }
+
+    /// Opens writable buffer for the file at given `path` with these options.
+    /// This is the same as [`FileMut::with_options`](crate::FileMut::with_options).
+    pub fn open_mut<P: AsRef<Path>>(&self, path: P, len: usize) -> io::Result<FileMut> {
+        FileMut::with_options(path, len, self)
+    }
+
+    /// Opens read-only buffer for the file at given `path` with these options.
+    /// This is the same as [`FileRef::with_options`](crate::FileRef::with_options).
+    pub fn open_ref<P: AsRef<Path>>(&self, path: P) -> io::Result<FileRef> {
+        FileRef::with_options(path, self)
+    }
+
+    /// Sets the cache capacity, that is, the maximum number of mapped blocks
+    /// that are kept around when not used. It must be greater than zero,
+    /// otherwise opening fails with an error of kind
+    /// [`InvalidInput`](io::ErrorKind::InvalidInput).
+    pub fn cache_capacity(&mut self, cache_capacity: usize) -> &mut Self {
+        self.cache_capacity = cache_capacity;
+        self
+    }
+
+    /// Sets the cache block size. It is rounded to the closest bigger number
+    /// that is aligned with the alignment that is required or recommended by
+    /// the operating system.
+    pub fn cache_block_size(&mut self, cache_block_size: usize) -> &mut Self {
+        self.cache_block_size = cache_block_size;
+        self
+    }
+
+    /// Sets which cached block is unmapped when the cache capacity is
+    /// exceeded. See [`EvictionPolicy`](crate::EvictionPolicy) for details.
+    pub fn eviction_policy(&mut self, eviction_policy: EvictionPolicy) -> &mut Self {
+        self.eviction_policy = eviction_policy;
+        self
+    }
+
+    /// Sets whether the mappings should be backed by huge pages (called large
+    /// pages on Windows). See [`HugePages`](crate::HugePages) for details.
+    pub fn huge_pages(&mut self, huge_pages: HugePages) -> &mut Self {
+        self.huge_pages = huge_pages;
+        self
+    }
+
+    /// Sets the expected pattern of accessing the buffer, which is passed to
+    /// the operating system as a hint. See
+    /// [`AccessPattern`](crate::AccessPattern) for details.
+    pub fn access_pattern(&mut self, access_pattern: AccessPattern) -> &mut Self {
+        self.access_pattern = Some(access_pattern);
+        self
+    }
+
+    /// Overrides the alignment of the buffer length and of the mapped blocks,
+    /// which is otherwise given by the operating system (see
+    /// [`granularity`](crate::granularity)). The mappings must still start at
+    /// offsets accepted by the operating system, so the alignment is rounded
+    /// to the closest bigger multiple of the granularity (or of the huge page
+    /// size if huge pages are used). Zero means no override.
+    ///
+    /// This makes the layout independent of the host (e.g., 4 KiB pages on
+    /// Linux, 16 KiB pages on Apple Silicon and 64 KiB allocation granularity
+    /// on Windows), which is useful for deterministic tests.
+    pub fn alignment(&mut self, alignment: usize) -> &mut Self {
+        self.alignment = Some(alignment).filter(|alignment| *alignment > 0);
+        self
+    }
+
+    /// Sets when a temporary file (i.e., a non-existing file opened with
+    /// writable access) is deleted. See [`TempStrategy`](crate::TempStrategy)
+    /// for details. An existing file is never deleted.
+    ///
+    /// # Examples
+    ///
+    /// Create a new file that is kept after the buffer is dropped and open it
+    /// again later without losing its contents:
+    ///
+    /// ```
+    /// use harrow::{FileMut, OpenOptions, TempStrategy};
+    ///
+    /// let options = OpenOptions::new()
+    ///     .temp_strategy(TempStrategy::Keep)
+    ///     .truncate(false)
+    ///     .clone();
+    ///
+    /// let mut file = FileMut::with_options("temp_strategy.bin", 4096, &options).unwrap();
+    /// file.write_at(b"kept", 0).unwrap();
+    /// drop(file);
+    ///
+    /// let file = FileMut::with_options("temp_strategy.bin", 1, &options).unwrap();
+    /// assert_eq!(&file.view(0, 4).unwrap()[..], b"kept");
+    /// # drop(file);
+    /// # std::fs::remove_file("temp_strategy.bin").unwrap();
+    /// ```
+    pub fn temp_strategy(&mut self, temp_strategy: TempStrategy) -> &mut Self {
+        self.temp_strategy = temp_strategy;
+        self
+    }
+
+    /// Sets whether an existing file opened with writable access is truncated
+    /// to the requested length if it is longer. Defaults to `true`.
+    ///
+    /// If set to `false`, the buffer adopts the current size of the file
+    /// (rounded up to the alignment) when it is larger than the requested
+    /// length, so no existing data are lost.
+    pub fn truncate(&mut self, truncate: bool) -> &mut Self {
+        self.truncate = truncate;
+        self
+    }
+
+    /// Makes the size of the mapped blocks adapt to the lengths of the recent
+    /// requests instead of using the fixed cache block size. See
+    /// [`AdaptiveBlockSize`](crate::AdaptiveBlockSize) for details.
+    pub fn adaptive_block_size(&mut self, adaptive: AdaptiveBlockSize) -> &mut Self {
+        self.adaptive_block_size = Some(adaptive);
+        self
+    }
+
+    /// Tunes the size of the mapped blocks for strided access. See
+    /// [`StridedHint`](crate::StridedHint) for details.
+    pub fn strided_hint(&mut self, hint: StridedHint) -> &mut Self {
+        self.strided_hint = Some(hint);
+        self
+    }
+
+    /// Sets a callback that is invoked whenever the cache misses and a new
+    /// block is mapped. The callback receives the offset and the length of the
+    /// block that was actually mapped (i.e., aligned and extended to the cache
+    /// block size).
+    ///
+    /// This is useful for profiling and debugging the access patterns, e.g.,
+    /// by logging the mappings or integrating with a tracing library.
+    pub fn on_fetch<F>(&mut self, on_fetch: F) -> &mut Self
+    where
+        F: Fn(usize, usize) + Send + Sync + 'static,
+    {
+        self.on_fetch = Some(Arc::new(on_fetch));
+        self
+    }
+
+    /// Sets a callback that is invoked when a writable buffer is dropped while
+    /// some of its cached blocks contain modifications that were not flushed.
+    /// The callback receives the total size of such dirty blocks.
+    ///
+    /// This is meant for development, to discover the code that relies on the
+    /// data being persisted at drop time instead of flushing explicitly.
+    pub fn on_unflushed_drop<F>(&mut self, on_unflushed_drop: F) -> &mut Self
+    where
+        F: Fn(usize) + Send + Sync + 'static,
+    {
+        self.on_unflushed_drop = Some(Arc::new(on_unflushed_drop));
+        self
+    }
+}
+
+impl OpenOptions {
+    pub(crate) fn validate(&self) -> io::Result<()> {
+        if self.cache_capacity == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "cache capacity must be greater than zero",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Debug for OpenOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OpenOptions")
+            .field("cache_capacity", &self.cache_capacity)
+            .field("cache_block_size", &self.cache_block_size)
+            .field("eviction_policy", &self.eviction_policy)
+            .field("huge_pages", &self.huge_pages)
+            .field("access_pattern", &self.access_pattern)
+            .field("alignment", &self.alignment)
+            .field("temp_strategy", &self.temp_strategy)
+            .field("truncate", &self.truncate)
+            .field("adaptive_block_size", &self.adaptive_block_size)
+            .field("strided_hint", &self.strided_hint)
+            .field("on_fetch", &self.on_fetch.as_ref().map(|_| ".."))
+            .field(
+                "on_unflushed_drop",
+                &self.on_unflushed_drop.as_ref().map(|_| ".."),
+            )
+            .finish()
+    }
+}
+
+impl Default for OpenOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Policy of choosing the cached block that is unmapped when the cache
+/// capacity is exceeded.
+///
+/// Only the blocks that are not currently referenced by any view are
+/// considered, and the pinned blocks are never evicted. The ties are broken by
+/// evicting the least recently used block.
+///
+/// # Examples
+///
+/// ```
+/// use harrow::{EvictionPolicy, FileMut, OpenOptions};
+///
+/// let block = harrow::granularity();
+/// let file = FileMut::with_options(
+///     "eviction_policy.bin",
+///     4 * block,
+///     OpenOptions::new()
+///         .cache_capacity(2)
+///         .cache_block_size(block)
+///         .eviction_policy(EvictionPolicy::Lfu),
+/// )
+/// .unwrap();
+///
+/// // The first block is hot, the others are read only once.
+/// for _ in 0..3 {
+///     file.view(0, 1).unwrap();
+/// }
+/// for i in 1..4 {
+///     file.view(i * block, 1).unwrap();
+/// }
+///
+/// // The hot block survived the scattered reads, which would have evicted it
+/// // with the default LRU policy.
+/// let misses = file.cache_stats().misses;
+/// file.view(0, 1).unwrap();
+/// assert_eq!(file.cache_stats().misses, misses);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EvictionPolicy {
+    /// Evict the block that was used least recently. This is the default.
+    #[default]
+    Lru,
+    /// Evict the block that was mapped first, regardless of how it is used.
+    Fifo,
+    /// Evict the block that was used the least number of times since it was
+    /// mapped. This protects the hot blocks from being evicted by a burst of
+    /// reads that are not repeated.
+    Lfu,
+}
+
+/// Usage of huge pages (large pages on Windows) for virtual mappings.
+///
+/// Huge pages reduce the pressure on the translation lookaside buffer when
+/// accessing big mapped blocks, but they come with platform-specific
+/// requirements:
+///
+/// * On Linux, the mappings are created with `MAP_HUGETLB`, which is supported
+///   only for files that reside on a `hugetlbfs` file system.
+/// * On Windows, the mappings are created with `SEC_LARGE_PAGES` and
+///   `FILE_MAP_LARGE_PAGES`. This requires that the process holds and has
+///   enabled the `SeLockMemoryPrivilege`.
+/// * Other platforms do not support huge pages.
+///
+/// When huge pages are requested, the cache blocks are aligned to the huge
+/// page size instead of the usual granularity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HugePages {
+    /// Never use huge pages. This is the default.
+    #[default]
+    Never,
+    /// Try to use huge pages, but silently fall back to normal pages if that
+    /// is not possible.
+    Prefer,
+    /// Use huge pages and fail with an error if that is not possible.
+    Require,
+}
+
+/// Expected pattern of accessing the buffer.
+///
+/// This is a hint for the operating system that tunes its read-ahead:
+///
+/// * On Unix, each mapped block is advised using `madvise` with `MADV_NORMAL`,
+///   `MADV_RANDOM` or `MADV_SEQUENTIAL`, respectively.
+/// * On Windows, the file is opened with `FILE_FLAG_RANDOM_ACCESS` or
+///   `FILE_FLAG_SEQUENTIAL_SCAN` (or neither for `Normal`).
+///
+/// If not set, no advice is given on Unix and the file is opened for random
+/// access on Windows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessPattern {
+    /// No particular pattern, the operating system uses its defaults.
+    Normal,
+    /// The accesses are scattered over the buffer, so reading ahead is
+    /// mostly wasted.
+    Random,
+    /// The buffer is read from the beginning to the end, so aggressive
+    /// reading ahead pays off.
+    Sequential,
+}
+
+/// Strategy of deleting temporary files.
+///
+/// A non-existing file that is opened with writable access is considered as a
+/// temporary. This determines whether and when such file is deleted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TempStrategy {
+    /// Delete the file as early as possible. On Unix, the file is removed from
+    /// the directory immediately after it is opened, so it never appears
+    /// there, and the storage is released when the buffer is dropped. On
+    /// Windows, this is the same as `DeleteOnClose`. This is the default.
+    #[default]
+    DeleteOnDrop,
+    /// Keep the file in the directory while the buffer exists and delete it
+    /// when the buffer is dropped. This is useful for inspecting the file
+    /// during debugging.
+    DeleteOnClose,
+    /// Never delete the file.
+    Keep,
+}
+
+/// Bounds for the size of the mapped blocks that adapts to the access pattern.
+///
+/// A fixed cache block size is wasteful when the requests are tiny (a huge
+/// block is mapped for reading a single byte) and too small when the requests
+/// are huge. With the adaptive block size, the cache tracks a moving average of
+/// the lengths of the recent requests and sizes the newly mapped blocks so that
+/// a bunch of such requests fits into them, bounded by `min` and `max`.
+///
+/// Both bounds are rounded to the closest bigger number that is aligned with
+/// the alignment that is required or recommended by the operating system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AdaptiveBlockSize {
+    /// The minimum size of a mapped block.
+    pub min: usize,
+    /// The maximum size of a mapped block.
+    pub max: usize,
+}
+
+/// Hint that the buffer is accessed with a fixed stride, e.g., when reading a
+/// column of records.
+///
+/// Each access of such scan touches a different block and uses only a small
+/// part of it, so mapping whole cache blocks churns the cache with mostly
+/// unused memory. With this hint, the mapped blocks are sized to `width`
+/// around each access instead. It takes precedence over
+/// [`AdaptiveBlockSize`](crate::AdaptiveBlockSize).
+///
+/// The width is rounded to the closest bigger number that is aligned with the
+/// alignment that is required or recommended by the operating system. If the
+/// stride is not bigger than the cache block size, the hint is ignored,
+/// because the whole blocks are then useful.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StridedHint {
+    /// Distance between the offsets of two consecutive accesses.
+    pub stride: usize,
+    /// Number of bytes used by a single access.
+    pub width: usize,
+}
@@ -1,5 +1,8 @@
 //! This module is just a facade to the os-specific implementations.
 
+use std::io;
+use std::path::Path;
+
 #[cfg(unix)]
 pub use crate::unix::*;
 
@@ -14,3 +17,32 @@ pub use crate::windows::*;
 pub fn granularity() -> usize {
     get_alignment()
 }
+
+/// Exchanges the two files, so that each path refers to the file that was
+/// previously at the other one. This is the building block for committing a
+/// new version of a file by swapping it with the old one.
+///
+/// On Linux, this uses `renameat2` with `RENAME_EXCHANGE` and on macOS
+/// `renamex_np` with `RENAME_SWAP`, so the exchange is atomic. Both files must
+/// be on the same file system. Windows has no such primitive, so the exchange
+/// is done in two steps: `ReplaceFileW` atomically replaces `b` with `a` and
+/// keeps the original `b` as a backup, which is then moved to `a`. On other
+/// platforms, an error of kind [`Unsupported`](io::ErrorKind::Unsupported) is
+/// returned.
+///
+/// # Examples
+///
+/// ```
+/// use std::fs;
+///
+/// fs::write("swap_old.txt", "old").unwrap();
+/// fs::write("swap_new.txt", "new").unwrap();
+///
+/// harrow::swap_files("swap_old.txt", "swap_new.txt").unwrap();
+/// assert_eq!(fs::read_to_string("swap_old.txt").unwrap(), "new");
+/// # fs::remove_file("swap_old.txt").unwrap();
+/// # fs::remove_file("swap_new.txt").unwrap();
+/// ```
+pub fn swap_files<P: AsRef<Path>, Q: AsRef<Path>>(a: P, b: Q) -> io::Result<()> {
+    exchange_files(a.as_ref(), b.as_ref())
+}
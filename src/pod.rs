@@ -0,0 +1,260 @@
+//! Typed access to plain old data structs stored in the buffers.
+
+use std::fmt;
+use std::io;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+use bytemuck::Pod;
+
+use crate::{FileMut, FileRef, ViewMut, ViewRef};
+
+/// A read-only view of a single value of type `T` stored in the buffer.
+///
+/// This struct is created by [`FileRef::struct_ref`] and
+/// [`FileMut::struct_ref`] and dereferences to `&T`.
+pub struct StructViewRef<'a, T> {
+    view: ViewRef<'a>,
+    _ty: PhantomData<&'a T>,
+}
+
+impl<T: Pod> Deref for StructViewRef<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // The size and the alignment were checked when the view was created.
+        bytemuck::from_bytes(self.view.as_slice())
+    }
+}
+
+impl<T: Pod + fmt::Debug> fmt::Debug for StructViewRef<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+/// A mutable view of a single value of type `T` stored in the buffer.
+///
+/// This struct is created by [`FileMut::struct_mut`] and dereferences to
+/// `&mut T`.
+pub struct StructViewMut<'a, T> {
+    view: ViewMut<'a>,
+    _ty: PhantomData<&'a mut T>,
+}
+
+impl<T: Pod> Deref for StructViewMut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        bytemuck::from_bytes(self.view.as_slice())
+    }
+}
+
+impl<T: Pod> DerefMut for StructViewMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        bytemuck::from_bytes_mut(self.view.as_mut_slice())
+    }
+}
+
+impl<T: Pod + fmt::Debug> fmt::Debug for StructViewMut<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl ViewRef<'_> {
+    /// Reinterprets the view as a slice of values of type `T`.
+    ///
+    /// Fails with an error of kind
+    /// [`InvalidInput`](io::ErrorKind::InvalidInput) if the length of the view
+    /// is not a multiple of the size of `T` or if the view is not aligned for
+    /// `T`. The mapped blocks are aligned to the page size, so the latter
+    /// depends only on the offset of the view.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use harrow::FileMut;
+    ///
+    /// let mut file = FileMut::new("as_slice_of.bin", 4096).unwrap();
+    /// file.view_mut(8, 16).unwrap().as_mut_slice_of::<u64>().unwrap()[1] = 42;
+    ///
+    /// let view = file.view(8, 16).unwrap();
+    /// assert_eq!(view.as_slice_of::<u64>().unwrap(), &[0, 42]);
+    /// assert!(file.view(4, 16).unwrap().as_slice_of::<u64>().is_err());
+    /// ```
+    pub fn as_slice_of<T: Pod>(&self) -> io::Result<&[T]> {
+        bytemuck::try_cast_slice(self.as_slice()).map_err(cast_error::<T>)
+    }
+}
+
+impl ViewMut<'_> {
+    /// Reinterprets the view as a mutable slice of values of type `T`. See
+    /// [`ViewRef::as_slice_of`] for details.
+    pub fn as_mut_slice_of<T: Pod>(&mut self) -> io::Result<&mut [T]> {
+        bytemuck::try_cast_slice_mut(self.as_mut_slice()).map_err(cast_error::<T>)
+    }
+}
+
+fn cast_error<T>(error: bytemuck::PodCastError) -> io::Error {
+    let message = match error {
+        bytemuck::PodCastError::TargetAlignmentGreaterAndInputNotAligned => {
+            format!("view is not aligned to {} bytes", std::mem::align_of::<T>())
+        }
+        bytemuck::PodCastError::OutputSliceWouldHaveSlop => format!(
+            "view length is not a multiple of {} bytes",
+            std::mem::size_of::<T>()
+        ),
+        error => error.to_string(),
+    };
+
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+impl FileMut {
+    /// Acquires a read-only view of a value of type `T` stored at offset
+    /// `off`. See [`FileRef::struct_ref`] for details.
+    pub fn struct_ref<T: Pod>(&self, off: usize) -> io::Result<StructViewRef<'_, T>> {
+        check_alignment::<T>(off)?;
+
+        Ok(StructViewRef {
+            view: self.view(off, std::mem::size_of::<T>())?,
+            _ty: PhantomData,
+        })
+    }
+
+    /// Acquires a mutable view of a value of type `T` stored at offset `off`.
+    /// Exactly `size_of::<T>()` bytes are mapped and the modifications are
+    /// written directly to the buffer.
+    ///
+    /// Fails with an error of kind
+    /// [`InvalidInput`](io::ErrorKind::InvalidInput) if `off` is not a
+    /// multiple of the alignment of `T`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value does not fit into the buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bytemuck::{Pod, Zeroable};
+    /// use harrow::FileMut;
+    ///
+    /// #[derive(Clone, Copy)]
+    /// #[repr(C)]
+    /// struct Header {
+    ///     magic: u32,
+    ///     count: u32,
+    /// }
+    ///
+    /// // Or use the derive macros enabled by `derive` feature of bytemuck.
+    /// unsafe impl Zeroable for Header {}
+    /// unsafe impl Pod for Header {}
+    ///
+    /// let mut file = FileMut::new("struct_mut.bin", 4096).unwrap();
+    ///
+    /// {
+    ///     let mut header = file.struct_mut::<Header>(0).unwrap();
+    ///     header.magic = 0xcafe;
+    ///     header.count += 1;
+    /// }
+    ///
+    /// assert_eq!(file.struct_ref::<Header>(0).unwrap().count, 1);
+    /// assert!(file.struct_mut::<Header>(2).is_err());
+    /// ```
+    pub fn struct_mut<T: Pod>(&mut self, off: usize) -> io::Result<StructViewMut<'_, T>> {
+        check_alignment::<T>(off)?;
+
+        Ok(StructViewMut {
+            view: self.view_mut(off, std::mem::size_of::<T>())?,
+            _ty: PhantomData,
+        })
+    }
+}
+
+impl FileRef {
+    /// Acquires a read-only view of a value of type `T` stored at offset
+    /// `off`. Exactly `size_of::<T>()` bytes are mapped.
+    ///
+    /// Fails with an error of kind
+    /// [`InvalidInput`](io::ErrorKind::InvalidInput) if `off` is not a
+    /// multiple of the alignment of `T`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value does not fit into the buffer.
+    pub fn struct_ref<T: Pod>(&self, off: usize) -> io::Result<StructViewRef<'_, T>> {
+        check_alignment::<T>(off)?;
+
+        Ok(StructViewRef {
+            view: self.view(off, std::mem::size_of::<T>())?,
+            _ty: PhantomData,
+        })
+    }
+}
+
+// The mapped blocks are aligned to the page size, which is a multiple of the
+// alignment of any reasonable type, so it is sufficient to check the offset.
+fn check_alignment<T>(off: usize) -> io::Result<()> {
+    let align = std::mem::align_of::<T>();
+
+    if !off.is_multiple_of(align) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("offset {} is not aligned to {}", off, align),
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let mut file = FileMut::new("pod_roundtrip.tmp", 64).unwrap();
+
+        *file.struct_mut::<[u64; 2]>(8).unwrap() = [1, 2];
+        file.struct_mut::<u32>(4).unwrap().clone_from(&7);
+
+        assert_eq!(*file.struct_ref::<[u64; 2]>(8).unwrap(), [1, 2]);
+        assert_eq!(file.read_u32_ne_at(4).unwrap(), 7);
+        assert_eq!(file.read_u64_ne_at(16).unwrap(), 2);
+
+        assert_eq!(
+            file.struct_ref::<u64>(4).unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+    }
+
+    #[test]
+    fn slice_of() {
+        let file = FileMut::new("pod_slice_of.tmp", 64).unwrap();
+
+        file.view_mut(8, 24)
+            .unwrap()
+            .as_mut_slice_of::<u64>()
+            .unwrap()
+            .copy_from_slice(&[1, 2, 3]);
+
+        assert_eq!(file.read_u64_ne_at(16).unwrap(), 2);
+        assert_eq!(
+            file.view(8, 24).unwrap().as_slice_of::<u64>().unwrap(),
+            &[1, 2, 3]
+        );
+        assert_eq!(
+            file.view(12, 8).unwrap().as_slice_of::<u32>().unwrap(),
+            &[0, 2]
+        );
+
+        // Misaligned offset and a length that is not a multiple of the size.
+        for (off, len) in [(4, 16), (8, 20)] {
+            let view = file.view(off, len).unwrap();
+            let error = view.as_slice_of::<u64>().unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        }
+    }
+}
@@ -0,0 +1,245 @@
+//! Implementation of the standard reading traits.
+
+use std::convert::TryFrom;
+use std::io::{self, BufRead, Read, Seek, SeekFrom};
+
+use crate::cache::ViewRef;
+use crate::infra::File;
+
+/// A reading cursor over the underlying file.
+///
+/// It implements [`Read`] and [`BufRead`], where the buffer is the cached block
+/// itself, so there is no additional buffering layer (and no need for wrapping
+/// it in [`BufReader`](std::io::BufReader)).
+///
+/// It also implements [`Seek`]. Since the length of the buffer is aligned, its
+/// tail may be just padding. The end for reading and for
+/// [`SeekFrom::End`] is the length of the buffer by default, but the logical
+/// length of the data can be set by [`with_len`](Reader::with_len).
+///
+/// This struct is created by [`FileRef::reader`](crate::FileRef::reader) or
+/// [`FileMut::reader`](crate::FileMut::reader).
+pub struct Reader<'a> {
+    file: &'a File,
+    pos: usize,
+    // The logical end of the data.
+    len: usize,
+    // The view at the cursor position together with its offset in the file.
+    view: Option<(usize, ViewRef<'a>)>,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn from_file(file: &'a File) -> Self {
+        Self {
+            file,
+            pos: 0,
+            len: file.len(),
+            view: None,
+        }
+    }
+
+    /// Sets the logical length of the data, e.g., the number of bytes actually
+    /// written before the buffer was padded to the aligned length. The reading
+    /// stops there and [`SeekFrom::End`] is relative to it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len` is greater than the buffer length.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::{Read, Seek, SeekFrom};
+    /// use harrow::FileMut;
+    ///
+    /// let mut file = FileMut::new("reader_with_len.bin", 4096).unwrap();
+    /// file.write_at(b"hello world", 0).unwrap();
+    ///
+    /// let mut reader = file.reader().with_len(11);
+    /// reader.seek(SeekFrom::End(-5)).unwrap();
+    ///
+    /// let mut word = String::new();
+    /// reader.read_to_string(&mut word).unwrap();
+    /// assert_eq!(word, "world");
+    /// ```
+    pub fn with_len(mut self, len: usize) -> Self {
+        if len > self.file.len() {
+            panic!("len out of bounds");
+        }
+
+        self.len = len;
+        self
+    }
+
+    /// Returns the current position of the cursor.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+}
+
+impl Read for Reader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let count = std::cmp::min(available.len(), buf.len());
+        buf[..count].copy_from_slice(&available[..count]);
+        self.consume(count);
+        Ok(count)
+    }
+}
+
+impl BufRead for Reader<'_> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        let len = self.len;
+
+        // The position may be past the end after seeking.
+        if self.pos >= len {
+            return Ok(&[]);
+        }
+
+        let in_view = match &self.view {
+            Some((off, view)) => *off <= self.pos && self.pos < off + view.len(),
+            None => false,
+        };
+
+        if !in_view {
+            // Drop the old view first so its block can be reused.
+            self.view = None;
+
+            let block_size = std::cmp::min(len - self.pos, self.file.cache_block_size());
+            let view = self.file.view(self.pos, block_size)?;
+            self.view = Some((self.pos, view));
+        }
+
+        let (off, view) = self.view.as_ref().unwrap();
+        let end = std::cmp::min(off + view.len(), len);
+        Ok(&view[self.pos - off..end - off])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = std::cmp::min(self.pos + amt, std::cmp::max(self.pos, self.len));
+    }
+}
+
+impl Seek for Reader<'_> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (base, offset) = match pos {
+            SeekFrom::Start(offset) => (0, offset as i128),
+            SeekFrom::End(offset) => (self.len, offset as i128),
+            SeekFrom::Current(offset) => (self.pos, offset as i128),
+        };
+
+        // Like for files, seeking past the end is allowed (the reading then
+        // returns no bytes), but seeking before the start is an error.
+        match usize::try_from(base as i128 + offset) {
+            Ok(pos) => {
+                self.pos = pos;
+                Ok(pos as u64)
+            }
+            Err(_) => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )),
+        }
+    }
+
+    fn stream_position(&mut self) -> io::Result<u64> {
+        Ok(self.pos as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io::{self, BufRead, Read, Seek, SeekFrom, Write};
+
+    use crate::{os, FileMut, FileRef, OpenOptions};
+
+    #[test]
+    fn read_lines_across_blocks() {
+        let path = "reader_lines.tmp";
+        let alignment = os::get_alignment();
+        let long_line = "x".repeat(alignment + 10);
+
+        {
+            let mut file = fs::File::create(path).unwrap();
+            write!(file, "first\n{}\nlast", long_line).unwrap();
+        }
+
+        let file =
+            FileRef::with_options(path, OpenOptions::new().cache_block_size(alignment)).unwrap();
+
+        let lines = file
+            .reader()
+            .lines()
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            lines,
+            vec!["first".to_string(), long_line, "last".to_string()]
+        );
+
+        let mut reader = file.reader();
+        let mut buf = [0; 3];
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"fir");
+        assert_eq!(reader.position(), 3);
+
+        let mut rest = Vec::new();
+        reader.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest.len(), file.len() - 3);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn seek() {
+        let alignment = os::get_alignment();
+        let mut file = FileMut::with_options(
+            "reader_seek.tmp",
+            2 * alignment,
+            OpenOptions::new().cache_block_size(alignment),
+        )
+        .unwrap();
+        file.write_at(b"across", alignment - 3).unwrap();
+
+        let mut reader = file.reader();
+        let mut buf = [0; 6];
+
+        assert_eq!(
+            reader.seek(SeekFrom::Start(alignment as u64 - 3)).unwrap() as usize,
+            alignment - 3
+        );
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"across");
+
+        reader.seek(SeekFrom::Current(-3)).unwrap();
+        reader.read_exact(&mut buf[..3]).unwrap();
+        assert_eq!(&buf[..3], b"oss");
+
+        assert_eq!(
+            reader.seek(SeekFrom::End(0)).unwrap() as usize,
+            2 * alignment
+        );
+        assert_eq!(
+            reader.seek(SeekFrom::End(10)).unwrap() as usize,
+            2 * alignment + 10
+        );
+        assert_eq!(reader.read(&mut buf).unwrap(), 0);
+
+        assert_eq!(
+            reader
+                .seek(SeekFrom::Current(-(3 * alignment as i64)))
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::InvalidInput
+        );
+        assert_eq!(reader.position(), 2 * alignment + 10);
+
+        // The logical end in the middle of a block.
+        let mut reader = file.reader().with_len(alignment + 3);
+        reader.seek(SeekFrom::End(-6)).unwrap();
+        let mut rest = Vec::new();
+        reader.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, b"across");
+    }
+}
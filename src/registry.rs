@@ -0,0 +1,93 @@
+//! Process-local registry of opened files.
+//!
+//! File locks are only advisory on most platforms and, on Unix, they are held
+//! per process, so they do not prevent opening the same file twice within one
+//! process. Two handles to the same file would have their own caches, which
+//! breaks the consistency assumptions of the cache. The registry detects such
+//! aliasing at construction.
+
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+
+static REGISTRY: Lazy<Mutex<HashMap<PathBuf, Entry>>> = Lazy::new(Default::default);
+
+#[derive(Default)]
+struct Entry {
+    readers: usize,
+    writer: bool,
+}
+
+/// A registration of an opened file. The file is unregistered when this is
+/// dropped.
+#[derive(Debug)]
+pub struct Registration {
+    path: PathBuf,
+    writable: bool,
+}
+
+impl Registration {
+    /// Registers an existing file. Fails if the file is already opened with
+    /// writable access, or if writable access is requested for a file that is
+    /// already opened.
+    pub fn register(path: &Path, writable: bool) -> io::Result<Self> {
+        let path = path.canonicalize()?;
+        let mut registry = REGISTRY.lock().unwrap();
+        let entry = registry.entry(path.clone()).or_default();
+
+        if entry.writer || (writable && entry.readers > 0) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                "the file is already opened in this process",
+            ));
+        }
+
+        if writable {
+            entry.writer = true;
+        } else {
+            entry.readers += 1;
+        }
+
+        Ok(Self { path, writable })
+    }
+
+    /// Turns the registration with writable access into a registration with
+    /// read-only access, so that the file can be opened for reading again.
+    pub fn downgrade(&mut self) {
+        if !self.writable {
+            return;
+        }
+
+        let mut registry = REGISTRY.lock().unwrap();
+        let entry = registry.entry(self.path.clone()).or_default();
+        entry.writer = false;
+        entry.readers += 1;
+        self.writable = false;
+    }
+
+    /// Returns the canonical path of the registered file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        let mut registry = REGISTRY.lock().unwrap();
+
+        if let Some(entry) = registry.get_mut(&self.path) {
+            if self.writable {
+                entry.writer = false;
+            } else {
+                entry.readers -= 1;
+            }
+
+            if !entry.writer && entry.readers == 0 {
+                registry.remove(&self.path);
+            }
+        }
+    }
+}
@@ -0,0 +1,99 @@
+//! Endian-aware accessors for scalar values.
+
+use std::io;
+
+use crate::{FileMut, FileRef};
+
+// Invokes the given macro with the list of all supported scalar types and byte
+// orders.
+macro_rules! with_scalars {
+    ($m:ident) => {
+        $m! {
+            (read_u16_le_at, write_u16_le_at, u16, from_le_bytes, to_le_bytes, "little-endian"),
+            (read_u16_be_at, write_u16_be_at, u16, from_be_bytes, to_be_bytes, "big-endian"),
+            (read_u16_ne_at, write_u16_ne_at, u16, from_ne_bytes, to_ne_bytes, "native-endian"),
+            (read_i16_le_at, write_i16_le_at, i16, from_le_bytes, to_le_bytes, "little-endian"),
+            (read_i16_be_at, write_i16_be_at, i16, from_be_bytes, to_be_bytes, "big-endian"),
+            (read_i16_ne_at, write_i16_ne_at, i16, from_ne_bytes, to_ne_bytes, "native-endian"),
+            (read_u32_le_at, write_u32_le_at, u32, from_le_bytes, to_le_bytes, "little-endian"),
+            (read_u32_be_at, write_u32_be_at, u32, from_be_bytes, to_be_bytes, "big-endian"),
+            (read_u32_ne_at, write_u32_ne_at, u32, from_ne_bytes, to_ne_bytes, "native-endian"),
+            (read_i32_le_at, write_i32_le_at, i32, from_le_bytes, to_le_bytes, "little-endian"),
+            (read_i32_be_at, write_i32_be_at, i32, from_be_bytes, to_be_bytes, "big-endian"),
+            (read_i32_ne_at, write_i32_ne_at, i32, from_ne_bytes, to_ne_bytes, "native-endian"),
+            (read_u64_le_at, write_u64_le_at, u64, from_le_bytes, to_le_bytes, "little-endian"),
+            (read_u64_be_at, write_u64_be_at, u64, from_be_bytes, to_be_bytes, "big-endian"),
+            (read_u64_ne_at, write_u64_ne_at, u64, from_ne_bytes, to_ne_bytes, "native-endian"),
+            (read_i64_le_at, write_i64_le_at, i64, from_le_bytes, to_le_bytes, "little-endian"),
+            (read_i64_be_at, write_i64_be_at, i64, from_be_bytes, to_be_bytes, "big-endian"),
+            (read_i64_ne_at, write_i64_ne_at, i64, from_ne_bytes, to_ne_bytes, "native-endian"),
+            (read_u128_le_at, write_u128_le_at, u128, from_le_bytes, to_le_bytes, "little-endian"),
+            (read_u128_be_at, write_u128_be_at, u128, from_be_bytes, to_be_bytes, "big-endian"),
+            (read_u128_ne_at, write_u128_ne_at, u128, from_ne_bytes, to_ne_bytes, "native-endian"),
+            (read_i128_le_at, write_i128_le_at, i128, from_le_bytes, to_le_bytes, "little-endian"),
+            (read_i128_be_at, write_i128_be_at, i128, from_be_bytes, to_be_bytes, "big-endian"),
+            (read_i128_ne_at, write_i128_ne_at, i128, from_ne_bytes, to_ne_bytes, "native-endian"),
+            (read_f32_le_at, write_f32_le_at, f32, from_le_bytes, to_le_bytes, "little-endian"),
+            (read_f32_be_at, write_f32_be_at, f32, from_be_bytes, to_be_bytes, "big-endian"),
+            (read_f32_ne_at, write_f32_ne_at, f32, from_ne_bytes, to_ne_bytes, "native-endian"),
+            (read_f64_le_at, write_f64_le_at, f64, from_le_bytes, to_le_bytes, "little-endian"),
+            (read_f64_be_at, write_f64_be_at, f64, from_be_bytes, to_be_bytes, "big-endian"),
+            (read_f64_ne_at, write_f64_ne_at, f64, from_ne_bytes, to_ne_bytes, "native-endian"),
+        }
+    };
+}
+
+macro_rules! impl_reads {
+    ($(($read:ident, $write:ident, $ty:ty, $from:ident, $to:ident, $endian:literal),)*) => {
+        $(
+            #[doc = concat!("Reads `", stringify!($ty), "` stored in ", $endian, " byte order at offset `off`.")]
+            pub fn $read(&self, off: usize) -> io::Result<$ty> {
+                let mut bytes = [0; std::mem::size_of::<$ty>()];
+                bytes.copy_from_slice(&self.0.view(off, std::mem::size_of::<$ty>())?);
+                Ok(<$ty>::$from(bytes))
+            }
+        )*
+    };
+}
+
+macro_rules! impl_writes {
+    ($(($read:ident, $write:ident, $ty:ty, $from:ident, $to:ident, $endian:literal),)*) => {
+        $(
+            #[doc = concat!("Writes `", stringify!($ty), "` in ", $endian, " byte order at offset `off`.")]
+            pub fn $write(&mut self, value: $ty, off: usize) -> io::Result<()> {
+                self.write_at(&value.$to(), off)
+            }
+        )*
+    };
+}
+
+impl FileMut {
+    with_scalars!(impl_reads);
+    with_scalars!(impl_writes);
+}
+
+impl FileRef {
+    with_scalars!(impl_reads);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let mut file = FileMut::new("scalar_roundtrip.tmp", 64).unwrap();
+
+        file.write_u32_be_at(0x0102_0304, 0).unwrap();
+        file.write_i64_le_at(-2, 4).unwrap();
+        file.write_f64_ne_at(1.5, 12).unwrap();
+
+        assert_eq!(&file.view(0, 4).unwrap()[..], &[1, 2, 3, 4]);
+        assert_eq!(file.read_u32_le_at(0).unwrap(), 0x0403_0201);
+        assert_eq!(file.read_u32_be_at(0).unwrap(), 0x0102_0304);
+        assert_eq!(file.read_u16_be_at(2).unwrap(), 0x0304);
+        assert_eq!(file.read_i64_le_at(4).unwrap(), -2);
+        assert_eq!(file.read_u64_le_at(4).unwrap(), u64::MAX - 1);
+        assert_eq!(file.read_f64_ne_at(12).unwrap(), 1.5);
+    }
+}
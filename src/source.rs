@@ -0,0 +1,222 @@
+//! Custom sources of blocks that can be fronted by the cache.
+
+use std::io;
+
+use crate::cache::{Cache, ViewRef};
+use crate::os;
+
+/// A source of data blocks that can be cached by [`CachedSource`].
+///
+/// Harrow uses virtual mappings of local files as the source of blocks, but
+/// the cache and the views work with any memory blocks. Implementing this
+/// trait allows to front, e.g., a network storage with the cache.
+pub trait BlockSource {
+    /// Returns the total length of the data.
+    fn len(&self) -> usize;
+
+    /// Returns `true` if there is no data.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Fetches a block that contains at least the bytes in `off..off + len`.
+    /// The block may start before `off` and be longer than `len` so that it
+    /// can serve future requests.
+    fn fetch(&self, off: usize, len: usize) -> io::Result<Block>;
+}
+
+/// A block of data fetched from a [`BlockSource`].
+pub struct Block(Inner);
+
+enum Inner {
+    Mapped(os::RawView),
+    Owned { off: usize, data: Box<[u8]> },
+}
+
+impl Block {
+    /// Creates a block that holds `data` which starts at offset `off` in the
+    /// source.
+    pub fn new(off: usize, data: Vec<u8>) -> Self {
+        Self(Inner::Owned {
+            off,
+            data: data.into_boxed_slice(),
+        })
+    }
+
+    /// Returns the offset of the block in the source.
+    pub fn offset(&self) -> usize {
+        match &self.0 {
+            Inner::Mapped(view) => view.offset(),
+            Inner::Owned { off, .. } => *off,
+        }
+    }
+
+    /// Returns the length of the block.
+    pub fn len(&self) -> usize {
+        match &self.0 {
+            Inner::Mapped(view) => view.len(),
+            Inner::Owned { data, .. } => data.len(),
+        }
+    }
+
+    /// Returns `true` if the block is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub(crate) fn as_ptr(&self) -> *const u8 {
+        match &self.0 {
+            Inner::Mapped(view) => view.as_ptr(),
+            Inner::Owned { data, .. } => data.as_ptr(),
+        }
+    }
+
+    pub(crate) fn flush(&self) -> io::Result<()> {
+        match &self.0 {
+            Inner::Mapped(view) => view.flush(),
+            // The owned blocks are never modified.
+            Inner::Owned { .. } => Ok(()),
+        }
+    }
+
+    pub(crate) fn advise_willneed(&self) -> io::Result<()> {
+        match &self.0 {
+            Inner::Mapped(view) => view.advise_willneed(),
+            // The owned blocks are already in the memory.
+            Inner::Owned { .. } => Ok(()),
+        }
+    }
+
+    // Drops the pages of the part of the block that overlaps the range from
+    // the memory of the process.
+    pub(crate) fn release(&self, off: usize, len: usize) -> io::Result<()> {
+        match &self.0 {
+            Inner::Mapped(view) => {
+                let start = std::cmp::max(off, view.offset());
+                let end = std::cmp::min(off + len, view.offset() + view.len());
+
+                if start < end {
+                    view.release(start - view.offset(), end - start)
+                } else {
+                    Ok(())
+                }
+            }
+            // The owned blocks cannot be dropped while in the cache.
+            Inner::Owned { .. } => Ok(()),
+        }
+    }
+
+    pub(crate) fn unmap(self) -> io::Result<()> {
+        match self.0 {
+            Inner::Mapped(view) => view.unmap(),
+            Inner::Owned { .. } => Ok(()),
+        }
+    }
+}
+
+impl From<os::RawView> for Block {
+    fn from(view: os::RawView) -> Self {
+        Self(Inner::Mapped(view))
+    }
+}
+
+/// A read-only cache of blocks fetched from a custom [`BlockSource`].
+///
+/// This uses the same least-recently used cache and views as
+/// [`FileRef`](crate::FileRef).
+///
+/// # Examples
+///
+/// ```
+/// use std::io;
+/// use harrow::{Block, BlockSource, CachedSource};
+///
+/// // A source that generates the bytes on demand.
+/// struct Counter;
+///
+/// impl BlockSource for Counter {
+///     fn len(&self) -> usize {
+///         1 << 20
+///     }
+///
+///     fn fetch(&self, off: usize, len: usize) -> io::Result<Block> {
+///         let off = off / 256 * 256;
+///         let data = (off..off + std::cmp::max(len, 256)).map(|i| i as u8).collect();
+///         Ok(Block::new(off, data))
+///     }
+/// }
+///
+/// let cached = CachedSource::new(Counter, 4);
+/// assert_eq!(cached.view(1000, 3).unwrap().as_slice(), &[232, 233, 234]);
+/// ```
+pub struct CachedSource<S> {
+    source: S,
+    cache: Cache,
+}
+
+impl<S: BlockSource> CachedSource<S> {
+    /// Creates the cache for the source with given capacity of blocks.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the capacity is zero.
+    pub fn new(source: S, capacity: usize) -> Self {
+        Self {
+            source,
+            cache: Cache::with_capacity(capacity),
+        }
+    }
+
+    /// Returns the total length of the data.
+    pub fn len(&self) -> usize {
+        self.source.len()
+    }
+
+    /// Returns `true` if there is no data.
+    pub fn is_empty(&self) -> bool {
+        self.source.is_empty()
+    }
+
+    /// Returns a reference to the underlying source.
+    pub fn source(&self) -> &S {
+        &self.source
+    }
+
+    /// Acquires a view to bytes at offset `off` with length `len`. The block
+    /// is fetched from the source if it is not cached.
+    ///
+    /// Fails with an error of kind [`InvalidData`](io::ErrorKind::InvalidData)
+    /// if the source returns a block that does not contain the requested
+    /// bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the requested bytes are out of bounds.
+    pub fn view(&self, off: usize, len: usize) -> io::Result<ViewRef<'_>> {
+        match off.checked_add(len) {
+            Some(end) if end <= self.source.len() => {}
+            Some(_) => panic!("out of bounds"),
+            None => panic!("offset + length overflows"),
+        }
+
+        self.cache.record_request(len);
+        self.cache.take(off, len).or_fetch(|off, len| {
+            let block = self.source.fetch(off, len)?;
+
+            if block.offset() <= off && block.offset() + block.len() >= off + len {
+                Ok(block)
+            } else {
+                Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "fetched block does not contain the requested bytes",
+                ))
+            }
+        })
+    }
+}
+
+// SAFETY: The cache holds only pointers to the blocks it owns and never
+// modifies them through shared references, so it is safe to share it between
+// threads as long as the source can be shared.
+unsafe impl<S: Send> Send for CachedSource<S> {}
+unsafe impl<S: Sync> Sync for CachedSource<S> {}
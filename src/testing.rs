@@ -0,0 +1,260 @@
+//! Model-based testing of the buffers.
+//!
+//! This module is available with the `testing` feature. It provides [`Model`]
+//! which applies the same operations to a [`FileMut`] and to an in-memory
+//! mirror and checks that they agree. It is intended for property-based
+//! testing and fuzzing (e.g., with `proptest` or `arbitrary`), both of harrow
+//! itself and of the code built on top of it.
+//!
+//! # Examples
+//!
+//! ```
+//! use harrow::testing::{Model, Op};
+//! use harrow::FileMut;
+//!
+//! let file = FileMut::new("model.bin", 8192).unwrap();
+//! let mut model = Model::new(file, 16384).unwrap();
+//!
+//! model.apply(&Op::WriteAt { off: 4090, data: b"across".to_vec() }).unwrap();
+//! model.apply(&Op::CopyWithin { src: 4090, dst: 0, count: 6 }).unwrap();
+//! model.apply(&Op::Resize { new_len: 12000 }).unwrap();
+//! model.apply(&Op::View { off: 0, len: 6 }).unwrap();
+//! model.check().unwrap();
+//! ```
+
+use std::fmt;
+use std::io;
+
+use crate::FileMut;
+
+/// An operation applied to both the buffer and the mirror.
+///
+/// The offsets and lengths may be arbitrary, they are clamped into the bounds
+/// of the buffer when applied. Hence any sequence of operations generated by a
+/// fuzzer is valid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Op {
+    /// Writes the data at given offset ([`FileMut::write_at`]).
+    WriteAt {
+        /// Offset of the write.
+        off: usize,
+        /// Data to be written.
+        data: Vec<u8>,
+    },
+    /// Copies bytes within the buffer ([`FileMut::copy_within`]).
+    CopyWithin {
+        /// Offset of the source range.
+        src: usize,
+        /// Offset of the destination range.
+        dst: usize,
+        /// Number of bytes to copy.
+        count: usize,
+    },
+    /// Resizes the buffer ([`FileMut::resize`]).
+    Resize {
+        /// The requested length.
+        new_len: usize,
+    },
+    /// Acquires a view and compares it with the mirror
+    /// ([`FileMut::view`]).
+    View {
+        /// Offset of the view.
+        off: usize,
+        /// Length of the view.
+        len: usize,
+    },
+}
+
+/// A buffer together with its in-memory mirror.
+///
+/// Every operation is applied to both and the results are compared. A
+/// mismatch is reported as an error of kind
+/// [`InvalidData`](io::ErrorKind::InvalidData), so the failing operation can
+/// be reported by the test harness.
+pub struct Model {
+    file: FileMut,
+    mirror: Vec<u8>,
+    max_len: usize,
+}
+
+impl Model {
+    /// Creates the model for the buffer. The mirror is initialized with the
+    /// current contents of the buffer. The length requested by
+    /// [`Op::Resize`] is clamped to `max_len`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_len` is zero.
+    pub fn new(file: FileMut, max_len: usize) -> io::Result<Self> {
+        assert!(max_len > 0, "max_len must be greater than zero");
+
+        let mirror = file.read_vec(0, file.len())?;
+
+        Ok(Self {
+            file,
+            mirror,
+            max_len,
+        })
+    }
+
+    /// Applies the operation to both the buffer and the mirror.
+    pub fn apply(&mut self, op: &Op) -> io::Result<()> {
+        let len = self.mirror.len();
+
+        match op {
+            Op::WriteAt { off, data } => {
+                let off = off % (len + 1);
+                let data = &data[..std::cmp::min(data.len(), len - off)];
+
+                self.file.write_at(data, off)?;
+                self.mirror[off..off + data.len()].copy_from_slice(data);
+                Ok(())
+            }
+            Op::CopyWithin { src, dst, count } => {
+                let src = src % (len + 1);
+                let dst = dst % (len + 1);
+                let count = std::cmp::min(*count, len - std::cmp::max(src, dst));
+
+                self.file.copy_within(src, dst, count)?;
+                self.mirror.copy_within(src..src + count, dst);
+                Ok(())
+            }
+            Op::Resize { new_len } => {
+                let new_len = new_len % self.max_len + 1;
+
+                self.file.resize(new_len)?;
+                // The buffer length is aligned, so it is taken from the buffer.
+                // The new bytes are zeros.
+                self.mirror.resize(self.file.len(), 0);
+                Ok(())
+            }
+            Op::View { off, len: view_len } => {
+                let off = off % (len + 1);
+                let view_len = std::cmp::min(*view_len, len - off);
+
+                let view = self.file.view(off, view_len)?;
+                compare(off, &view, &self.mirror[off..off + view_len])
+            }
+        }
+    }
+
+    /// Compares the whole contents of the buffer with the mirror.
+    pub fn check(&self) -> io::Result<()> {
+        if self.file.len() != self.mirror.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "length mismatch: buffer has {}, mirror has {}",
+                    self.file.len(),
+                    self.mirror.len()
+                ),
+            ));
+        }
+
+        let contents = self.file.read_vec(0, self.file.len())?;
+        compare(0, &contents, &self.mirror)
+    }
+
+    /// Returns a reference to the buffer.
+    pub fn file(&self) -> &FileMut {
+        &self.file
+    }
+
+    /// Returns the mirror of the buffer contents.
+    pub fn mirror(&self) -> &[u8] {
+        &self.mirror
+    }
+
+    /// Consumes the model, returning the buffer.
+    pub fn into_inner(self) -> FileMut {
+        self.file
+    }
+}
+
+impl fmt::Debug for Model {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Model")
+            .field("len", &self.mirror.len())
+            .field("max_len", &self.max_len)
+            .finish_non_exhaustive()
+    }
+}
+
+fn compare(off: usize, actual: &[u8], expected: &[u8]) -> io::Result<()> {
+    match actual.iter().zip(expected).position(|(a, e)| a != e) {
+        Some(pos) => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "contents mismatch at offset {}: buffer has {}, mirror has {}",
+                off + pos,
+                actual[pos],
+                expected[pos]
+            ),
+        )),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{os, OpenOptions};
+
+    // A simple xorshift generator, so the sequence of operations is
+    // reproducible.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0 as usize
+        }
+    }
+
+    #[test]
+    fn random_operations() {
+        let alignment = os::get_alignment();
+        let file = FileMut::with_options(
+            "testing_random_operations.tmp",
+            4 * alignment,
+            OpenOptions::new()
+                .cache_capacity(2)
+                .cache_block_size(alignment),
+        )
+        .unwrap();
+
+        let mut model = Model::new(file, 8 * alignment).unwrap();
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+
+        for i in 0..500 {
+            let op = match rng.next() % 8 {
+                0 => Op::Resize {
+                    new_len: rng.next(),
+                },
+                1 | 2 => Op::CopyWithin {
+                    src: rng.next(),
+                    dst: rng.next(),
+                    count: rng.next() % (2 * alignment),
+                },
+                3 | 4 => Op::View {
+                    off: rng.next(),
+                    len: rng.next() % (2 * alignment),
+                },
+                _ => Op::WriteAt {
+                    off: rng.next(),
+                    data: (0..rng.next() % (2 * alignment))
+                        .map(|j| (i + j) as u8)
+                        .collect(),
+                },
+            };
+
+            model
+                .apply(&op)
+                .unwrap_or_else(|err| panic!("{:?} failed: {}", op, err));
+        }
+
+        model.check().unwrap();
+    }
+}
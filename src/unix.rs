@@ -1,17 +1,33 @@
 use std::ffi::CString;
 use std::io;
-use std::path::Path;
+use std::mem::ManuallyDrop;
+use std::path::{Path, PathBuf};
 use std::ptr::NonNull;
 
 use crate::ext::ResultExt;
+use crate::options::{AccessPattern, HugePages, OpenOptions, TempStrategy};
 
 pub struct RawFile {
     fd: libc::c_int,
     len: usize,
+    huge_pages: HugePages,
+    access_pattern: Option<AccessPattern>,
+    // Path of a temporary file that should be removed when dropped.
+    remove_on_drop: Option<CString>,
+    // Path of a temporary file that was removed from the directory right after
+    // it was opened.
+    unlinked: Option<CString>,
+    // Whether the file descriptor was already closed by an explicit call.
+    closed: bool,
+    // Whether the views are mapped privately, i.e., the modifications are
+    // never written to the file.
+    private: bool,
 }
 
 impl RawFile {
-    pub fn open_writable(path: &Path, len: usize) -> io::Result<Self> {
+    pub fn open_writable(path: &Path, len: usize, options: &OpenOptions) -> io::Result<Self> {
+        check_huge_pages(options.huge_pages)?;
+
         let exists = path.exists();
         let path = cstr(path)?;
 
@@ -34,7 +50,7 @@ impl RawFile {
             // Simulate delete_on_close. The file will be removed from the
             // directory, but will exists while we have the file descriptor
             // open.
-            if !exists {
+            if !exists && options.temp_strategy == TempStrategy::DeleteOnDrop {
                 ffi::remove(&path).cleanup(|| ffi::close(fd))?;
             }
 
@@ -49,10 +65,28 @@ impl RawFile {
             fd
         };
 
-        Ok(Self { fd, len })
+        let (remove_on_drop, unlinked) = match options.temp_strategy {
+            _ if exists => (None, None),
+            TempStrategy::DeleteOnDrop => (None, Some(path)),
+            TempStrategy::DeleteOnClose => (Some(path), None),
+            TempStrategy::Keep => (None, None),
+        };
+
+        Ok(Self {
+            fd,
+            len,
+            huge_pages: options.huge_pages,
+            access_pattern: options.access_pattern,
+            remove_on_drop,
+            unlinked,
+            closed: false,
+            private: false,
+        })
     }
 
-    pub fn open_readonly(path: &Path) -> io::Result<Option<Self>> {
+    pub fn open_readonly(path: &Path, options: &OpenOptions) -> io::Result<Option<Self>> {
+        check_huge_pages(options.huge_pages)?;
+
         let len = path.metadata()?.len() as usize;
 
         if len == 0 {
@@ -73,7 +107,52 @@ impl RawFile {
             fd
         };
 
-        Ok(Some(Self { fd, len }))
+        Ok(Some(Self {
+            fd,
+            len,
+            huge_pages: options.huge_pages,
+            access_pattern: options.access_pattern,
+            remove_on_drop: None,
+            unlinked: None,
+            closed: false,
+            private: false,
+        }))
+    }
+
+    /// Creates an anonymous file that has no path. On Linux, it is created by
+    /// `memfd_create`, so it lives only in the memory (and swap). Elsewhere,
+    /// it is a temporary file that is removed right after it is created.
+    pub fn open_anonymous(len: usize, options: &OpenOptions) -> io::Result<Self> {
+        check_huge_pages(options.huge_pages)?;
+
+        // SAFETY: The file descriptor returned by ffi::create_anonymous is
+        // valid.
+        let fd = unsafe {
+            let fd = ffi::create_anonymous()?;
+            ffi::truncate(fd, len as libc::off_t).cleanup(|| ffi::close(fd))?;
+            fd
+        };
+
+        Ok(Self {
+            fd,
+            len,
+            huge_pages: options.huge_pages,
+            access_pattern: options.access_pattern,
+            remove_on_drop: None,
+            unlinked: None,
+            closed: false,
+            private: false,
+        })
+    }
+
+    /// Opens the file like [`open_readonly`](RawFile::open_readonly), but the
+    /// views can be writable. The modifications are private to the views and
+    /// are never written to the file.
+    pub fn open_private(path: &Path, options: &OpenOptions) -> io::Result<Option<Self>> {
+        Ok(Self::open_readonly(path, options)?.map(|mut raw| {
+            raw.private = true;
+            raw
+        }))
     }
 
     pub fn resize(&mut self, new_len: usize) -> io::Result<()> {
@@ -93,23 +172,192 @@ impl RawFile {
             libc::PROT_READ
         };
 
-        // SAFETY: The file descriptor is valid and len is not zero.
-        let ptr = unsafe { ffi::map(self.fd, len as libc::size_t, off as libc::off_t, prot)? };
+        let sharing = if self.private {
+            libc::MAP_PRIVATE
+        } else {
+            libc::MAP_SHARED
+        };
 
-        Ok(RawView { ptr, off, len })
+        let map = |flags| {
+            // SAFETY: The file descriptor is valid and len is not zero.
+            unsafe {
+                ffi::map(
+                    self.fd,
+                    len as libc::size_t,
+                    off as libc::off_t,
+                    prot,
+                    sharing | flags,
+                )
+            }
+        };
+
+        let ptr = match self.huge_pages {
+            HugePages::Never => map(0)?,
+            // Huge pages are available only under some circumstances (e.g., the
+            // file must be on hugetlbfs), so fall back to normal pages if the
+            // mapping fails.
+            HugePages::Prefer => map(HUGE_PAGES_FLAG).or_else(|_| map(0))?,
+            HugePages::Require => map(HUGE_PAGES_FLAG)?,
+        };
+
+        if let Some(access_pattern) = self.access_pattern {
+            let advice = match access_pattern {
+                AccessPattern::Normal => libc::MADV_NORMAL,
+                AccessPattern::Random => libc::MADV_RANDOM,
+                AccessPattern::Sequential => libc::MADV_SEQUENTIAL,
+            };
+
+            // SAFETY: The pointer and the length come from the successful
+            // mapping above.
+            unsafe {
+                ffi::advise(ptr, len, advice).cleanup(|| ffi::unmap(ptr, len))?;
+            }
+        }
+
+        Ok(RawView {
+            ptr,
+            off,
+            len,
+            writable: writable && !self.private,
+        })
     }
 
     pub fn len(&self) -> usize {
         self.len
     }
+
+    pub fn is_private(&self) -> bool {
+        self.private
+    }
+
+    /// Converts the exclusive lock of a writable file into a shared one, so
+    /// that the file can be opened for reading elsewhere.
+    pub fn downgrade_lock(&self) -> io::Result<()> {
+        // SAFETY: The file descriptor is valid and open for the lifetime of
+        // self. Setting a shared lock over the exclusive one replaces it
+        // atomically.
+        unsafe { ffi::lock(self.fd, self.len as libc::off_t, false) }
+    }
+
+    /// Allocates the storage for the first `len` bytes of the file, which
+    /// must not exceed its length.
+    pub fn preallocate(&self, len: usize) -> io::Result<()> {
+        // SAFETY: The file descriptor is valid.
+        unsafe { ffi::preallocate(self.fd, len) }
+    }
+
+    /// Deallocates the storage of the range, which then reads as zeros. The
+    /// length of the file is not changed.
+    pub fn punch_hole(&self, off: usize, len: usize) -> io::Result<()> {
+        // SAFETY: The file descriptor is valid.
+        unsafe { ffi::punch_hole(self.fd, off, len) }
+    }
+
+    /// Writes the buffers to the file at the offset, bypassing the mappings.
+    /// Returns the number of bytes written, which may be less than the total
+    /// length of the buffers.
+    pub fn write_vectored_at(&self, off: usize, bufs: &[&[u8]]) -> io::Result<usize> {
+        // SAFETY: The file descriptor is valid.
+        unsafe { ffi::write_vectored(self.fd, off, bufs) }
+    }
+
+    /// Advises the kernel to drop the pages of the range from the page cache.
+    /// The range must not be mapped and its data must be already written to
+    /// the storage. On platforms other than Linux, this does nothing.
+    pub fn release(&self, off: usize, len: usize) -> io::Result<()> {
+        // SAFETY: The file descriptor is valid.
+        unsafe { ffi::release(self.fd, off, len) }
+    }
+
+    /// Writes the modified data of the file in the range to the storage. On
+    /// Linux, only the range is written using `sync_file_range`, elsewhere
+    /// the whole file is synchronized.
+    pub fn sync_range(&self, off: usize, len: usize) -> io::Result<()> {
+        // SAFETY: The file descriptor is valid.
+        unsafe { ffi::sync_range(self.fd, off, len) }
+    }
+
+    /// Makes a temporary file persistent and returns its path, or `None` if
+    /// the file is not a temporary.
+    pub fn persist(&mut self) -> io::Result<Option<PathBuf>> {
+        if let Some(path) = self.remove_on_drop.take() {
+            return Ok(Some(pathbuf(path)));
+        }
+
+        if let Some(path) = self.unlinked.take() {
+            // A removed file cannot be linked back to the directory (Linux
+            // allows that only for files opened with O_TMPFILE), so the
+            // contents are copied to a new file instead.
+            let path = pathbuf(path);
+            return self.copy_to(&path).map(|_| Some(path));
+        }
+
+        Ok(None)
+    }
+
+    fn copy_to(&self, path: &Path) -> io::Result<()> {
+        use std::io::{Seek, SeekFrom};
+        use std::os::unix::fs::OpenOptionsExt;
+        use std::os::unix::io::FromRawFd;
+
+        // SAFETY: The file descriptor is valid and it is not closed by the
+        // wrapper, because its drop is suppressed.
+        let src = ManuallyDrop::new(unsafe { std::fs::File::from_raw_fd(self.fd) });
+        let mut src = &*src;
+
+        // Use the same permissions as for the new files opened by us. The
+        // cast is needed on platforms where mode_t is not u32.
+        #[allow(clippy::unnecessary_cast)]
+        let mut dst = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(libc::S_IRUSR as u32)
+            .open(path)?;
+
+        let copied = src
+            .seek(SeekFrom::Start(0))
+            .and_then(|_| io::copy(&mut src, &mut dst))
+            .and_then(|_| dst.sync_all());
+
+        if copied.is_err() {
+            let _ = std::fs::remove_file(path);
+        }
+
+        copied
+    }
+
+    /// Unlocks and closes the file (and removes it if it is a temporary),
+    /// returning the first error. Subsequent calls do nothing.
+    pub fn close(&mut self) -> io::Result<()> {
+        if self.closed {
+            return Ok(());
+        }
+
+        self.closed = true;
+
+        // SAFETY: The file descriptor is valid, because it was not closed yet.
+        unsafe {
+            let unlocked = ffi::unlock(self.fd, self.len as libc::off_t);
+            let closed = ffi::close(self.fd);
+            let removed = match self.remove_on_drop.take() {
+                Some(path) => ffi::remove(&path),
+                None => Ok(()),
+            };
+
+            unlocked.and(closed).and(removed)
+        }
+    }
+
+    pub fn as_fd(&self) -> std::os::unix::io::BorrowedFd<'_> {
+        // SAFETY: The file descriptor is valid and open for the lifetime of
+        // self, it is closed only when dropped.
+        unsafe { std::os::unix::io::BorrowedFd::borrow_raw(self.fd) }
+    }
 }
 
 impl Drop for RawFile {
     fn drop(&mut self) {
-        unsafe {
-            let _ = ffi::unlock(self.fd, self.len as libc::off_t);
-            let _ = ffi::close(self.fd);
-        }
+        let _ = self.close();
     }
 }
 
@@ -117,6 +365,8 @@ pub struct RawView {
     ptr: NonNull<libc::c_void>,
     off: usize,
     len: usize,
+    // Flushing is pointless for read-only and private mappings.
+    writable: bool,
 }
 
 impl RawView {
@@ -133,8 +383,45 @@ impl RawView {
     }
 
     pub fn flush(&self) -> io::Result<()> {
+        if !self.writable {
+            return Ok(());
+        }
+
         unsafe { ffi::sync(self.ptr, self.len) }
     }
+
+    /// Advises the kernel that the whole view will be accessed soon, so it
+    /// can start reading the pages ahead.
+    pub fn advise_willneed(&self) -> io::Result<()> {
+        // SAFETY: The pointer and the length come from a successful mapping.
+        unsafe { ffi::advise(self.ptr, self.len, libc::MADV_WILLNEED) }
+    }
+
+    /// Drops the pages of the range of the view from the memory of the
+    /// process. The mapping stays valid and the pages are read from the file
+    /// again when accessed, so this must not be used for private mappings,
+    /// whose modifications would be lost.
+    pub fn release(&self, off: usize, len: usize) -> io::Result<()> {
+        // The address must be aligned to the page size, the length is rounded
+        // up by the kernel.
+        let start = off - off % get_alignment();
+
+        // SAFETY: The range is within the view (the caller guarantees that)
+        // and the start is aligned to the page size, which the view is
+        // aligned to as well.
+        unsafe {
+            let ptr = NonNull::new_unchecked(self.ptr.as_ptr().cast::<u8>().add(start));
+            ffi::advise(ptr.cast(), off + len - start, libc::MADV_DONTNEED)
+        }
+    }
+
+    /// Unmaps the view, reporting the error that is ignored when dropped.
+    pub fn unmap(self) -> io::Result<()> {
+        let view = ManuallyDrop::new(self);
+        // SAFETY: The pointer and length come from a successful mapping and
+        // the view is not unmapped again, because its drop is suppressed.
+        unsafe { ffi::unmap(view.ptr, view.len) }
+    }
 }
 
 impl Drop for RawView {
@@ -162,11 +449,65 @@ pub fn get_alignment() -> usize {
     }
 }
 
+pub fn get_huge_page_size() -> Option<usize> {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    {
+        // The default huge page size is reported in /proc/meminfo as, e.g.,
+        // "Hugepagesize:       2048 kB".
+        let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+        meminfo
+            .lines()
+            .find_map(|line| line.strip_prefix("Hugepagesize:"))
+            .and_then(|size| size.trim().strip_suffix("kB"))
+            .and_then(|size| size.trim().parse::<usize>().ok())
+            .map(|size| size * 1024)
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    {
+        None
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const HUGE_PAGES_FLAG: libc::c_int = libc::MAP_HUGETLB;
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+const HUGE_PAGES_FLAG: libc::c_int = 0;
+
+pub fn is_no_space(err: &io::Error) -> bool {
+    err.raw_os_error() == Some(libc::ENOSPC)
+}
+
+pub fn exchange_files(a: &Path, b: &Path) -> io::Result<()> {
+    let a = cstr(a)?;
+    let b = cstr(b)?;
+
+    // SAFETY: Both paths are valid null-terminated strings.
+    unsafe { ffi::exchange(&a, &b) }
+}
+
+fn check_huge_pages(huge_pages: HugePages) -> io::Result<()> {
+    if huge_pages == HugePages::Require && HUGE_PAGES_FLAG == 0 {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "huge pages are not supported on this platform",
+        ))
+    } else {
+        Ok(())
+    }
+}
+
 fn cstr(path: &Path) -> io::Result<CString> {
     use std::os::unix::ffi::OsStrExt;
     Ok(CString::new(path.as_os_str().as_bytes())?)
 }
 
+fn pathbuf(path: CString) -> PathBuf {
+    use std::os::unix::ffi::OsStringExt;
+    PathBuf::from(std::ffi::OsString::from_vec(path.into_bytes()))
+}
+
 mod ffi {
     use std::ffi::CStr;
     use std::io;
@@ -202,6 +543,45 @@ mod ffi {
         }
     }
 
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub unsafe fn create_anonymous() -> io::Result<libc::c_int> {
+        // The name is used only for debugging purposes.
+        let name = b"harrow\0";
+        let fd = libc::memfd_create(name.as_ptr().cast(), libc::MFD_CLOEXEC);
+
+        if fd == -1 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(fd)
+        }
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    pub unsafe fn create_anonymous() -> io::Result<libc::c_int> {
+        use std::os::unix::ffi::OsStrExt;
+
+        use crate::ext::ResultExt;
+
+        let mut template = std::env::temp_dir()
+            .join("harrow-XXXXXX")
+            .into_os_string()
+            .as_bytes()
+            .to_vec();
+        template.push(0);
+
+        // The template is replaced by the unique name of the created file.
+        let fd = libc::mkstemp(template.as_mut_ptr().cast());
+
+        if fd == -1 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let path = CStr::from_bytes_with_nul_unchecked(&template);
+        remove(path).cleanup(|| close(fd))?;
+
+        Ok(fd)
+    }
+
     pub unsafe fn remove(path: &CStr) -> io::Result<()> {
         if libc::remove(path.as_ptr()) == -1 {
             Err(io::Error::last_os_error())
@@ -253,8 +633,9 @@ mod ffi {
         len: libc::size_t,
         off: libc::off_t,
         prot: libc::c_int,
+        flags: libc::c_int,
     ) -> io::Result<NonNull<libc::c_void>> {
-        let ptr = libc::mmap(std::ptr::null_mut(), len, prot, libc::MAP_SHARED, fd, off);
+        let ptr = libc::mmap(std::ptr::null_mut(), len, prot, flags, fd, off);
 
         if ptr == libc::MAP_FAILED {
             Err(io::Error::last_os_error())
@@ -271,6 +652,18 @@ mod ffi {
         }
     }
 
+    pub unsafe fn advise(
+        ptr: NonNull<libc::c_void>,
+        len: libc::size_t,
+        advice: libc::c_int,
+    ) -> io::Result<()> {
+        if libc::madvise(ptr.as_ptr(), len, advice) == -1 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
     pub unsafe fn sync(ptr: NonNull<libc::c_void>, len: libc::size_t) -> io::Result<()> {
         if libc::msync(ptr.as_ptr(), len, libc::MS_SYNC) == -1 {
             Err(io::Error::last_os_error())
@@ -278,4 +671,173 @@ mod ffi {
             Ok(())
         }
     }
+
+    #[cfg(target_os = "linux")]
+    pub unsafe fn exchange(a: &CStr, b: &CStr) -> io::Result<()> {
+        // The syscall is used directly, because the wrapper is not available
+        // in all C libraries.
+        let result = libc::syscall(
+            libc::SYS_renameat2,
+            libc::AT_FDCWD,
+            a.as_ptr(),
+            libc::AT_FDCWD,
+            b.as_ptr(),
+            libc::RENAME_EXCHANGE,
+        );
+
+        if result == -1 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    #[cfg(any(target_os = "macos", target_os = "ios"))]
+    pub unsafe fn exchange(a: &CStr, b: &CStr) -> io::Result<()> {
+        if libc::renamex_np(a.as_ptr(), b.as_ptr(), libc::RENAME_SWAP) == -1 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "ios")))]
+    pub unsafe fn exchange(_a: &CStr, _b: &CStr) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "atomic exchange of files is not supported on this platform",
+        ))
+    }
+
+    #[cfg(target_os = "linux")]
+    pub unsafe fn sync_range(fd: libc::c_int, off: usize, len: usize) -> io::Result<()> {
+        let flags = libc::SYNC_FILE_RANGE_WAIT_BEFORE
+            | libc::SYNC_FILE_RANGE_WRITE
+            | libc::SYNC_FILE_RANGE_WAIT_AFTER;
+
+        if libc::sync_file_range(fd, off as _, len as _, flags) == -1 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub unsafe fn sync_range(fd: libc::c_int, _off: usize, _len: usize) -> io::Result<()> {
+        if libc::fsync(fd) == -1 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    pub unsafe fn write_vectored(fd: libc::c_int, off: usize, bufs: &[&[u8]]) -> io::Result<usize> {
+        // The number of buffers per call is limited by IOV_MAX, the rest is
+        // written by subsequent calls.
+        let iovecs = bufs
+            .iter()
+            .take(IOV_MAX)
+            .map(|buf| libc::iovec {
+                iov_base: buf.as_ptr() as *mut libc::c_void,
+                iov_len: buf.len(),
+            })
+            .collect::<Vec<_>>();
+
+        let written = libc::pwritev(
+            fd,
+            iovecs.as_ptr(),
+            iovecs.len() as libc::c_int,
+            off as libc::off_t,
+        );
+
+        if written == -1 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(written as usize)
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    const IOV_MAX: usize = 1024;
+
+    #[cfg(not(target_os = "linux"))]
+    pub unsafe fn write_vectored(fd: libc::c_int, off: usize, bufs: &[&[u8]]) -> io::Result<usize> {
+        // Only the first non-empty buffer is written, the rest is written by
+        // subsequent calls.
+        let buf = match bufs.iter().find(|buf| !buf.is_empty()) {
+            Some(buf) => buf,
+            None => return Ok(0),
+        };
+
+        let written = libc::pwrite(
+            fd,
+            buf.as_ptr() as *const libc::c_void,
+            buf.len(),
+            off as libc::off_t,
+        );
+
+        if written == -1 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(written as usize)
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    pub unsafe fn release(fd: libc::c_int, off: usize, len: usize) -> io::Result<()> {
+        match libc::posix_fadvise(
+            fd,
+            off as libc::off_t,
+            len as libc::off_t,
+            libc::POSIX_FADV_DONTNEED,
+        ) {
+            0 => Ok(()),
+            // The error is returned instead of being set to errno.
+            error => Err(io::Error::from_raw_os_error(error)),
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub unsafe fn release(_fd: libc::c_int, _off: usize, _len: usize) -> io::Result<()> {
+        // Unmapping the blocks already removes the pages from the resident set
+        // of the process, the rest is up to the kernel.
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    pub unsafe fn punch_hole(fd: libc::c_int, off: usize, len: usize) -> io::Result<()> {
+        let mode = libc::FALLOC_FL_PUNCH_HOLE | libc::FALLOC_FL_KEEP_SIZE;
+
+        if libc::fallocate(fd, mode, off as libc::off_t, len as libc::off_t) == -1 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub unsafe fn punch_hole(_fd: libc::c_int, _off: usize, _len: usize) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "punching holes is not supported on this platform",
+        ))
+    }
+
+    #[cfg(target_os = "linux")]
+    pub unsafe fn preallocate(fd: libc::c_int, len: usize) -> io::Result<()> {
+        if libc::fallocate(fd, 0, 0, len as libc::off_t) == -1 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub unsafe fn preallocate(_fd: libc::c_int, _len: usize) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "preallocation is not supported on this platform",
+        ))
+    }
 }
@@ -1,61 +1,84 @@
 use std::io;
-use std::path::Path;
+use std::mem::ManuallyDrop;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use winapi::{
     shared::minwindef::DWORD,
     um::{
         fileapi::{CREATE_NEW, OPEN_EXISTING},
-        memoryapi::{FILE_MAP_ALL_ACCESS, FILE_MAP_READ},
-        winbase::{FILE_FLAG_DELETE_ON_CLOSE, FILE_FLAG_RANDOM_ACCESS},
+        memoryapi::{FILE_MAP_ALL_ACCESS, FILE_MAP_COPY, FILE_MAP_LARGE_PAGES, FILE_MAP_READ},
+        winbase::{FILE_FLAG_DELETE_ON_CLOSE, FILE_FLAG_RANDOM_ACCESS, FILE_FLAG_SEQUENTIAL_SCAN},
         winnt::{
             FILE_ATTRIBUTE_NORMAL, FILE_ATTRIBUTE_READONLY, FILE_ATTRIBUTE_TEMPORARY,
             FILE_SHARE_READ, GENERIC_READ, GENERIC_WRITE, PAGE_READONLY, PAGE_READWRITE,
+            SEC_COMMIT, SEC_LARGE_PAGES,
         },
     },
 };
 
 use crate::ext::ResultExt;
+use crate::options::{AccessPattern, HugePages, OpenOptions, TempStrategy};
 
 pub struct RawFile {
     file_hndl: ffi::RawHandle,
-    map_hndl: ffi::RawHandle,
+    // The mapping is missing only if it could not be recreated after a failed
+    // resize.
+    map_hndl: Option<ffi::RawHandle>,
     len: usize,
     map_protect: DWORD,
+    large_pages: bool,
+    // Path of a temporary file that is deleted when the file handle is closed.
+    temporary: Option<PathBuf>,
+    // Whether the handles were already closed by an explicit call.
+    closed: bool,
+    // Whether the views are mapped as copy-on-write, i.e., the modifications
+    // are never written to the file.
+    private: bool,
 }
 
 impl RawFile {
-    pub fn open_writable(path: &Path, len: usize) -> io::Result<Self> {
+    pub fn open_writable(path: &Path, len: usize, options: &OpenOptions) -> io::Result<Self> {
         let exists = path.exists();
+        let temporary = if !exists && options.temp_strategy != TempStrategy::Keep {
+            Some(path.to_path_buf())
+        } else {
+            None
+        };
         let path = lpcwstr(path);
 
         let desired_access = GENERIC_READ | GENERIC_WRITE;
         let share_mode = 0;
         let creation = if exists { OPEN_EXISTING } else { CREATE_NEW };
-        let mut attributes = FILE_ATTRIBUTE_NORMAL | FILE_FLAG_RANDOM_ACCESS;
-        if !exists {
+        let mut attributes = FILE_ATTRIBUTE_NORMAL | access_flags(options);
+        if temporary.is_some() {
             attributes |= FILE_FLAG_DELETE_ON_CLOSE | FILE_ATTRIBUTE_TEMPORARY;
         }
         let protect = PAGE_READWRITE;
 
-        let (file_hndl, map_hndl) = unsafe {
+        let (file_hndl, map_hndl, protect) = unsafe {
             let file_hndl =
                 ffi::create_file(&path, desired_access, share_mode, creation, attributes)?;
             ffi::resize_file(file_hndl, len).cleanup(|| ffi::close(file_hndl))?;
             // TODO: Lock the file using LockFileEx
-            let map_hndl =
-                ffi::create_mapping(file_hndl, protect).cleanup(|| ffi::close(file_hndl))?;
-            (file_hndl, map_hndl)
+            let (map_hndl, protect) = create_mapping(file_hndl, protect, options.huge_pages)
+                .cleanup(|| ffi::close(file_hndl))?;
+            (file_hndl, map_hndl, protect)
         };
 
         Ok(Self {
             file_hndl,
-            map_hndl,
+            map_hndl: Some(map_hndl),
             len,
             map_protect: protect,
+            large_pages: protect & SEC_LARGE_PAGES != 0,
+            temporary,
+            closed: false,
+            private: false,
         })
     }
 
-    pub fn open_readonly(path: &Path) -> io::Result<Option<Self>> {
+    pub fn open_readonly(path: &Path, options: &OpenOptions) -> io::Result<Option<Self>> {
         let len = path.metadata()?.len() as usize;
 
         if len == 0 {
@@ -67,28 +90,108 @@ impl RawFile {
         let desired_access = GENERIC_READ;
         let share_mode = FILE_SHARE_READ;
         let creation = OPEN_EXISTING;
-        let attributes = FILE_ATTRIBUTE_NORMAL | FILE_FLAG_RANDOM_ACCESS | FILE_ATTRIBUTE_READONLY;
+        let attributes = FILE_ATTRIBUTE_NORMAL | access_flags(options) | FILE_ATTRIBUTE_READONLY;
         let protect = PAGE_READONLY;
 
         let file_hndl =
             unsafe { ffi::create_file(&path, desired_access, share_mode, creation, attributes)? };
         // TODO: Lock the file using LockFileEx
-        let map_hndl =
-            unsafe { ffi::create_mapping(file_hndl, protect).cleanup(|| ffi::close(file_hndl))? };
+        let (map_hndl, protect) = unsafe {
+            create_mapping(file_hndl, protect, options.huge_pages)
+                .cleanup(|| ffi::close(file_hndl))?
+        };
 
         Ok(Some(Self {
             file_hndl,
-            map_hndl,
+            map_hndl: Some(map_hndl),
             len,
             map_protect: protect,
+            large_pages: protect & SEC_LARGE_PAGES != 0,
+            temporary: None,
+            closed: false,
+            private: false,
+        }))
+    }
+
+    /// Creates an anonymous file that has no path visible to the user.
+    pub fn open_anonymous(len: usize, options: &OpenOptions) -> io::Result<Self> {
+        // A mapping backed by the paging file cannot be resized, so a
+        // temporary file with a unique name is used instead. The system keeps
+        // the data of such files in the memory as long as possible and the
+        // file is deleted when the handle is closed.
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "harrow-{}-{}.tmp",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let path = lpcwstr(&path);
+
+        let desired_access = GENERIC_READ | GENERIC_WRITE;
+        let share_mode = 0;
+        let creation = CREATE_NEW;
+        let attributes =
+            FILE_ATTRIBUTE_TEMPORARY | FILE_FLAG_DELETE_ON_CLOSE | access_flags(options);
+        let protect = PAGE_READWRITE;
+
+        let (file_hndl, map_hndl, protect) = unsafe {
+            let file_hndl =
+                ffi::create_file(&path, desired_access, share_mode, creation, attributes)?;
+            ffi::resize_file(file_hndl, len).cleanup(|| ffi::close(file_hndl))?;
+            let (map_hndl, protect) = create_mapping(file_hndl, protect, options.huge_pages)
+                .cleanup(|| ffi::close(file_hndl))?;
+            (file_hndl, map_hndl, protect)
+        };
+
+        Ok(Self {
+            file_hndl,
+            map_hndl: Some(map_hndl),
+            len,
+            map_protect: protect,
+            large_pages: protect & SEC_LARGE_PAGES != 0,
+            // The file is deleted on close regardless, but it has no path that
+            // could be kept.
+            temporary: None,
+            closed: false,
+            private: false,
+        })
+    }
+
+    /// Opens the file like [`open_readonly`](RawFile::open_readonly), but the
+    /// views can be writable. The modifications are private to the views and
+    /// are never written to the file.
+    pub fn open_private(path: &Path, options: &OpenOptions) -> io::Result<Option<Self>> {
+        Ok(Self::open_readonly(path, options)?.map(|mut raw| {
+            raw.private = true;
+            raw
         }))
     }
 
     pub fn resize(&mut self, new_len: usize) -> io::Result<()> {
+        // The mapping must be closed before the file can be resized. If any
+        // step after that fails, the file is restored to the old size and the
+        // mapping is recreated. If even that fails, the buffer is left without
+        // a mapping, so the views fail until a resize succeeds, but the closed
+        // handle is never used again.
+        //
+        // The caller must unmap all views of the mapping beforehand, otherwise
+        // the file cannot be resized.
         unsafe {
-            ffi::close(self.map_hndl)?;
-            ffi::resize_file(self.file_hndl, new_len)?;
-            self.map_hndl = ffi::create_mapping(self.file_hndl, self.map_protect)?;
+            if let Some(map_hndl) = self.map_hndl.take() {
+                ffi::close(map_hndl)?;
+            }
+
+            let result = ffi::resize_file(self.file_hndl, new_len)
+                .and_then(|_| ffi::create_mapping(self.file_hndl, self.map_protect));
+
+            match result {
+                Ok(map_hndl) => self.map_hndl = Some(map_hndl),
+                Err(err) => {
+                    let _ = ffi::resize_file(self.file_hndl, self.len);
+                    self.map_hndl = ffi::create_mapping(self.file_hndl, self.map_protect).ok();
+                    return Err(err);
+                }
+            }
         }
 
         self.len = new_len;
@@ -97,36 +200,152 @@ impl RawFile {
     }
 
     pub fn view(&self, off: usize, len: usize, writable: bool) -> io::Result<RawView> {
-        let desired_access = if writable {
+        let mut desired_access = if writable && self.private {
+            // The mapping object is read-only, which is fine for copy-on-write
+            // views.
+            FILE_MAP_COPY
+        } else if writable {
             FILE_MAP_ALL_ACCESS
         } else {
             FILE_MAP_READ
         };
 
-        let ptr = unsafe { ffi::map_view(self.map_hndl, desired_access, len, off)? };
+        if self.large_pages {
+            desired_access |= FILE_MAP_LARGE_PAGES;
+        }
+
+        let map_hndl = self.map_hndl.ok_or_else(|| {
+            io::Error::other("the file mapping could not be recreated after a failed resize")
+        })?;
 
-        Ok(RawView { ptr, off, len })
+        let ptr = unsafe { ffi::map_view(map_hndl, desired_access, len, off)? };
+
+        Ok(RawView {
+            ptr,
+            off,
+            len,
+            writable: writable && !self.private,
+        })
     }
 
     pub fn len(&self) -> usize {
         self.len
     }
-}
 
-impl Drop for RawFile {
-    fn drop(&mut self) {
+    pub fn is_private(&self) -> bool {
+        self.private
+    }
+
+    /// Converts the exclusive lock of a writable file into a shared one, so
+    /// that the file can be opened for reading elsewhere.
+    pub fn downgrade_lock(&self) -> io::Result<()> {
+        // The file is not locked on Windows.
+        Ok(())
+    }
+
+    /// Allocates the storage for the first `len` bytes of the file, which
+    /// must not exceed its length.
+    pub fn preallocate(&self, len: usize) -> io::Result<()> {
+        // SAFETY: The file handle is valid.
+        unsafe { ffi::allocate_file(self.file_hndl, len) }
+    }
+
+    /// Writes the buffers to the file at the offset, bypassing the mappings.
+    /// Returns the number of bytes written, which may be less than the total
+    /// length of the buffers.
+    pub fn write_vectored_at(&self, off: usize, bufs: &[&[u8]]) -> io::Result<usize> {
+        // WriteFileGather requires page-sized buffers and unbuffered access,
+        // so only the first non-empty buffer is written, the rest is written
+        // by subsequent calls.
+        match bufs.iter().find(|buf| !buf.is_empty()) {
+            // SAFETY: The file handle is valid.
+            Some(buf) => unsafe { ffi::write_at(self.file_hndl, off, buf) },
+            None => Ok(0),
+        }
+    }
+
+    /// Advises the system to drop the pages of the range from the memory. The
+    /// range must not be mapped and its data must be already written to the
+    /// storage.
+    pub fn release(&self, _off: usize, _len: usize) -> io::Result<()> {
+        // Unmapping the views already removes the pages from the working set
+        // of the process. There is no way to drop the pages of a file range
+        // from the standby list, which is managed by the system.
+        Ok(())
+    }
+
+    /// Deallocates the storage of the range, which then reads as zeros. The
+    /// length of the file is not changed.
+    pub fn punch_hole(&self, off: usize, len: usize) -> io::Result<()> {
+        // SAFETY: The file handle is valid.
+        unsafe {
+            // Only sparse files can have their storage deallocated, otherwise
+            // the range is just zeroed.
+            ffi::set_sparse(self.file_hndl)?;
+            ffi::set_zero_data(self.file_hndl, off, len)
+        }
+    }
+
+    /// Writes the modified data of the file to the storage. Windows cannot
+    /// synchronize only a range, so the whole file is synchronized.
+    pub fn sync_range(&self, _off: usize, _len: usize) -> io::Result<()> {
+        // SAFETY: The file handle is valid.
+        unsafe { ffi::flush_file(self.file_hndl) }
+    }
+
+    pub fn as_handle(&self) -> std::os::windows::io::BorrowedHandle<'_> {
+        // SAFETY: The file handle is valid and open for the lifetime of self,
+        // it is closed only when dropped.
+        unsafe { std::os::windows::io::BorrowedHandle::borrow_raw(self.file_hndl.as_ptr() as _) }
+    }
+
+    /// Makes a temporary file persistent and returns its path, or `None` if
+    /// the file is not a temporary.
+    pub fn persist(&mut self) -> io::Result<Option<PathBuf>> {
+        if self.temporary.is_none() {
+            return Ok(None);
+        }
+
+        // SAFETY: The file handle is valid.
+        unsafe { ffi::keep_on_close(self.file_hndl)? };
+
+        Ok(self.temporary.take())
+    }
+
+    /// Closes the mapping and the file (which deletes it if it is a
+    /// temporary), returning the first error. Subsequent calls do nothing.
+    pub fn close(&mut self) -> io::Result<()> {
+        if self.closed {
+            return Ok(());
+        }
+
+        self.closed = true;
+
+        // SAFETY: The handles are valid, because they were not closed yet.
         unsafe {
             // The order does not really matter.
-            let _ = ffi::close(self.map_hndl);
-            let _ = ffi::close(self.file_hndl);
+            let map_closed = self
+                .map_hndl
+                .take()
+                .map_or(Ok(()), |map_hndl| ffi::close(map_hndl));
+            let file_closed = ffi::close(self.file_hndl);
+            map_closed.and(file_closed)
         }
     }
 }
 
+impl Drop for RawFile {
+    fn drop(&mut self) {
+        let _ = self.close();
+    }
+}
+
 pub struct RawView {
     ptr: ffi::RawPtr,
     off: usize,
     len: usize,
+    // Flushing is pointless for read-only and private mappings.
+    writable: bool,
 }
 
 impl RawView {
@@ -143,8 +362,38 @@ impl RawView {
     }
 
     pub fn flush(&self) -> io::Result<()> {
+        if !self.writable {
+            return Ok(());
+        }
+
         unsafe { ffi::flush_view(self.ptr, self.len) }
     }
+
+    /// Advises the system that the whole view will be accessed soon, so it
+    /// can start reading the pages ahead.
+    pub fn advise_willneed(&self) -> io::Result<()> {
+        // SAFETY: The pointer and the length come from a successful mapping.
+        unsafe { ffi::prefetch(self.ptr, self.len) }
+    }
+
+    /// Removes the pages of the range of the view from the working set of the
+    /// process. The mapping stays valid and the pages are read from the file
+    /// again when accessed (if the system dropped them in the meantime).
+    pub fn release(&self, off: usize, len: usize) -> io::Result<()> {
+        // SAFETY: The range is within the view (the caller guarantees that).
+        unsafe {
+            let ptr = self.ptr.as_ptr().cast::<u8>().add(off);
+            ffi::unlock_pages(ptr.cast(), len)
+        }
+    }
+
+    /// Unmaps the view, reporting the error that is ignored when dropped.
+    pub fn unmap(self) -> io::Result<()> {
+        let view = ManuallyDrop::new(self);
+        // SAFETY: The pointer comes from a successful mapping and the view is
+        // not unmapped again, because its drop is suppressed.
+        unsafe { ffi::unmap_view(view.ptr) }
+    }
 }
 
 impl Drop for RawView {
@@ -164,6 +413,73 @@ pub fn get_alignment() -> usize {
     system_info.dwAllocationGranularity as usize
 }
 
+pub fn get_huge_page_size() -> Option<usize> {
+    // SAFETY: A simple call to the function without any arguments.
+    let size = unsafe { winapi::um::memoryapi::GetLargePageMinimum() };
+
+    // Zero means that the processor does not support large pages.
+    if size == 0 {
+        None
+    } else {
+        Some(size)
+    }
+}
+
+pub fn is_no_space(err: &io::Error) -> bool {
+    use winapi::shared::winerror::{ERROR_DISK_FULL, ERROR_HANDLE_DISK_FULL};
+    matches!(
+        err.raw_os_error().map(|code| code as DWORD),
+        Some(ERROR_DISK_FULL) | Some(ERROR_HANDLE_DISK_FULL)
+    )
+}
+
+/// Creates the mapping object with large pages if requested. Returns the
+/// mapping handle together with the protection that was actually used.
+unsafe fn create_mapping(
+    file_hndl: ffi::RawHandle,
+    protect: DWORD,
+    huge_pages: HugePages,
+) -> io::Result<(ffi::RawHandle, DWORD)> {
+    // Large pages must be committed up front.
+    let large_protect = protect | SEC_COMMIT | SEC_LARGE_PAGES;
+
+    match huge_pages {
+        HugePages::Never => ffi::create_mapping(file_hndl, protect).map(|hndl| (hndl, protect)),
+        // Large pages require SeLockMemoryPrivilege (and are not supported for
+        // all kinds of files), so fall back to normal pages if the mapping
+        // fails.
+        HugePages::Prefer => ffi::create_mapping(file_hndl, large_protect)
+            .map(|hndl| (hndl, large_protect))
+            .or_else(|_| ffi::create_mapping(file_hndl, protect).map(|hndl| (hndl, protect))),
+        HugePages::Require => {
+            ffi::create_mapping(file_hndl, large_protect).map(|hndl| (hndl, large_protect))
+        }
+    }
+}
+
+pub fn exchange_files(a: &Path, b: &Path) -> io::Result<()> {
+    // Windows has no primitive for exchanging two files. ReplaceFileW moves a
+    // to b atomically and keeps the original b as a backup, which is then
+    // moved to a.
+    let mut backup = b.as_os_str().to_owned();
+    backup.push(".harrow-swap");
+    let backup = Path::new(&backup);
+
+    unsafe {
+        ffi::replace_file(&lpcwstr(b), &lpcwstr(a), &lpcwstr(backup))?;
+        ffi::move_file(&lpcwstr(backup), &lpcwstr(a))
+    }
+}
+
+fn access_flags(options: &OpenOptions) -> DWORD {
+    match options.access_pattern {
+        // Random access is the default for backwards compatibility.
+        None | Some(AccessPattern::Random) => FILE_FLAG_RANDOM_ACCESS,
+        Some(AccessPattern::Sequential) => FILE_FLAG_SEQUENTIAL_SCAN,
+        Some(AccessPattern::Normal) => 0,
+    }
+}
+
 fn lpcwstr(path: &Path) -> Vec<u16> {
     use std::os::windows::ffi::OsStrExt;
     let mut wstr = path.as_os_str().encode_wide().collect::<Vec<_>>();
@@ -178,10 +494,22 @@ mod ffi {
     use winapi::{
         shared::{basetsd::SIZE_T, minwindef::DWORD, ntdef::LONGLONG},
         um::{
-            fileapi::{CreateFileW, SetFileInformationByHandle, FILE_END_OF_FILE_INFO},
+            fileapi::{
+                CreateFileW, FlushFileBuffers, SetFileInformationByHandle, WriteFile,
+                FILE_ALLOCATION_INFO, FILE_END_OF_FILE_INFO,
+            },
             handleapi::{CloseHandle, INVALID_HANDLE_VALUE},
-            memoryapi::{CreateFileMappingW, FlushViewOfFile, MapViewOfFile, UnmapViewOfFile},
-            minwinbase::FileEndOfFileInfo,
+            ioapiset::DeviceIoControl,
+            memoryapi::{
+                CreateFileMappingW, FlushViewOfFile, MapViewOfFile, PrefetchVirtualMemory,
+                UnmapViewOfFile, VirtualUnlock, WIN32_MEMORY_RANGE_ENTRY,
+            },
+            minwinbase::{
+                FileAllocationInfo, FileDispositionInfoEx, FileEndOfFileInfo, OVERLAPPED,
+            },
+            processthreadsapi::GetCurrentProcess,
+            winbase::{MoveFileExW, ReplaceFileW},
+            winioctl::{FSCTL_SET_SPARSE, FSCTL_SET_ZERO_DATA},
             winnt::WCHAR,
         },
     };
@@ -231,6 +559,85 @@ mod ffi {
         }
     }
 
+    // Not provided by winapi.
+    #[allow(non_snake_case)]
+    #[repr(C)]
+    struct FILE_DISPOSITION_INFO_EX {
+        Flags: DWORD,
+    }
+
+    const FILE_DISPOSITION_FLAG_ON_CLOSE: DWORD = 0x0000_0008;
+
+    pub unsafe fn keep_on_close(hndl: RawHandle) -> io::Result<()> {
+        // Setting FILE_DISPOSITION_INFO { DeleteFile: FALSE } has no effect on
+        // files opened with FILE_FLAG_DELETE_ON_CLOSE. The extended variant
+        // clears the delete-on-close state when FILE_DISPOSITION_FLAG_DELETE
+        // is not set, but it requires Windows 10 1809 or later.
+        let mut info = FILE_DISPOSITION_INFO_EX {
+            Flags: FILE_DISPOSITION_FLAG_ON_CLOSE,
+        };
+
+        let result = SetFileInformationByHandle(
+            hndl.as_ptr(),
+            FileDispositionInfoEx,
+            &mut info as *mut _ as *mut _,
+            std::mem::size_of::<FILE_DISPOSITION_INFO_EX>() as DWORD,
+        );
+
+        if result == 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    pub unsafe fn prefetch(ptr: RawPtr, len: usize) -> io::Result<()> {
+        let mut entry = WIN32_MEMORY_RANGE_ENTRY {
+            VirtualAddress: ptr.as_ptr(),
+            NumberOfBytes: len as SIZE_T,
+        };
+
+        // Requires Windows 8 or later.
+        if PrefetchVirtualMemory(GetCurrentProcess(), 1, &mut entry, 0) == 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    pub unsafe fn unlock_pages(ptr: *mut winapi::ctypes::c_void, len: usize) -> io::Result<()> {
+        use winapi::shared::winerror::ERROR_NOT_LOCKED;
+
+        // Unlocking pages that are not locked removes them from the working
+        // set, which is what we want, but it is reported as an error.
+        if VirtualUnlock(ptr, len as SIZE_T) == 0 {
+            let error = io::Error::last_os_error();
+            if error.raw_os_error() != Some(ERROR_NOT_LOCKED as i32) {
+                return Err(error);
+            }
+        }
+
+        Ok(())
+    }
+
+    pub unsafe fn allocate_file(hndl: RawHandle, len: usize) -> io::Result<()> {
+        let mut info = FILE_ALLOCATION_INFO::default();
+        *info.AllocationSize.QuadPart_mut() = len as LONGLONG;
+
+        let result = SetFileInformationByHandle(
+            hndl.as_ptr(),
+            FileAllocationInfo,
+            &mut info as *mut _ as *mut _,
+            std::mem::size_of::<FILE_ALLOCATION_INFO>() as DWORD,
+        );
+
+        if result == 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
     pub unsafe fn create_mapping(hndl: RawHandle, protect: DWORD) -> io::Result<RawHandle> {
         // Passing 0,0 to maximum size arguments make the mapping the same size
         // as is the size of the file.
@@ -287,4 +694,118 @@ mod ffi {
             Ok(())
         }
     }
+
+    pub unsafe fn replace_file(
+        replaced: &[WCHAR],
+        replacement: &[WCHAR],
+        backup: &[WCHAR],
+    ) -> io::Result<()> {
+        let result = ReplaceFileW(
+            replaced.as_ptr(),
+            replacement.as_ptr(),
+            backup.as_ptr(),
+            0,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        );
+
+        if result == 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    pub unsafe fn move_file(existing: &[WCHAR], new: &[WCHAR]) -> io::Result<()> {
+        if MoveFileExW(existing.as_ptr(), new.as_ptr(), 0) == 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    pub unsafe fn write_at(hndl: RawHandle, off: usize, buf: &[u8]) -> io::Result<usize> {
+        // The offset of a synchronous write is given by the overlapped
+        // structure.
+        let mut overlapped: OVERLAPPED = std::mem::zeroed();
+        {
+            let s = overlapped.u.s_mut();
+            s.Offset = off as u64 as DWORD;
+            s.OffsetHigh = ((off as u64) >> 32) as DWORD;
+        }
+
+        let len = std::cmp::min(buf.len(), DWORD::MAX as usize) as DWORD;
+        let mut written: DWORD = 0;
+
+        if WriteFile(
+            hndl.as_ptr(),
+            buf.as_ptr() as *const _,
+            len,
+            &mut written,
+            &mut overlapped,
+        ) == 0
+        {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(written as usize)
+        }
+    }
+
+    pub unsafe fn flush_file(hndl: RawHandle) -> io::Result<()> {
+        if FlushFileBuffers(hndl.as_ptr()) == 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    // FILE_ZERO_DATA_INFORMATION, which is not defined in winapi.
+    #[repr(C)]
+    struct ZeroDataInformation {
+        file_offset: LONGLONG,
+        beyond_final_zero: LONGLONG,
+    }
+
+    pub unsafe fn set_sparse(hndl: RawHandle) -> io::Result<()> {
+        device_io_control(hndl, FSCTL_SET_SPARSE, std::ptr::null_mut(), 0)
+    }
+
+    pub unsafe fn set_zero_data(hndl: RawHandle, off: usize, len: usize) -> io::Result<()> {
+        let mut info = ZeroDataInformation {
+            file_offset: off as LONGLONG,
+            beyond_final_zero: (off + len) as LONGLONG,
+        };
+
+        device_io_control(
+            hndl,
+            FSCTL_SET_ZERO_DATA,
+            &mut info as *mut _ as *mut _,
+            std::mem::size_of::<ZeroDataInformation>() as DWORD,
+        )
+    }
+
+    unsafe fn device_io_control(
+        hndl: RawHandle,
+        code: DWORD,
+        input: *mut winapi::ctypes::c_void,
+        input_size: DWORD,
+    ) -> io::Result<()> {
+        let mut returned = 0;
+        let result = DeviceIoControl(
+            hndl.as_ptr(),
+            code,
+            input,
+            input_size,
+            std::ptr::null_mut(),
+            0,
+            &mut returned,
+            std::ptr::null_mut(),
+        );
+
+        if result == 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
 }
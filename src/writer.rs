@@ -0,0 +1,110 @@
+//! Implementation of the standard writing trait.
+
+use std::io::{self, Write};
+
+use crate::FileMut;
+
+/// A writing cursor over the underlying file.
+///
+/// It implements [`Write`]. The writes go directly to the cached blocks, so
+/// there is no need for wrapping it in [`BufWriter`](std::io::BufWriter).
+///
+/// When a write would exceed the length of the buffer, the buffer is grown
+/// using [`resize`](FileMut::resize) to double its length, or to the end of
+/// the write if that is more. Doubling keeps the number of resizes
+/// logarithmic for a sequence of small writes. Note that the length is then
+/// aligned, so the tail of the buffer after the last written byte is just
+/// padding; use [`position`](Writer::position) to get the logical length of
+/// the data.
+///
+/// This struct is created by [`FileMut::writer`](crate::FileMut::writer).
+pub struct Writer<'a> {
+    file: &'a mut FileMut,
+    pos: usize,
+}
+
+impl<'a> Writer<'a> {
+    pub(crate) fn from_file(file: &'a mut FileMut) -> Self {
+        Self { file, pos: 0 }
+    }
+
+    /// Returns the current position of the cursor.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+}
+
+impl Write for Writer<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        // Write at most up to the end of the current block, so the view does
+        // not span multiple blocks.
+        let block_size = self.file.0.cache_block_size();
+        let count = std::cmp::min(buf.len(), block_size - self.pos % block_size);
+
+        let end = self.pos.checked_add(count).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "write position overflow")
+        })?;
+
+        let len = self.file.len();
+        if end > len {
+            self.file
+                .resize(std::cmp::max(end, len.saturating_mul(2)))?;
+        }
+
+        self.file.write_at(&buf[..count], self.pos)?;
+        self.pos = end;
+
+        Ok(count)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use crate::{os, FileMut, OpenOptions};
+
+    #[test]
+    fn write_grows() {
+        let alignment = os::get_alignment();
+        let mut file = FileMut::with_options(
+            "writer_grows.tmp",
+            alignment,
+            OpenOptions::new().cache_block_size(alignment),
+        )
+        .unwrap();
+
+        let data = (0..3 * alignment + 10)
+            .map(|i| (i % 251) as u8)
+            .collect::<Vec<_>>();
+
+        {
+            let mut writer = file.writer();
+            writer.write_all(&data[..10]).unwrap();
+            assert_eq!(writer.position(), 10);
+
+            // Crosses the end of the buffer, so it is resized in the middle.
+            writer.write_all(&data[10..]).unwrap();
+            assert_eq!(writer.position(), data.len());
+            writer.flush().unwrap();
+        }
+
+        // Doubled twice.
+        assert_eq!(file.len(), 4 * alignment);
+        assert_eq!(file.read_vec(0, data.len()).unwrap(), data);
+
+        let mut writer = file.writer();
+        assert_eq!(writer.write(&[]).unwrap(), 0);
+        writer.write_all(b"overwritten").unwrap();
+        assert_eq!(file.read_vec(0, 11).unwrap(), b"overwritten");
+        assert_eq!(file.len(), 4 * alignment);
+    }
+}